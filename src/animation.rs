@@ -0,0 +1,184 @@
+use std::{
+    ops::{Add, Deref, Mul},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// How values are interpolated between keyframes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    /// Straight lines between keyframes.
+    #[default]
+    Linear,
+    /// A Catmull-Rom spline through the keyframes, which changes direction smoothly.
+    Spline,
+}
+
+/// The keyframes that contribute to a value together with their weights, at most four of them.
+///
+/// Stored inline, as it is computed for every ray hitting an animated object.
+#[derive(Debug, Clone, Copy)]
+pub struct KeyframeWeights {
+    weights: [(usize, f64); 4],
+    len: usize,
+}
+
+impl KeyframeWeights {
+    fn new<const N: usize>(weights: [(usize, f64); N]) -> Self {
+        let mut all = [(0, 0.0); 4];
+        all[..N].copy_from_slice(&weights);
+        Self {
+            weights: all,
+            len: N,
+        }
+    }
+}
+
+impl Deref for KeyframeWeights {
+    type Target = [(usize, f64)];
+
+    fn deref(&self) -> &Self::Target {
+        &self.weights[..self.len]
+    }
+}
+
+/// Returns the keyframes that contribute to the value at `time` together with their weights.
+///
+/// `times` are the increasing times of the keyframes. Before the first and after the last
+/// keyframe the value of that keyframe is used.
+pub fn keyframe_weights(times: &[f64], time: f64, interpolation: Interpolation) -> KeyframeWeights {
+    let next = times.partition_point(|&t| t <= time);
+    if next == 0 {
+        return KeyframeWeights::new([(0, 1.0)]);
+    }
+    if next == times.len() {
+        return KeyframeWeights::new([(next - 1, 1.0)]);
+    }
+
+    let (i1, i2) = (next - 1, next);
+    let duration = times[i2] - times[i1];
+    let t = (time - times[i1]) / duration;
+
+    match interpolation {
+        Interpolation::Linear => KeyframeWeights::new([(i1, 1.0 - t), (i2, t)]),
+        Interpolation::Spline => {
+            // Cubic Hermite basis with the tangent at every keyframe estimated from its
+            // neighbours, which makes the result a weighted sum of four keyframes.
+            let (t2, t3) = (t * t, t * t * t);
+            let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+            let h10 = t3 - 2.0 * t2 + t;
+            let h01 = -2.0 * t3 + 3.0 * t2;
+            let h11 = t3 - t2;
+
+            // At the ends the missing neighbour is replaced by the keyframe itself.
+            let i0 = i1.saturating_sub(1);
+            let i3 = (i2 + 1).min(times.len() - 1);
+            let start_tangent = h10 * duration / (times[i2] - times[i0]);
+            let end_tangent = h11 * duration / (times[i3] - times[i1]);
+
+            KeyframeWeights::new([
+                (i0, -start_tangent),
+                (i1, h00 - end_tangent),
+                (i2, h01 + start_tangent),
+                (i3, end_tangent),
+            ])
+        }
+    }
+}
+
+/// Interpolates the values of keyframes given as `(time, value)` pairs at `time`.
+pub fn interpolate<T>(keyframes: &[(f64, T)], time: f64, interpolation: Interpolation) -> T
+where
+    T: Copy + Add<Output = T> + Mul<f64, Output = T>,
+{
+    let times: Vec<f64> = keyframes.iter().map(|(t, _)| *t).collect();
+    let weights = keyframe_weights(&times, time, interpolation);
+
+    let (first, weight) = weights[0];
+    weights[1..]
+        .iter()
+        .fold(keyframes[first].1 * weight, |sum, &(i, weight)| {
+            sum + keyframes[i].1 * weight
+        })
+}
+
+/// An inclusive range of frame numbers, written as `first-last` or as a single frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRange {
+    pub first: u32,
+    pub last: u32,
+}
+
+impl FromStr for FrameRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |frame: &str| {
+            frame
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| format!("`{frame}` is not a frame number"))
+        };
+        let range = match s.split_once('-') {
+            Some((first, last)) => FrameRange {
+                first: parse(first)?,
+                last: parse(last)?,
+            },
+            None => {
+                let frame = parse(s)?;
+                FrameRange {
+                    first: frame,
+                    last: frame,
+                }
+            }
+        };
+        if range.last < range.first {
+            return Err(format!(
+                "the last frame {} comes before the first frame {}",
+                range.last, range.first
+            ));
+        }
+        Ok(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{interpolate, FrameRange, Interpolation};
+
+    #[test]
+    fn interpolation_passes_through_keyframes() {
+        let keyframes = [(1.0, 0.0), (2.0, 3.0), (4.0, -1.0), (5.0, 2.0)];
+
+        for interpolation in [Interpolation::Linear, Interpolation::Spline] {
+            for (time, value) in keyframes {
+                let actual = interpolate(&keyframes, time, interpolation);
+                assert!((actual - value).abs() < 1e-12);
+            }
+            assert_eq!(interpolate(&keyframes, 0.0, interpolation), 0.0);
+            assert_eq!(interpolate(&keyframes, 9.0, interpolation), 2.0);
+        }
+
+        assert!((interpolate(&keyframes, 3.0, Interpolation::Linear) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn spline_between_two_keyframes_is_linear() {
+        let keyframes = [(0.0, 1.0), (4.0, 5.0)];
+        for i in 0..=8 {
+            let time = i as f64 / 2.0;
+            let spline = interpolate(&keyframes, time, Interpolation::Spline);
+            assert!((spline - (1.0 + time)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn parses_frame_ranges() {
+        assert_eq!("1-48".parse(), Ok(FrameRange { first: 1, last: 48 }));
+        assert_eq!("7".parse(), Ok(FrameRange { first: 7, last: 7 }));
+        assert!("5-2".parse::<FrameRange>().is_err());
+        assert!("1-x".parse::<FrameRange>().is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    materials::material::MaterialRef,
+    objects::{bezier_patch::BezierPatch, hittable::Hittable},
+    vec3::Vec3,
+};
+
+use super::ImportError;
+
+/// Loads the Bézier patches of the BPT file at `path`, such as those of the Utah teapot.
+pub fn load_bpt(path: &Path, material: MaterialRef) -> Result<Vec<Arc<dyn Hittable>>, ImportError> {
+    let source = std::fs::read_to_string(path).map_err(|err| ImportError::Io {
+        path: path.to_owned(),
+        err,
+    })?;
+
+    Ok(parse_bpt(&source, path)?
+        .into_iter()
+        .map(|points| Arc::new(BezierPatch::new(points, material)) as Arc<dyn Hittable>)
+        .collect())
+}
+
+/// Parses the control points of the patches in a BPT file.
+///
+/// The file starts with the number of patches. Each patch starts with its degrees along
+/// both parameters, which must be 3 for bicubic patches, followed by its 16 control points.
+pub fn parse_bpt(source: &str, path: &Path) -> Result<Vec<[Vec3; 16]>, ImportError> {
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+    let mut last_line = 0;
+    let mut next_numbers = |count: usize, what: &str| -> Result<(usize, Vec<f64>), ImportError> {
+        let Some((line, text)) = lines.next() else {
+            return Err(ImportError::parse(
+                path,
+                last_line,
+                format!("unexpected end of file, expected {what}"),
+            ));
+        };
+        last_line = line;
+
+        let numbers: Vec<f64> = text
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| ImportError::parse(path, line, format!("expected {what}")))?;
+        if numbers.len() != count {
+            return Err(ImportError::parse(path, line, format!("expected {what}")));
+        }
+        Ok((line, numbers))
+    };
+
+    let (_, count) = next_numbers(1, "the number of patches")?;
+    let mut patches = vec![];
+    for _ in 0..count[0] as usize {
+        let (line, degrees) = next_numbers(2, "the degrees of a patch")?;
+        if degrees != [3.0, 3.0] {
+            return Err(ImportError::parse(
+                path,
+                line,
+                "only bicubic patches of degree 3 are supported",
+            ));
+        }
+
+        let mut points = [Vec3(0.0, 0.0, 0.0); 16];
+        for point in &mut points {
+            let (_, p) = next_numbers(3, "a control point")?;
+            *point = Vec3(p[0], p[1], p[2]);
+        }
+        patches.push(points);
+    }
+    Ok(patches)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::import::ImportError;
+
+    use super::parse_bpt;
+
+    #[test]
+    fn parses_patches() {
+        let mut source = "1\n3 3\n".to_owned();
+        for i in 0..16 {
+            source += &format!("{} 0 {}\n", i % 4, i / 4);
+        }
+        let patches = parse_bpt(&source, Path::new("test.bpt")).unwrap();
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0][6].x(), 2.0);
+        assert_eq!(patches[0][6].z(), 1.0);
+
+        match parse_bpt("1\n2 3\n", Path::new("test.bpt")) {
+            Err(ImportError::Parse { line, .. }) => assert_eq!(line, 2),
+            _ => panic!("expected parse error"),
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use ::gltf::{
+    camera::Projection,
+    image::{Data as ImageData, Format},
+    material::AlphaMode,
+    mesh::Mode,
+    Node,
+};
+use image::{DynamicImage, RgbImage};
+
+use crate::{
+    materials::{
+        material::{Dialectric, DiffuseLight, Lambertian, Material, MaterialRef, Metal},
+        normal_map::{NormalMap, SurfaceDetail},
+        texture::{ImageTexture, SolidColor, TexCoord, Texture},
+    },
+    matrix::Matrix4,
+    objects::{
+        hittable::Hittable,
+        instance::Instance,
+        mesh::{MeshData, TriangleMesh},
+        subdivision::{PolygonMesh, Subdivision},
+        world::World,
+    },
+    scenes::scene::Settings,
+    util::rad2deg,
+    vec3::{Color, Vec3},
+};
+
+use super::ImportError;
+
+/// A camera of a glTF file, converted to the parameters used by `Camera::new`.
+pub struct GltfCamera {
+    pub eye: Vec3,
+    pub target: Vec3,
+    pub up: Vec3,
+    /// Vertical field of view in degrees.
+    pub fov: f64,
+    pub aspect_ratio: Option<f64>,
+}
+
+impl GltfCamera {
+    /// Replaces the camera settings with this camera. If the camera has a fixed
+    /// aspect ratio the width of the image is adjusted to match it.
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.camera_eye = self.eye;
+        settings.camera_target = self.target;
+        settings.camera_up = self.up;
+        settings.fov = self.fov;
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            settings.width = (settings.height as f64 * aspect_ratio).round() as u32;
+        }
+    }
+}
+
+pub struct GltfScene {
+    pub objects: Vec<Arc<dyn Hittable>>,
+    /// Cameras in the order they are found while traversing the node hierarchy.
+    pub cameras: Vec<GltfCamera>,
+}
+
+/// Loads the default scene (or the first scene) of the glTF or GLB file at `path`.
+///
+/// Every mesh is loaded once, with a triangle mesh for each of its primitives, and placed by
+/// an instance for every node that uses it. Materials are registered in `world` unless `material_override` is given.
+/// Buffers and images are read from the file itself, data URIs or files next to it.
+/// With a `subdivision`, each primitive is subdivided on its own.
+pub fn load_gltf(
+    path: &Path,
+    world: &mut World,
+    material_override: Option<MaterialRef>,
+    subdivision: Option<Subdivision>,
+) -> Result<GltfScene, ImportError> {
+    let (document, buffers, images) = ::gltf::import(path).map_err(|err| ImportError::Gltf {
+        path: path.to_owned(),
+        err,
+    })?;
+
+    let Some(scene) = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    else {
+        return Err(ImportError::invalid(path, "file does not contain a scene"));
+    };
+
+    let mut importer = GltfImporter {
+        path,
+        world,
+        material_override,
+        subdivision,
+        buffers: &buffers,
+        images: &images,
+        materials: HashMap::new(),
+        meshes: HashMap::new(),
+        scene: GltfScene {
+            objects: vec![],
+            cameras: vec![],
+        },
+    };
+
+    for node in scene.nodes() {
+        importer.node(&node, Matrix4::IDENTITY)?;
+    }
+
+    if importer.scene.objects.is_empty() {
+        return Err(ImportError::invalid(
+            path,
+            "scene does not contain any triangles",
+        ));
+    }
+
+    Ok(importer.scene)
+}
+
+struct GltfImporter<'a> {
+    path: &'a Path,
+    world: &'a mut World,
+    material_override: Option<MaterialRef>,
+    subdivision: Option<Subdivision>,
+    buffers: &'a [::gltf::buffer::Data],
+    images: &'a [ImageData],
+    /// Registered materials by glTF material index, `None` for the default material.
+    materials: HashMap<Option<usize>, MaterialRef>,
+    /// Loaded meshes by glTF mesh index, `None` for meshes without triangles.
+    meshes: HashMap<usize, Option<Arc<dyn Hittable>>>,
+    scene: GltfScene,
+}
+
+impl<'a> GltfImporter<'a> {
+    fn node(&mut self, node: &Node, parent_transform: Matrix4) -> Result<(), ImportError> {
+        let local = node
+            .transform()
+            .matrix()
+            .map(|column| column.map(f64::from));
+        let transform = parent_transform * Matrix4::from_columns(local);
+
+        if let Some(camera) = node.camera() {
+            if let Projection::Perspective(perspective) = camera.projection() {
+                let eye = transform.transform_point(Vec3(0.0, 0.0, 0.0));
+                let forward = transform.transform_vector(Vec3(0.0, 0.0, -1.0)).normalize();
+                self.scene.cameras.push(GltfCamera {
+                    eye,
+                    target: eye + forward,
+                    up: transform.transform_vector(Vec3(0.0, 1.0, 0.0)).normalize(),
+                    fov: rad2deg(perspective.yfov() as f64),
+                    aspect_ratio: perspective.aspect_ratio().map(f64::from),
+                });
+            }
+        }
+
+        if let Some(mesh) = node.mesh() {
+            if let Some(geometry) = self.mesh(&mesh)? {
+                if transform.inverse().is_none() {
+                    return Err(ImportError::invalid(
+                        self.path,
+                        "node transform is singular",
+                    ));
+                }
+                self.scene
+                    .objects
+                    .push(Arc::new(Instance::new(geometry, transform)));
+            }
+        }
+
+        for child in node.children() {
+            self.node(&child, transform)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the object made of the primitives of `mesh`, which is loaded once and shared
+    /// by all nodes using the mesh, or `None` if the mesh has no triangles.
+    fn mesh(&mut self, mesh: &::gltf::Mesh) -> Result<Option<Arc<dyn Hittable>>, ImportError> {
+        if let Some(geometry) = self.meshes.get(&mesh.index()) {
+            return Ok(geometry.clone());
+        }
+
+        let mut primitives = vec![];
+        for primitive in mesh.primitives() {
+            if let Some(object) = self.primitive(&primitive)? {
+                primitives.push(object);
+            }
+        }
+        let geometry = match primitives.len() {
+            0 => None,
+            1 => primitives.pop(),
+            _ => Some(Arc::new(self.world.object_bvh(&primitives)) as Arc<dyn Hittable>),
+        };
+
+        self.meshes.insert(mesh.index(), geometry.clone());
+        Ok(geometry)
+    }
+
+    /// Loads a primitive as a triangle mesh, or returns `None` if it has no triangles.
+    fn primitive(
+        &mut self,
+        primitive: &::gltf::Primitive,
+    ) -> Result<Option<Arc<dyn Hittable>>, ImportError> {
+        let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+
+        let Some(positions) = reader.read_positions() else {
+            return Ok(None);
+        };
+        let positions: Vec<Vec3> = positions
+            .map(|[x, y, z]| Vec3(x as f64, y as f64, z as f64))
+            .collect();
+
+        let vertices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        let indices: Vec<[u32; 3]> = match primitive.mode() {
+            Mode::Triangles => vertices
+                .chunks_exact(3)
+                .map(|t| [t[0], t[1], t[2]])
+                .collect(),
+            Mode::TriangleStrip => (2..vertices.len())
+                .map(|i| {
+                    // Every other triangle of a strip is flipped to keep the winding consistent.
+                    if i % 2 == 0 {
+                        [vertices[i - 2], vertices[i - 1], vertices[i]]
+                    } else {
+                        [vertices[i - 1], vertices[i - 2], vertices[i]]
+                    }
+                })
+                .collect(),
+            Mode::TriangleFan => (2..vertices.len())
+                .map(|i| [vertices[0], vertices[i - 1], vertices[i]])
+                .collect(),
+            // Points and lines have no surface that could be hit.
+            _ => return Ok(None),
+        };
+        if indices.is_empty() {
+            return Ok(None);
+        }
+
+        let mut data = MeshData::new(positions, indices);
+
+        if let Some(normals) = reader.read_normals() {
+            data.normals = normals
+                .map(|[x, y, z]| Vec3(x as f64, y as f64, z as f64).normalize())
+                .collect();
+        }
+
+        if let Some(uvs) = reader.read_tex_coords(0) {
+            // glTF places the origin of texture coordinates in the top left corner.
+            data.uvs = uvs
+                .into_f32()
+                .map(|[u, v]| TexCoord::new(u as f64, 1.0 - v as f64))
+                .collect();
+        }
+
+        data.validate()
+            .map_err(|err| ImportError::invalid(self.path, err))?;
+        // glTF only stores triangles, so unlike OBJ and PLY faces there are no quads to keep.
+        if let Some(subdivision) = self.subdivision {
+            data = PolygonMesh::from_triangles(&data).subdivide(subdivision);
+        }
+
+        let material = match self.material_override {
+            Some(material) => material,
+            None => self.material(&primitive.material())?,
+        };
+
+        let (mesh, stats) = TriangleMesh::build(Arc::new(data), material, self.world.bvh_builder());
+        self.world.add_bvh_stats(stats);
+
+        Ok(Some(Arc::new(mesh)))
+    }
+
+    /// Maps a metallic-roughness material to the closest material supported by the raytracer:
+    ///  - emissive materials become diffuse lights,
+    ///  - transmissive or blended materials become dielectrics,
+    ///  - mostly metallic materials become metals with the roughness as fuzziness,
+    ///  - everything else becomes lambertian.
+    ///
+    /// Metals and lambertians are colored by the base color texture, or the base color factor
+    /// if there is none. All but lights keep the normal texture as a normal map.
+    fn material(&mut self, material: &::gltf::Material) -> Result<MaterialRef, ImportError> {
+        if let Some(&material) = self.materials.get(&material.index()) {
+            return Ok(material);
+        }
+
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, _] = pbr.base_color_factor();
+        let base_color = Color::new(r as f64, g as f64, b as f64);
+
+        let [r, g, b] = material.emissive_factor();
+        let emissive = Color::new(r as f64, g as f64, b as f64)
+            * material.emissive_strength().unwrap_or(1.0) as f64;
+
+        let transmission = material
+            .transmission()
+            .map_or(0.0, |t| t.transmission_factor());
+
+        let surface_detail = SurfaceDetail {
+            normal_map: match material.normal_texture() {
+                Some(info) => {
+                    let image = self.image(&info.texture())?;
+                    Some(NormalMap::new(DynamicImage::ImageRgb8(image).into_rgb16()))
+                }
+                None => None,
+            },
+            bump_map: None,
+        };
+
+        let converted: Box<dyn Material> = if !emissive.near_zero() {
+            Box::new(DiffuseLight::from_color(emissive))
+        } else if transmission > 0.5 || material.alpha_mode() == AlphaMode::Blend {
+            Box::new(
+                Dialectric::new(material.ior().unwrap_or(1.5) as f64)
+                    .with_surface_detail(surface_detail),
+            )
+        } else if pbr.metallic_factor() > 0.5 {
+            let texture = self.base_color(&pbr, base_color)?;
+            Box::new(
+                Metal::new(texture, pbr.roughness_factor() as f64)
+                    .with_surface_detail(surface_detail),
+            )
+        } else {
+            let texture = self.base_color(&pbr, base_color)?;
+            Box::new(Lambertian::new(texture).with_surface_detail(surface_detail))
+        };
+
+        let material_ref = self.world.register_material(converted);
+        self.materials.insert(material.index(), material_ref);
+
+        Ok(material_ref)
+    }
+
+    /// Returns the base color texture of a material, or its base color factor if it has none.
+    fn base_color(
+        &self,
+        pbr: &::gltf::material::PbrMetallicRoughness,
+        factor: Color,
+    ) -> Result<Arc<dyn Texture>, ImportError> {
+        Ok(match pbr.base_color_texture() {
+            Some(info) => Arc::new(ImageTexture::new(self.image(&info.texture())?)),
+            None => Arc::new(SolidColor { color: factor }),
+        })
+    }
+
+    fn image(&self, texture: &::gltf::Texture) -> Result<RgbImage, ImportError> {
+        let image = &self.images[texture.source().index()];
+        to_rgb_image(image).ok_or_else(|| {
+            ImportError::invalid(
+                self.path,
+                format!("unsupported image format {:?}", image.format),
+            )
+        })
+    }
+}
+
+/// Converts 8-bit images to RGB. Grayscale images are expanded and alpha is dropped.
+fn to_rgb_image(image: &ImageData) -> Option<RgbImage> {
+    let channels = match image.format {
+        Format::R8 => 1,
+        Format::R8G8 => 2,
+        Format::R8G8B8 => 3,
+        Format::R8G8B8A8 => 4,
+        _ => return None,
+    };
+
+    let pixels = image
+        .pixels
+        .chunks_exact(channels)
+        .flat_map(|p| {
+            if channels < 3 {
+                [p[0]; 3]
+            } else {
+                [p[0], p[1], p[2]]
+            }
+        })
+        .collect();
+
+    RgbImage::from_raw(image.width, image.height, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use image::{Rgb, RgbImage};
+
+    use crate::{
+        objects::{bvh::BvhBuilder, world::World},
+        ray::Ray,
+        scene_file::exporter::export,
+        scenes::scene::Settings,
+        util::Interval,
+        vec3::{Color, Vec3},
+    };
+
+    use super::load_gltf;
+
+    /// Writes a glTF file with the given nodes and materials next to a buffer holding a
+    /// triangle with the corners (0, 0, 0), (1, 0, 0) and (0, 1, 0) as mesh 0.
+    fn write_triangle(name: &str, nodes: &str, materials: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("raytracer_rs_gltf_{name}"));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let positions: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let bytes: Vec<u8> = positions.iter().flat_map(|p| p.to_le_bytes()).collect();
+        std::fs::write(dir.join("triangle.bin"), bytes).unwrap();
+
+        let material = if materials.is_empty() {
+            ""
+        } else {
+            r#", "material": 0"#
+        };
+        let path = dir.join("triangle.gltf");
+        std::fs::write(
+            &path,
+            format!(
+                r#"{{
+                    "asset": {{ "version": "2.0" }},
+                    "scene": 0,
+                    {nodes},
+                    "cameras": [{{ "type": "perspective", "perspective": {{ "yfov": 0.5, "znear": 0.1 }} }}],
+                    "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}{material} }}] }}],
+                    {materials}
+                    "accessors": [{{
+                        "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                        "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+                    }}],
+                    "bufferViews": [{{ "buffer": 0, "byteLength": 36 }}],
+                    "buffers": [{{ "uri": "triangle.bin", "byteLength": 36 }}]
+                }}"#
+            ),
+        )
+        .unwrap();
+        path
+    }
+
+    #[test]
+    fn applies_node_transforms() {
+        let path = write_triangle(
+            "transforms",
+            r#""scenes": [{ "nodes": [0, 2] }],
+            "nodes": [
+                { "translation": [0.0, 0.0, -5.0], "children": [1] },
+                { "mesh": 0, "scale": [2.0, 2.0, 2.0] },
+                { "camera": 0, "translation": [0.0, 0.0, 3.0] }
+            ]"#,
+            "",
+        );
+
+        let mut world = World::new(BvhBuilder::default());
+        let scene = load_gltf(&path, &mut world, None, None).unwrap();
+        assert_eq!(scene.objects.len(), 1);
+        assert_eq!(world.materials.len(), 1);
+
+        let camera = &scene.cameras[0];
+        assert!((camera.eye - Vec3(0.0, 0.0, 3.0)).near_zero());
+        assert!((camera.target - Vec3(0.0, 0.0, 2.0)).near_zero());
+
+        // The triangle is scaled by two and moved away from the camera.
+        let ray = Ray::new(Vec3(1.5, 0.25, 0.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = scene.objects[0]
+            .hit(&ray, Interval(0.001, f64::INFINITY))
+            .unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn shares_meshes_between_nodes() {
+        let path = write_triangle(
+            "shared",
+            r#""scenes": [{ "nodes": [0, 1] }],
+            "nodes": [
+                { "mesh": 0, "translation": [-2.0, 0.0, 0.0] },
+                { "mesh": 0, "translation": [2.0, 0.0, 0.0] }
+            ]"#,
+            "",
+        );
+
+        let mut world = World::new(BvhBuilder::default());
+        let scene = load_gltf(&path, &mut world, None, None).unwrap();
+        assert_eq!(scene.objects.len(), 2);
+        world.set_objects(scene.objects);
+
+        let description = export(Settings::default(), &world, None).unwrap();
+        assert_eq!(description.geometry.len(), 1);
+    }
+
+    #[test]
+    fn metals_use_base_color_texture() {
+        let path = write_triangle(
+            "metal",
+            r#""scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }]"#,
+            r#""materials": [{
+                "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 }, "roughnessFactor": 0.0 }
+            }],
+            "textures": [{ "source": 0 }],
+            "images": [{ "uri": "red.png" }],"#,
+        );
+        RgbImage::from_pixel(1, 1, Rgb([255, 0, 0]))
+            .save(path.with_file_name("red.png"))
+            .unwrap();
+
+        let mut world = World::new(BvhBuilder::default());
+        let scene = load_gltf(&path, &mut world, None, None).unwrap();
+
+        let ray = Ray::new(Vec3(0.25, 0.25, 1.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = scene.objects[0]
+            .hit(&ray, Interval(0.001, f64::INFINITY))
+            .unwrap();
+        let scattered = world.materials[rec.material.0].scatter(&ray, &rec).unwrap();
+        assert!((scattered.attenuation - Color::new(1.0, 0.0, 0.0)).near_zero());
+        // Without roughness the metal is a perfect mirror.
+        assert!((scattered.ray.direction - Vec3(0.0, 0.0, 1.0)).near_zero());
+    }
+}
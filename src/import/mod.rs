@@ -0,0 +1,77 @@
+use std::{fmt, path::PathBuf};
+
+use crate::vec3::{Color, Vec3};
+
+pub mod bpt;
+pub mod gltf;
+pub mod obj;
+pub mod ply;
+
+/// Color of imported faces without a material.
+pub const DEFAULT_COLOR: Color = Vec3(0.8, 0.8, 0.8);
+
+#[derive(Debug)]
+pub enum ImportError {
+    /// A file could not be read.
+    Io { path: PathBuf, err: std::io::Error },
+
+    /// A file contains invalid data.
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+
+    /// A glTF file or one of its buffers or images could not be loaded.
+    Gltf { path: PathBuf, err: ::gltf::Error },
+
+    /// A file is well-formed but contains data that cannot be imported.
+    Invalid { path: PathBuf, message: String },
+
+    /// An image referenced by a material could not be loaded.
+    Texture {
+        path: PathBuf,
+        err: image::ImageError,
+    },
+}
+
+impl ImportError {
+    pub fn parse(path: impl Into<PathBuf>, line: usize, message: impl Into<String>) -> Self {
+        Self::Parse {
+            path: path.into(),
+            line,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid(path: impl Into<PathBuf>, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io { path, err } => {
+                write!(f, "failed to read `{}`: {err}", path.display())
+            }
+            ImportError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", path.display()),
+            ImportError::Gltf { path, err } => {
+                write!(f, "failed to load `{}`: {err}", path.display())
+            }
+            ImportError::Invalid { path, message } => write!(f, "{}: {message}", path.display()),
+            ImportError::Texture { path, err } => {
+                write!(f, "failed to load texture `{}`: {err}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
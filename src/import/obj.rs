@@ -0,0 +1,578 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    materials::{
+        material::{Dialectric, DiffuseLight, Lambertian, Material, MaterialRef, Metal},
+        normal_map::{BumpMap, NormalMap, SurfaceDetail},
+        texture::{ImageTexture, TexCoord},
+    },
+    objects::{
+        hittable::Hittable,
+        mesh::{MeshData, TriangleMesh},
+        subdivision::{PolygonMesh, Subdivision},
+        world::World,
+    },
+    vec3::{Color, Vec3},
+};
+
+use super::{ImportError, DEFAULT_COLOR};
+
+/// Loads the Wavefront OBJ file at `path` as triangle meshes, one for every material used in it.
+///
+/// Materials are read from the MTL files referenced by the OBJ file and registered in `world`,
+/// unless `material_override` is given, in which case it is used for all faces.
+/// Quads and other polygons are triangulated as fans, or after the subdivision if one is given.
+pub fn load_obj(
+    path: &Path,
+    world: &mut World,
+    material_override: Option<MaterialRef>,
+    subdivision: Option<Subdivision>,
+) -> Result<Vec<Arc<dyn Hittable>>, ImportError> {
+    let obj = parse_obj(&read(path)?, path)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let mut mtl_materials = HashMap::new();
+    if material_override.is_none() {
+        for library in &obj.material_libraries {
+            let mtl_path = base_dir.join(library);
+            mtl_materials.extend(parse_mtl(&read(&mtl_path)?, &mtl_path)?);
+        }
+    }
+
+    let mut registered: HashMap<Option<String>, MaterialRef> = HashMap::new();
+    let mut objects: Vec<Arc<dyn Hittable>> = vec![];
+
+    for group in &obj.groups {
+        if group.faces.is_empty() {
+            continue;
+        }
+
+        let material = match material_override {
+            Some(material) => material,
+            None => match registered.get(&group.material) {
+                Some(&material) => material,
+                None => {
+                    let material = match &group.material {
+                        Some(name) => {
+                            let Some(mtl) = mtl_materials.get(name) else {
+                                return Err(ImportError::parse(
+                                    path,
+                                    group.line,
+                                    format!("unknown material `{name}`"),
+                                ));
+                            };
+                            mtl.to_material()?
+                        }
+                        None => Box::new(Lambertian::from_color(DEFAULT_COLOR)),
+                    };
+                    let material = world.register_material(material);
+                    registered.insert(group.material.clone(), material);
+                    material
+                }
+            },
+        };
+
+        let data = match subdivision {
+            Some(subdivision) => group.to_polygon_mesh(&obj).subdivide(subdivision),
+            None => group.to_mesh_data(&obj),
+        };
+        let (mesh, stats) = TriangleMesh::build(Arc::new(data), material, world.bvh_builder());
+        world.add_bvh_stats(stats);
+        objects.push(Arc::new(mesh));
+    }
+
+    if objects.is_empty() {
+        return Err(ImportError::parse(
+            path,
+            1,
+            "file does not contain any faces",
+        ));
+    }
+
+    Ok(objects)
+}
+
+fn read(path: &Path) -> Result<String, ImportError> {
+    std::fs::read_to_string(path).map_err(|err| ImportError::Io {
+        path: path.to_owned(),
+        err,
+    })
+}
+
+/// The contents of an OBJ file. Faces keep their polygons so that they
+/// can be processed further before being triangulated.
+pub struct ObjFile {
+    pub positions: Vec<Vec3>,
+    pub uvs: Vec<TexCoord>,
+    pub normals: Vec<Vec3>,
+    pub material_libraries: Vec<PathBuf>,
+    pub groups: Vec<FaceGroup>,
+}
+
+/// All faces of an OBJ file that use the same material.
+pub struct FaceGroup {
+    pub material: Option<String>,
+    /// Line of the first `usemtl` statement selecting the material.
+    pub line: usize,
+    pub faces: Vec<Vec<FaceVertex>>,
+}
+
+/// Zero-based indices of the attributes of a face corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FaceVertex {
+    pub position: usize,
+    pub uv: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+impl FaceGroup {
+    /// Returns the distinct vertices used by this group, and its faces as indices into them.
+    fn vertices(&self) -> (Vec<FaceVertex>, Vec<Vec<u32>>) {
+        let mut vertex_indices: HashMap<FaceVertex, u32> = HashMap::new();
+        let mut vertices = vec![];
+        let faces = self
+            .faces
+            .iter()
+            .map(|face| {
+                face.iter()
+                    .map(|vertex| {
+                        *vertex_indices.entry(*vertex).or_insert_with(|| {
+                            vertices.push(*vertex);
+                            vertices.len() as u32 - 1
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        (vertices, faces)
+    }
+
+    /// Collects the vertices used by this group into a mesh, triangulating polygons as fans.
+    ///
+    /// Texture coordinates and normals are only kept if every vertex of the group has them.
+    pub fn to_mesh_data(&self, obj: &ObjFile) -> MeshData {
+        let (vertices, faces) = self.vertices();
+        let mut indices = vec![];
+        for face in faces {
+            for i in 1..face.len() - 1 {
+                indices.push([face[0], face[i], face[i + 1]]);
+            }
+        }
+
+        let mut data = MeshData::new(
+            vertices.iter().map(|v| obj.positions[v.position]).collect(),
+            indices,
+        );
+        if vertices.iter().all(|v| v.uv.is_some()) {
+            data.uvs = vertices.iter().map(|v| obj.uvs[v.uv.unwrap()]).collect();
+        }
+        if vertices.iter().all(|v| v.normal.is_some()) {
+            data.normals = vertices
+                .iter()
+                .map(|v| obj.normals[v.normal.unwrap()])
+                .collect();
+        }
+
+        data
+    }
+
+    /// Collects the vertices used by this group into a mesh that keeps its polygons for
+    /// subdivision. Normals are left out, as subdivision computes its own.
+    pub fn to_polygon_mesh(&self, obj: &ObjFile) -> PolygonMesh {
+        let (vertices, faces) = self.vertices();
+        let uvs = if vertices.iter().all(|v| v.uv.is_some()) {
+            vertices.iter().map(|v| obj.uvs[v.uv.unwrap()]).collect()
+        } else {
+            vec![]
+        };
+
+        PolygonMesh {
+            positions: vertices.iter().map(|v| obj.positions[v.position]).collect(),
+            uvs,
+            colors: vec![],
+            faces,
+        }
+    }
+}
+
+pub fn parse_obj(source: &str, path: &Path) -> Result<ObjFile, ImportError> {
+    let mut obj = ObjFile {
+        positions: vec![],
+        uvs: vec![],
+        normals: vec![],
+        material_libraries: vec![],
+        groups: vec![FaceGroup {
+            material: None,
+            line: 1,
+            faces: vec![],
+        }],
+    };
+    let mut current_group = 0;
+
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let error = |message: String| ImportError::parse(path, line_number, message);
+
+        let line = line.split('#').next().unwrap();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let tokens: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => obj.positions.push(parse_vec3(&tokens).map_err(error)?),
+            "vn" => obj.normals.push(parse_vec3(&tokens).map_err(error)?),
+            "vt" => {
+                let uv = parse_floats(&tokens, 1).map_err(error)?;
+                obj.uvs
+                    .push(TexCoord::new(uv[0], uv.get(1).copied().unwrap_or(0.0)));
+            }
+            "f" => {
+                if tokens.len() < 3 {
+                    return Err(error("face must have at least three vertices".to_owned()));
+                }
+                let face = tokens
+                    .iter()
+                    .map(|token| parse_face_vertex(token, &obj))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+                obj.groups[current_group].faces.push(face);
+            }
+            "usemtl" => {
+                let name = tokens.join(" ");
+                current_group = match obj
+                    .groups
+                    .iter()
+                    .position(|g| g.material.as_deref() == Some(&name))
+                {
+                    Some(group) => group,
+                    None => {
+                        obj.groups.push(FaceGroup {
+                            material: Some(name),
+                            line: line_number,
+                            faces: vec![],
+                        });
+                        obj.groups.len() - 1
+                    }
+                };
+            }
+            "mtllib" => obj
+                .material_libraries
+                .extend(tokens.iter().map(PathBuf::from)),
+            // Object and group names, smoothing groups, lines and points are not needed.
+            _ => {}
+        }
+    }
+
+    Ok(obj)
+}
+
+fn parse_floats(tokens: &[&str], min_count: usize) -> Result<Vec<f64>, String> {
+    if tokens.len() < min_count {
+        return Err(format!("expected at least {min_count} numbers"));
+    }
+    tokens
+        .iter()
+        .map(|token| {
+            token
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number `{token}`"))
+        })
+        .collect()
+}
+
+fn parse_vec3(tokens: &[&str]) -> Result<Vec3, String> {
+    let v = parse_floats(tokens, 3)?;
+    Ok(Vec3(v[0], v[1], v[2]))
+}
+
+/// Parses a face corner of the form `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+fn parse_face_vertex(token: &str, obj: &ObjFile) -> Result<FaceVertex, String> {
+    let mut parts = token.split('/');
+
+    let position = parse_index(parts.next(), obj.positions.len(), "vertex", token)?
+        .ok_or_else(|| format!("face vertex `{token}` has no position"))?;
+    let uv = parse_index(parts.next(), obj.uvs.len(), "texture coordinate", token)?;
+    let normal = parse_index(parts.next(), obj.normals.len(), "normal", token)?;
+
+    Ok(FaceVertex {
+        position,
+        uv,
+        normal,
+    })
+}
+
+/// Converts a one-based (or negative, relative) OBJ index into a zero-based index.
+fn parse_index(
+    part: Option<&str>,
+    count: usize,
+    kind: &str,
+    token: &str,
+) -> Result<Option<usize>, String> {
+    let Some(part) = part.filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+
+    let index: i64 = part
+        .parse()
+        .map_err(|_| format!("invalid face vertex `{token}`"))?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!("{kind} index {index} is out of range"));
+    }
+
+    Ok(Some(resolved as usize))
+}
+
+/// A material from an MTL file.
+#[derive(Default)]
+struct MtlMaterial {
+    name: String,
+    diffuse: Option<Color>,
+    specular: Option<Color>,
+    emissive: Option<Color>,
+    shininess: Option<f64>,
+    optical_density: Option<f64>,
+    dissolve: Option<f64>,
+    illumination_model: Option<u32>,
+    diffuse_map: Option<PathBuf>,
+    normal_map: Option<PathBuf>,
+    bump_map: Option<PathBuf>,
+    bump_multiplier: Option<f64>,
+}
+
+impl MtlMaterial {
+    /// Maps the material to the closest material supported by the raytracer:
+    ///  - emissive materials (`Ke`) become diffuse lights,
+    ///  - transparent materials (`d` < 1 or a refractive `illum`) become dielectrics with index `Ni`,
+    ///  - reflective materials (a reflective `illum` or only `Ks`) become metals with `Ks` as albedo
+    ///    and a fuzziness derived from the specular exponent `Ns`,
+    ///  - everything else becomes lambertian using `map_Kd` or `Kd`.
+    ///
+    /// All but lights keep the normal map `norm` and the bump map `map_Bump`.
+    fn to_material(&self) -> Result<Box<dyn Material>, ImportError> {
+        let is_black = |c: Option<Color>| c.is_none_or(|c| c.near_zero());
+        let illum = self.illumination_model.unwrap_or(2);
+
+        if !is_black(self.emissive) {
+            return Ok(Box::new(DiffuseLight::from_color(self.emissive.unwrap())));
+        }
+
+        let surface_detail = self.surface_detail()?;
+        if self.dissolve.is_some_and(|d| d < 1.0) || matches!(illum, 4 | 6 | 7 | 9) {
+            return Ok(Box::new(
+                Dialectric::new(self.optical_density.unwrap_or(1.5))
+                    .with_surface_detail(surface_detail),
+            ));
+        }
+
+        if !is_black(self.specular)
+            && (matches!(illum, 3 | 5 | 8)
+                || (is_black(self.diffuse) && self.diffuse_map.is_none()))
+        {
+            let shininess = self.shininess.unwrap_or(0.0).max(0.0);
+            let fuzziness = (2.0 / (shininess + 2.0)).sqrt();
+            return Ok(Box::new(
+                Metal::from_color(self.specular.unwrap(), fuzziness)
+                    .with_surface_detail(surface_detail),
+            ));
+        }
+
+        if let Some(path) = &self.diffuse_map {
+            let texture = ImageTexture::open(path).map_err(|err| ImportError::Texture {
+                path: path.clone(),
+                err,
+            })?;
+            return Ok(Box::new(
+                Lambertian::new(Arc::new(texture)).with_surface_detail(surface_detail),
+            ));
+        }
+
+        Ok(Box::new(
+            Lambertian::from_color(self.diffuse.unwrap_or(DEFAULT_COLOR))
+                .with_surface_detail(surface_detail),
+        ))
+    }
+
+    fn surface_detail(&self) -> Result<SurfaceDetail, ImportError> {
+        let texture_error = |path: &PathBuf| {
+            let path = path.clone();
+            move |err| ImportError::Texture { path, err }
+        };
+
+        let normal_map = match &self.normal_map {
+            Some(path) => Some(NormalMap::open(path).map_err(texture_error(path))?),
+            None => None,
+        };
+        let bump_map = match &self.bump_map {
+            Some(path) => {
+                let strength = self.bump_multiplier.unwrap_or(1.0);
+                Some(BumpMap::open(path, strength).map_err(texture_error(path))?)
+            }
+            None => None,
+        };
+
+        Ok(SurfaceDetail {
+            normal_map,
+            bump_map,
+        })
+    }
+}
+
+fn parse_mtl(source: &str, path: &Path) -> Result<HashMap<String, MtlMaterial>, ImportError> {
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut materials = HashMap::new();
+    let mut current: Option<MtlMaterial> = None;
+
+    for (i, line) in source.lines().enumerate() {
+        let error = |message: String| ImportError::parse(path, i + 1, message);
+
+        let line = line.split('#').next().unwrap();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let tokens: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            if let Some(material) = current.take() {
+                materials.insert(material.name.clone(), material);
+            }
+            current = Some(MtlMaterial {
+                name: tokens.join(" "),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some(material) = current.as_mut() else {
+            return Err(error(format!("`{keyword}` before the first `newmtl`")));
+        };
+
+        let single = |tokens: &[&str]| parse_floats(tokens, 1).map(|v| v[0]);
+
+        match keyword {
+            "Kd" => material.diffuse = Some(parse_vec3(&tokens).map_err(error)?),
+            "Ks" => material.specular = Some(parse_vec3(&tokens).map_err(error)?),
+            "Ke" => material.emissive = Some(parse_vec3(&tokens).map_err(error)?),
+            "Ns" => material.shininess = Some(single(&tokens).map_err(error)?),
+            "Ni" => material.optical_density = Some(single(&tokens).map_err(error)?),
+            "d" => material.dissolve = Some(single(&tokens).map_err(error)?),
+            "Tr" => material.dissolve = Some(1.0 - single(&tokens).map_err(error)?),
+            "illum" => {
+                material.illumination_model = Some(
+                    tokens
+                        .first()
+                        .and_then(|t| t.parse().ok())
+                        .ok_or_else(|| error("invalid illumination model".to_owned()))?,
+                )
+            }
+            "map_Kd" => {
+                // Texture options such as `-s 1 1 1` precede the file name.
+                let file = tokens
+                    .last()
+                    .ok_or_else(|| error("missing texture file name".to_owned()))?;
+                material.diffuse_map = Some(base_dir.join(file));
+            }
+            "norm" => {
+                let file = tokens
+                    .last()
+                    .ok_or_else(|| error("missing normal map file name".to_owned()))?;
+                material.normal_map = Some(base_dir.join(file));
+            }
+            "map_Bump" | "map_bump" | "bump" => {
+                let file = tokens
+                    .last()
+                    .ok_or_else(|| error("missing bump map file name".to_owned()))?;
+                material.bump_map = Some(base_dir.join(file));
+                // The only option that matters here is the bump multiplier, `-bm <value>`.
+                if let Some(i) = tokens.iter().position(|&t| t == "-bm") {
+                    let multiplier = tokens
+                        .get(i + 1)
+                        .and_then(|t| t.parse().ok())
+                        .ok_or_else(|| error("invalid bump multiplier".to_owned()))?;
+                    material.bump_multiplier = Some(multiplier);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(material) = current.take() {
+        materials.insert(material.name.clone(), material);
+    }
+
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{
+        import::ImportError,
+        objects::{bvh::BvhBuilder, world::World},
+    };
+
+    use super::{load_obj, parse_obj};
+
+    #[test]
+    fn triangulates_polygons() {
+        let source = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 2 0\n\
+                      vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+                      f 1/1 2/2 3/3 4/4\n\
+                      f -5 -2 -1\n";
+        let obj = parse_obj(source, Path::new("test.obj")).unwrap();
+        assert_eq!(obj.groups.len(), 1);
+
+        let data = obj.groups[0].to_mesh_data(&obj);
+        assert_eq!(data.indices.len(), 3);
+        // The triangle without texture coordinates means none are kept.
+        assert!(data.uvs.is_empty());
+        assert!(data.validate().is_ok());
+    }
+
+    #[test]
+    fn reports_out_of_range_index() {
+        let source = "v 0 0 0\nv 1 0 0\nf 1 2 3\n";
+        match parse_obj(source, Path::new("test.obj")) {
+            Err(ImportError::Parse { line, message, .. }) => {
+                assert_eq!(line, 3);
+                assert_eq!(message, "vertex index 3 is out of range");
+            }
+            _ => panic!("expected parse error"),
+        }
+    }
+
+    #[test]
+    fn reports_missing_texture() {
+        let dir = std::env::temp_dir().join("raytracer_rs_obj_missing_texture");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("model.obj"),
+            "mtllib model.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl textured\nf 1 2 3\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("model.mtl"),
+            "newmtl textured\nKd 1 1 1\nmap_Kd missing.png\n",
+        )
+        .unwrap();
+
+        let mut world = World::new(BvhBuilder::default());
+        match load_obj(&dir.join("model.obj"), &mut world, None, None) {
+            Err(ImportError::Texture { path, .. }) => assert!(path.ends_with("missing.png")),
+            _ => panic!("expected texture error"),
+        }
+    }
+}
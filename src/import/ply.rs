@@ -0,0 +1,710 @@
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    materials::{
+        material::{Lambertian, MaterialRef},
+        texture::{TexCoord, VertexColorTexture},
+    },
+    objects::{
+        hittable::Hittable,
+        mesh::{MeshData, TriangleMesh},
+        point_cloud::{PointCloud, PointCloudData, PointShape},
+        subdivision::{PolygonMesh, Subdivision},
+        world::World,
+    },
+    vec3::{Color, Vec3},
+};
+
+use super::{ImportError, DEFAULT_COLOR};
+
+/// Loads the PLY file at `path` as a triangle mesh.
+///
+/// ASCII and binary little-endian files are supported. Unless `material_override` is given,
+/// the mesh gets a diffuse material colored by the vertex colors of the file, or a gray
+/// one if it has none. Polygons are triangulated as fans, unless the mesh is subdivided,
+/// which starts from the polygons themselves.
+pub fn load_ply(
+    path: &Path,
+    world: &mut World,
+    material_override: Option<MaterialRef>,
+    subdivision: Option<Subdivision>,
+) -> Result<Arc<dyn Hittable>, ImportError> {
+    let bytes = std::fs::read(path).map_err(|err| ImportError::Io {
+        path: path.to_owned(),
+        err,
+    })?;
+    let (mut data, faces) = parse_ply(&bytes, path)?;
+    if let Some(subdivision) = subdivision {
+        let polygons = PolygonMesh {
+            positions: data.positions,
+            uvs: data.uvs,
+            colors: data.colors,
+            faces,
+        };
+        data = polygons.subdivide(subdivision);
+    }
+
+    let material = match material_override {
+        Some(material) => material,
+        None => default_material(world, !data.colors.is_empty()),
+    };
+
+    let (mesh, stats) = TriangleMesh::build(Arc::new(data), material, world.bvh_builder());
+    world.add_bvh_stats(stats);
+    Ok(Arc::new(mesh))
+}
+
+/// Loads the vertices of the PLY file at `path` with their normals and colors as a point
+/// cloud, e.g. of a LiDAR scan. Faces are ignored.
+///
+/// Unless `material_override` is given, the points get a diffuse material colored by their
+/// colors, or a gray one if they have none.
+pub fn load_ply_points(
+    path: &Path,
+    world: &mut World,
+    material_override: Option<MaterialRef>,
+    radius: f64,
+    shape: PointShape,
+) -> Result<Arc<dyn Hittable>, ImportError> {
+    let bytes = std::fs::read(path).map_err(|err| ImportError::Io {
+        path: path.to_owned(),
+        err,
+    })?;
+    let (mesh, _) = parse_elements(&bytes, path)?;
+    let data = PointCloudData {
+        positions: mesh.positions,
+        normals: mesh.normals,
+        colors: mesh.colors,
+    };
+    let material = match material_override {
+        Some(material) => material,
+        None => default_material(world, !data.colors.is_empty()),
+    };
+    let (cloud, stats) = PointCloud::build(data, radius, shape, material, world.bvh_builder())
+        .map_err(|err| ImportError::invalid(path, err))?;
+    world.add_bvh_stats(stats);
+
+    Ok(Arc::new(cloud))
+}
+
+/// Registers the default material of imported points, which is colored by their colors
+/// if they have any.
+pub fn default_material(world: &mut World, colored: bool) -> MaterialRef {
+    if colored {
+        world.register_material(Box::new(Lambertian::new(Arc::new(VertexColorTexture))))
+    } else {
+        world.register_material(Box::new(Lambertian::from_color(DEFAULT_COLOR)))
+    }
+}
+
+/// Parses the contents of a PLY file into mesh data with its polygons triangulated as fans,
+/// and also returns the polygons themselves.
+///
+/// Only the `vertex` and `face` elements are used, all other elements are skipped.
+fn parse_ply(bytes: &[u8], path: &Path) -> Result<(MeshData, Vec<Vec<u32>>), ImportError> {
+    let (mut data, faces) = parse_elements(bytes, path)?;
+    if faces.is_empty() {
+        return Err(ImportError::invalid(
+            path,
+            "file contains no faces, point clouds cannot be loaded as meshes",
+        ));
+    }
+    for face in &faces {
+        for i in 2..face.len() {
+            data.indices.push([face[0], face[i - 1], face[i]]);
+        }
+    }
+    data.validate()
+        .map_err(|err| ImportError::invalid(path, err))?;
+
+    Ok((data, faces))
+}
+
+/// Reads the vertices of a PLY file into mesh data without triangles, together with its faces,
+/// without checking that they form a mesh.
+fn parse_elements(bytes: &[u8], path: &Path) -> Result<(MeshData, Vec<Vec<u32>>), ImportError> {
+    let (header, body) = parse_header(bytes, path)?;
+
+    let mut reader = match header.format {
+        Format::Ascii => {
+            let body = std::str::from_utf8(body)
+                .map_err(|_| ImportError::invalid(path, "ASCII data is not valid UTF-8"))?;
+            BodyReader::Ascii {
+                tokens: Box::new(body.lines().enumerate().flat_map(move |(i, line)| {
+                    let line_number = header.body_line + i;
+                    line.split_whitespace()
+                        .map(move |token| (line_number, token))
+                })),
+                line: header.body_line,
+            }
+        }
+        Format::BinaryLittleEndian => BodyReader::Binary {
+            bytes: body,
+            position: 0,
+        },
+    };
+
+    // Every element takes up at least one byte, which limits how much memory the counts
+    // in the header can claim.
+    let body_size = body.len();
+    let vertex_count = header
+        .elements
+        .iter()
+        .find(|element| element.name == "vertex")
+        .map_or(0, |element| element.count);
+
+    let mut data = MeshData::new(vec![], vec![]);
+    let mut faces = vec![];
+    let mut values = vec![];
+    let mut list = vec![];
+
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let vertex = VertexLayout::new(element);
+                let Some(position) = vertex.position else {
+                    return Err(ImportError::parse(
+                        path,
+                        element.line,
+                        "vertex element has no `x`, `y` and `z` properties",
+                    ));
+                };
+
+                data.positions.reserve(element.count.min(body_size));
+                for _ in 0..element.count {
+                    reader.read_element(element, None, &mut values, &mut list, path)?;
+                    let vec = |[x, y, z]: [usize; 3]| Vec3(values[x], values[y], values[z]);
+
+                    data.positions.push(vec(position));
+                    if let Some(normal) = vertex.normal {
+                        data.normals.push(vec(normal).normalize());
+                    }
+                    if let Some([u, v]) = vertex.uv {
+                        data.uvs.push(TexCoord::new(values[u], values[v]));
+                    }
+                    if let Some(color) = vertex.color {
+                        let [r, g, b] = color.map(|(i, scale)| values[i] * scale);
+                        data.colors.push(Color::new(r, g, b));
+                    }
+                }
+            }
+            "face" => {
+                let Some(indices) = element.property_index(&["vertex_indices", "vertex_index"])
+                else {
+                    return Err(ImportError::parse(
+                        path,
+                        element.line,
+                        "face element has no `vertex_indices` property",
+                    ));
+                };
+
+                for face_index in 0..element.count {
+                    reader.read_element(element, Some(indices), &mut values, &mut list, path)?;
+                    if list.len() < 3 {
+                        return Err(ImportError::invalid(
+                            path,
+                            format!("face {face_index} has fewer than three vertices"),
+                        ));
+                    }
+                    let mut face = Vec::with_capacity(list.len());
+                    for &index in &list {
+                        if !(index >= 0.0 && index < vertex_count as f64 && index.fract() == 0.0) {
+                            return Err(reader.error(
+                                path,
+                                &format!("face {face_index} refers to invalid vertex {index}"),
+                            ));
+                        }
+                        face.push(index as u32);
+                    }
+                    faces.push(face);
+                }
+            }
+            _ => {
+                for _ in 0..element.count {
+                    reader.read_element(element, None, &mut values, &mut list, path)?;
+                }
+            }
+        }
+    }
+
+    Ok((data, faces))
+}
+
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+    /// Line number of the first line after the header, used for errors in ASCII files.
+    body_line: usize,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+    /// Line of the element declaration in the header.
+    line: usize,
+}
+
+impl Element {
+    /// Returns the index of the first property with one of the given names.
+    fn property_index(&self, names: &[&str]) -> Option<usize> {
+        self.properties
+            .iter()
+            .position(|property| names.contains(&property.name.as_str()))
+    }
+}
+
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+enum PropertyKind {
+    Scalar(ScalarType),
+    /// A list of values preceded by the number of values in it.
+    List {
+        count: ScalarType,
+        item: ScalarType,
+    },
+}
+
+#[derive(Clone, Copy)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => ScalarType::I8,
+            "uchar" | "uint8" => ScalarType::U8,
+            "short" | "int16" => ScalarType::I16,
+            "ushort" | "uint16" => ScalarType::U16,
+            "int" | "int32" => ScalarType::I32,
+            "uint" | "uint32" => ScalarType::U32,
+            "float" | "float32" => ScalarType::F32,
+            "double" | "float64" => ScalarType::F64,
+            _ => return None,
+        })
+    }
+
+    /// Factor that maps color values of this type to the range from 0 to 1.
+    fn color_scale(self) -> f64 {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1.0 / 255.0,
+            ScalarType::I16 | ScalarType::U16 => 1.0 / 65535.0,
+            ScalarType::I32 | ScalarType::U32 => 1.0 / u32::MAX as f64,
+            ScalarType::F32 | ScalarType::F64 => 1.0,
+        }
+    }
+}
+
+fn parse_header<'a>(bytes: &'a [u8], path: &Path) -> Result<(Header, &'a [u8]), ImportError> {
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    let mut position = 0;
+    let mut line_number = 0;
+
+    loop {
+        let Some(length) = bytes[position..].iter().position(|&b| b == b'\n') else {
+            return Err(ImportError::invalid(path, "missing `end_header`"));
+        };
+        let line = String::from_utf8_lossy(&bytes[position..position + length]);
+        position += length + 1;
+        line_number += 1;
+
+        let mut tokens = line.split_whitespace();
+        let keyword = tokens.next();
+        if line_number == 1 {
+            if keyword != Some("ply") {
+                return Err(ImportError::parse(path, 1, "not a PLY file"));
+            }
+            continue;
+        }
+
+        match keyword {
+            Some("format") => {
+                format = Some(match tokens.next() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary_little_endian") => Format::BinaryLittleEndian,
+                    Some(other) => {
+                        return Err(ImportError::parse(
+                            path,
+                            line_number,
+                            format!("unsupported format `{other}`"),
+                        ))
+                    }
+                    None => return Err(ImportError::parse(path, line_number, "missing format")),
+                });
+            }
+            Some("element") => {
+                let (Some(name), Some(count)) = (tokens.next(), tokens.next()) else {
+                    return Err(ImportError::parse(
+                        path,
+                        line_number,
+                        "expected element name and count",
+                    ));
+                };
+                let count = count.parse().map_err(|_| {
+                    ImportError::parse(path, line_number, format!("invalid count `{count}`"))
+                })?;
+                elements.push(Element {
+                    name: name.to_owned(),
+                    count,
+                    properties: vec![],
+                    line: line_number,
+                });
+            }
+            Some("property") => {
+                let Some(element) = elements.last_mut() else {
+                    return Err(ImportError::parse(
+                        path,
+                        line_number,
+                        "property declared before any element",
+                    ));
+                };
+                let tokens: Vec<&str> = tokens.collect();
+                let scalar_type = |name: &str| {
+                    ScalarType::parse(name).ok_or_else(|| {
+                        ImportError::parse(path, line_number, format!("unknown type `{name}`"))
+                    })
+                };
+                let property = match tokens.as_slice() {
+                    ["list", count, item, name] => Property {
+                        name: (*name).to_owned(),
+                        kind: PropertyKind::List {
+                            count: scalar_type(count)?,
+                            item: scalar_type(item)?,
+                        },
+                    },
+                    [ty, name] => Property {
+                        name: (*name).to_owned(),
+                        kind: PropertyKind::Scalar(scalar_type(ty)?),
+                    },
+                    _ => {
+                        return Err(ImportError::parse(
+                            path,
+                            line_number,
+                            "expected property type and name",
+                        ))
+                    }
+                };
+                element.properties.push(property);
+            }
+            Some("end_header") => break,
+            Some("comment" | "obj_info") | None => {}
+            Some(other) => {
+                return Err(ImportError::parse(
+                    path,
+                    line_number,
+                    format!("unknown header keyword `{other}`"),
+                ))
+            }
+        }
+    }
+
+    let Some(format) = format else {
+        return Err(ImportError::invalid(path, "missing format declaration"));
+    };
+
+    Ok((
+        Header {
+            format,
+            elements,
+            body_line: line_number + 1,
+        },
+        &bytes[position..],
+    ))
+}
+
+/// Indices of the vertex properties used for the mesh.
+struct VertexLayout {
+    position: Option<[usize; 3]>,
+    normal: Option<[usize; 3]>,
+    uv: Option<[usize; 2]>,
+    /// Indices of the color channels together with the scale that maps them to colors.
+    color: Option<[(usize, f64); 3]>,
+}
+
+impl VertexLayout {
+    fn new(element: &Element) -> Self {
+        let index = |names: &[&str]| element.property_index(names);
+        let color = |names: &[&str]| {
+            let i = index(names)?;
+            match element.properties[i].kind {
+                PropertyKind::Scalar(ty) => Some((i, ty.color_scale())),
+                PropertyKind::List { .. } => None,
+            }
+        };
+
+        Self {
+            position: index(&["x"])
+                .zip(index(&["y"]))
+                .zip(index(&["z"]))
+                .map(|((x, y), z)| [x, y, z]),
+            normal: index(&["nx"])
+                .zip(index(&["ny"]))
+                .zip(index(&["nz"]))
+                .map(|((x, y), z)| [x, y, z]),
+            uv: index(&["u", "s", "texture_u", "texture_s"])
+                .zip(index(&["v", "t", "texture_v", "texture_t"]))
+                .map(|(u, v)| [u, v]),
+            color: color(&["red", "r"])
+                .zip(color(&["green", "g"]))
+                .zip(color(&["blue", "b"]))
+                .map(|((r, g), b)| [r, g, b]),
+        }
+    }
+}
+
+enum BodyReader<'a> {
+    Ascii {
+        tokens: Box<dyn Iterator<Item = (usize, &'a str)> + 'a>,
+        /// Line of the last token, used for errors.
+        line: usize,
+    },
+    Binary {
+        bytes: &'a [u8],
+        position: usize,
+    },
+}
+
+impl BodyReader<'_> {
+    /// Reads one instance of `element`. Scalar properties are written to `values`, at the index
+    /// of the property. The items of the list property `keep_list` are written to `list`,
+    /// all other lists are skipped.
+    fn read_element(
+        &mut self,
+        element: &Element,
+        keep_list: Option<usize>,
+        values: &mut Vec<f64>,
+        list: &mut Vec<f64>,
+        path: &Path,
+    ) -> Result<(), ImportError> {
+        values.clear();
+        list.clear();
+
+        for (i, property) in element.properties.iter().enumerate() {
+            match property.kind {
+                PropertyKind::Scalar(ty) => values.push(self.read(ty, path)?),
+                PropertyKind::List { count, item } => {
+                    let count = self.read(count, path)?;
+                    if count < 0.0 {
+                        return Err(self.error(path, "negative list length"));
+                    }
+                    for _ in 0..count as usize {
+                        let value = self.read(item, path)?;
+                        if keep_list == Some(i) {
+                            list.push(value);
+                        }
+                    }
+                    values.push(0.0);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(&mut self, ty: ScalarType, path: &Path) -> Result<f64, ImportError> {
+        match self {
+            BodyReader::Ascii { tokens, line } => {
+                let Some((token_line, token)) = tokens.next() else {
+                    return Err(ImportError::parse(path, *line, "unexpected end of file"));
+                };
+                *line = token_line;
+                token.parse().map_err(|_| {
+                    ImportError::parse(path, token_line, format!("invalid number `{token}`"))
+                })
+            }
+            BodyReader::Binary { bytes, position } => {
+                let size = match ty {
+                    ScalarType::I8 | ScalarType::U8 => 1,
+                    ScalarType::I16 | ScalarType::U16 => 2,
+                    ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+                    ScalarType::F64 => 8,
+                };
+                let Some(b) = bytes.get(*position..*position + size) else {
+                    return Err(ImportError::invalid(path, "unexpected end of file"));
+                };
+                *position += size;
+                Ok(match ty {
+                    ScalarType::I8 => b[0] as i8 as f64,
+                    ScalarType::U8 => b[0] as f64,
+                    ScalarType::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    ScalarType::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    ScalarType::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    ScalarType::F64 => f64::from_le_bytes(b.try_into().unwrap()),
+                })
+            }
+        }
+    }
+
+    fn error(&self, path: &Path, message: &str) -> ImportError {
+        match self {
+            BodyReader::Ascii { line, .. } => ImportError::parse(path, *line, message),
+            BodyReader::Binary { .. } => ImportError::invalid(path, message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use crate::{
+        materials::{
+            material::MaterialRef,
+            texture::{Texture, VertexColorTexture},
+        },
+        objects::{
+            bvh::BvhBuilder,
+            hittable::Hittable,
+            mesh::TriangleMesh,
+            subdivision::{PolygonMesh, Subdivision, SubdivisionScheme},
+        },
+        ray::Ray,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::{parse_ply, ImportError};
+
+    const ASCII_QUAD: &str = "ply
+format ascii 1.0
+comment a unit quad with a red, green, blue and white corner
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+0 2
+";
+
+    #[test]
+    fn binary_matches_ascii() {
+        let mut binary = ASCII_QUAD
+            .replace("format ascii", "format binary_little_endian")
+            .split_inclusive("end_header\n")
+            .next()
+            .unwrap()
+            .as_bytes()
+            .to_vec();
+        for (position, color) in [
+            ([0.0f32, 0.0, 0.0], [255, 0, 0]),
+            ([1.0, 0.0, 0.0], [0, 255, 0]),
+            ([1.0, 1.0, 0.0], [0, 0, 255]),
+            ([0.0, 1.0, 0.0], [255, 255, 255]),
+        ] {
+            binary.extend(position.iter().flat_map(|p| p.to_le_bytes()));
+            binary.extend(color);
+        }
+        binary.push(4);
+        binary.extend([0i32, 1, 2, 3, 0, 2].iter().flat_map(|i| i.to_le_bytes()));
+
+        let path = Path::new("quad.ply");
+        let (ascii, _) = parse_ply(ASCII_QUAD.as_bytes(), path).unwrap();
+        let (binary, _) = parse_ply(&binary, path).unwrap();
+
+        assert_eq!(ascii.indices, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(binary.indices, ascii.indices);
+        for (a, b) in ascii.positions.iter().zip(&binary.positions) {
+            assert!((*a - *b).near_zero());
+        }
+        for (a, b) in ascii.colors.iter().zip(&binary.colors) {
+            assert!((*a - *b).near_zero());
+        }
+    }
+
+    #[test]
+    fn interpolates_vertex_colors() {
+        let (data, _) = parse_ply(ASCII_QUAD.as_bytes(), Path::new("quad.ply")).unwrap();
+        let (mesh, _) = TriangleMesh::build(Arc::new(data), MaterialRef(0), BvhBuilder::default());
+
+        // Halfway along the edge between the red and the green corner.
+        let ray = Ray::new(Vec3(0.5, 0.0, 1.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = mesh.hit(&ray, Interval(0.001, f64::INFINITY)).unwrap();
+        let color = VertexColorTexture.sample(&rec);
+
+        assert!((color - Vec3(0.5, 0.5, 0.0)).near_zero());
+    }
+
+    #[test]
+    fn reports_invalid_number() {
+        let source = ASCII_QUAD.replace("1 1 0 0 0 255", "1 one 0 0 0 255");
+        match parse_ply(source.as_bytes(), Path::new("quad.ply")) {
+            Err(ImportError::Parse { line, message, .. }) => {
+                assert_eq!(line, 19);
+                assert_eq!(message, "invalid number `one`");
+            }
+            _ => panic!("expected parse error"),
+        }
+    }
+
+    #[test]
+    fn keeps_polygons_for_subdivision() {
+        let (data, faces) = parse_ply(ASCII_QUAD.as_bytes(), Path::new("quad.ply")).unwrap();
+        assert_eq!(faces, vec![vec![0, 1, 2, 3]]);
+
+        // Catmull-Clark splits the quad into four quads rather than each of its two
+        // triangles into three.
+        let polygons = PolygonMesh {
+            positions: data.positions,
+            uvs: data.uvs,
+            colors: data.colors,
+            faces,
+        };
+        let subdivision = Subdivision {
+            scheme: SubdivisionScheme::CatmullClark,
+            levels: 1,
+        };
+        assert_eq!(polygons.subdivide(subdivision).indices.len(), 8);
+    }
+
+    #[test]
+    fn rejects_invalid_vertex_indices() {
+        for (face, index) in [("4 0 1 2 -1", "-1"), ("4 0 1 2 4", "4")] {
+            let source = ASCII_QUAD.replace("4 0 1 2 3", face);
+            match parse_ply(source.as_bytes(), Path::new("quad.ply")) {
+                Err(ImportError::Parse { line, message, .. }) => {
+                    assert_eq!(line, 21);
+                    assert_eq!(message, format!("face 0 refers to invalid vertex {index}"));
+                }
+                _ => panic!("expected parse error"),
+            }
+        }
+    }
+
+    #[test]
+    fn header_counts_do_not_reserve_memory() {
+        let source = ASCII_QUAD.replace("element vertex 4", "element vertex 4000000000000");
+        assert!(parse_ply(source.as_bytes(), Path::new("quad.ply")).is_err());
+    }
+}
@@ -1,10 +1,12 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use animation::FrameRange;
 use camera::Camera;
 use clap::Parser;
 use image::ImageFormat;
 
-use objects::world::World;
+use objects::{bvh::BvhBuilder, world::World};
+use scenes::scene::Settings;
 #[cfg(feature = "sdl")]
 use sdl2::{event::Event, pixels::PixelFormatEnum};
 #[cfg(feature = "sdl")]
@@ -12,13 +14,18 @@ use util::linear_to_gamma;
 #[cfg(feature = "sdl")]
 use vec3::Color;
 
-use crate::vec3::Vec3;
-
+mod animation;
 mod camera;
+mod import;
 mod materials;
+mod matrix;
 mod objects;
+mod polynomial;
 mod ray;
+mod scene_file;
 mod scenes;
+#[cfg(test)]
+mod test_util;
 mod util;
 mod vec3;
 
@@ -92,53 +99,92 @@ struct Args {
     #[clap(long)]
     max_bounces: Option<u32>,
 
+    /// Time at which the shutter of the camera opens.
+    #[clap(long)]
+    shutter_open: Option<f64>,
+
+    /// Time at which the shutter of the camera closes. Set it to the opening time
+    /// to render a single instant without motion blur.
+    #[clap(long)]
+    shutter_close: Option<f64>,
+
+    /// Renders the frames of an animation in the given range, e.g. `1-48`, instead of a
+    /// single image. The frames are saved next to the output image as frame_0001.png, ...
+    #[clap(long)]
+    frames: Option<FrameRange>,
+
     /// Selects which scenes to render
     #[clap(long, default_value_t = Scene::BouncingSpheres)]
     scene: Scene,
+
+    /// Loads the scene from a TOML scene file instead of using one of the built-in scenes.
+    #[clap(long, conflicts_with = "scene")]
+    scene_file: Option<PathBuf>,
+
+    /// Writes the selected scene to a TOML scene file instead of rendering it.
+    #[clap(long)]
+    dump_scene: Option<PathBuf>,
+
+    /// Algorithm used to build the bounding volume hierarchy of the scene.
+    #[clap(long, default_value_t = BvhBuilder::Sah)]
+    bvh: BvhBuilder,
+
+    /// Prints statistics about the bounding volume hierarchy of the scene.
+    #[clap(long, default_value_t = false)]
+    bvh_stats: bool,
 }
 
 fn main() {
     let args = Args::parse();
 
-    let scene: &dyn scenes::scene::Scene = match args.scene {
-        Scene::BouncingSpheres => &scenes::bouncing_spheres::BouncingSpheresScene,
-        Scene::CheckeredSpheres => &scenes::checkered_spheres::CheckeredSpheresScene,
-        Scene::Earth => &scenes::earth::EarthScene,
-        Scene::Quads => &scenes::quads::QuadsScene,
-        Scene::SimpleLight => &scenes::simple_light::SimpleLightScene,
-        Scene::BouncingSpheresWithLight => {
-            &scenes::bouncing_spheres_with_light::BouncingSpheresWithLightScene
+    let (mut settings, mut world) = if let Some(path) = &args.scene_file {
+        match scene_file::loader::load(path, args.bvh) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                eprintln!("{}: {err}", path.display());
+                std::process::exit(1);
+            }
         }
-        Scene::EmptyCornellBox => &scenes::empty_cornell_box::EmptyCornellBoxScene,
-        Scene::CornellBox => &scenes::cornell_box::CornellBoxScene,
-        Scene::CornellSmoke => &scenes::cornell_smoke::CornellSmokeScene,
-        Scene::Everything => &scenes::everything::EverythingScene,
+    } else {
+        let scene = builtin_scene(args.scene);
+        (scene.default_settings(), scene.world(args.bvh))
     };
 
-    let default_settings = scene.default_settings();
-
-    let width = args.width.unwrap_or(default_settings.width);
-    let height = args.height.unwrap_or(default_settings.height);
-
-    let camera = Camera::new(
-        width,
-        height,
-        default_settings.camera_eye,
-        default_settings.camera_target,
-        Vec3(0.0, 1.0, 0.0),
-        args.fov.unwrap_or(default_settings.fov),
-        args.focus_distance
-            .unwrap_or(default_settings.focus_distance),
-        args.defocus_angle.unwrap_or(default_settings.defocus_angle),
-        args.samples.unwrap_or(default_settings.samples),
-        args.max_bounces.unwrap_or(default_settings.max_bounces),
-        default_settings.background_color,
-    );
+    apply_overrides(&args, &mut settings);
 
-    let world = scene.world();
+    if settings.shutter_close < settings.shutter_open {
+        eprintln!("the shutter cannot close before it opens");
+        std::process::exit(1);
+    }
+
+    if let Err(err) = settings.animation.validate() {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+
+    if let Some(path) = &args.dump_scene {
+        if let Err(err) = scene_file::exporter::write(path, settings, &world) {
+            eprintln!("{}: {err}", path.display());
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let stats = world.build_bvh();
+    eprintln!(
+        "BVH build time (seconds): {}",
+        stats.build_time.as_secs_f64()
+    );
+    if args.bvh_stats {
+        eprintln!("{stats}");
+    }
 
     #[cfg(feature = "sdl")]
     if args.live_window {
+        let width = settings.width;
+        let height = settings.height;
+        let camera = camera_for_frame(&settings, 0);
+
         let sdl_context = sdl2::init().unwrap();
         let video_subsystem = sdl_context.video().unwrap();
         let window = video_subsystem
@@ -147,6 +193,11 @@ fn main() {
             .build()
             .unwrap();
 
+        let scene_name = match &args.scene_file {
+            Some(path) => path.display().to_string(),
+            None => args.scene.to_string(),
+        };
+
         let mut canvas = window.into_canvas().build().unwrap();
         let texture_creator = canvas.texture_creator();
         let mut render_target = texture_creator
@@ -201,7 +252,7 @@ fn main() {
                     .window_mut()
                     .set_title(&format!(
                         "Scene: {} -- Total Number of Samples per Pixel: {}",
-                        args.scene, num_total_samples
+                        scene_name, num_total_samples
                     ))
                     .unwrap();
             }
@@ -211,15 +262,83 @@ fn main() {
             canvas.present();
         }
     } else {
-        render_to_image(args, camera, world);
+        render_to_files(&args, &settings, &world);
     }
 
     #[cfg(not(feature = "sdl"))]
-    render_to_image(args, camera, world);
+    render_to_files(&args, &settings, &world);
 }
 
-fn render_to_image(args: Args, camera: Camera, world: World) {
-    let img = camera.render(&world);
-    img.save_with_format(args.output_filename, ImageFormat::Png)
+/// Replaces the settings of the scene with those given on the command line.
+fn apply_overrides(args: &Args, settings: &mut Settings) {
+    settings.width = args.width.unwrap_or(settings.width);
+    settings.height = args.height.unwrap_or(settings.height);
+    settings.fov = args.fov.unwrap_or(settings.fov);
+    settings.focus_distance = args.focus_distance.unwrap_or(settings.focus_distance);
+    settings.defocus_angle = args.defocus_angle.unwrap_or(settings.defocus_angle);
+    settings.samples = args.samples.unwrap_or(settings.samples);
+    settings.max_bounces = args.max_bounces.unwrap_or(settings.max_bounces);
+    settings.shutter_open = args.shutter_open.unwrap_or(settings.shutter_open);
+    settings.shutter_close = args.shutter_close.unwrap_or(settings.shutter_close);
+}
+
+fn builtin_scene(scene: Scene) -> &'static dyn scenes::scene::Scene {
+    match scene {
+        Scene::BouncingSpheres => &scenes::bouncing_spheres::BouncingSpheresScene,
+        Scene::CheckeredSpheres => &scenes::checkered_spheres::CheckeredSpheresScene,
+        Scene::Earth => &scenes::earth::EarthScene,
+        Scene::Quads => &scenes::quads::QuadsScene,
+        Scene::SimpleLight => &scenes::simple_light::SimpleLightScene,
+        Scene::BouncingSpheresWithLight => {
+            &scenes::bouncing_spheres_with_light::BouncingSpheresWithLightScene
+        }
+        Scene::EmptyCornellBox => &scenes::empty_cornell_box::EmptyCornellBoxScene,
+        Scene::CornellBox => &scenes::cornell_box::CornellBoxScene,
+        Scene::CornellSmoke => &scenes::cornell_smoke::CornellSmokeScene,
+        Scene::Everything => &scenes::everything::EverythingScene,
+    }
+}
+
+/// Sets up the camera to expose frame `frame` of the animation. A still image is frame 0.
+fn camera_for_frame(settings: &Settings, frame: u32) -> Camera {
+    let shutter_open = frame as f64 + settings.shutter_open;
+    let shutter_close = frame as f64 + settings.shutter_close;
+    let settings = settings.at_time(shutter_open);
+
+    Camera::new(
+        settings.width,
+        settings.height,
+        settings.camera_eye,
+        settings.camera_target,
+        settings.camera_up,
+        settings.fov,
+        settings.focus_distance,
+        settings.defocus_angle,
+        settings.samples,
+        settings.max_bounces,
+        settings.background_color,
+        shutter_open,
+        shutter_close,
+    )
+}
+
+/// Renders either a single image or, for animations, every frame in the requested range.
+fn render_to_files(args: &Args, settings: &Settings, world: &World) {
+    let Some(frames) = args.frames.or(settings.animation.frame_range()) else {
+        let img = camera_for_frame(settings, 0).render(world);
+        img.save_with_format(&args.output_filename, ImageFormat::Png)
+            .expect("failed to save output image");
+        return;
+    };
+
+    let directory = args.output_filename.parent().unwrap_or(Path::new("."));
+    for frame in frames.first..=frames.last {
+        eprintln!("Frame {frame} of {}-{}", frames.first, frames.last);
+        let img = camera_for_frame(settings, frame).render(world);
+        img.save_with_format(
+            directory.join(format!("frame_{frame:04}.png")),
+            ImageFormat::Png,
+        )
         .expect("failed to save output image");
+    }
 }
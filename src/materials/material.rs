@@ -1,11 +1,19 @@
 use std::sync::Arc;
 
 use crate::{
-    materials::texture::{SolidColor, TexCoord, Texture},
+    materials::{
+        normal_map::{SurfaceDetail, SurfaceDetailFields},
+        texture::{SolidColor, Texture},
+    },
     objects::hittable::HitRecord,
     ray::Ray,
+    scene_file::{
+        description::MaterialDescription,
+        error::ExportError,
+        exporter::{DescribeMaterial, SceneExporter},
+    },
     util::{rand_f64, rand_unit_vec3, reflect, reflectance, refract},
-    vec3::{Color, Vec3},
+    vec3::Color,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -16,69 +24,144 @@ pub struct ScatterResult {
     pub attenuation: Color,
 }
 
-pub trait Material: Send + Sync {
+pub trait Material: Send + Sync + DescribeMaterial {
     fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<ScatterResult>;
-    fn emit(&self, _uv: TexCoord, _point: Vec3) -> Color {
+    fn emit(&self, _rec: &HitRecord) -> Color {
         Color::new(0.0, 0.0, 0.0)
     }
 }
 
 pub struct Lambertian {
     pub texture: Arc<dyn Texture>,
+    pub surface_detail: SurfaceDetail,
 }
 
 impl Lambertian {
     pub fn new(texture: Arc<dyn Texture>) -> Self {
-        Self { texture }
+        Self {
+            texture,
+            surface_detail: SurfaceDetail::default(),
+        }
     }
 
     pub fn from_color(albedo: Color) -> Self {
+        Self::new(Arc::new(SolidColor { color: albedo }))
+    }
+
+    pub fn with_surface_detail(self, surface_detail: SurfaceDetail) -> Self {
         Self {
-            texture: Arc::new(SolidColor { color: albedo }),
+            surface_detail,
+            ..self
         }
     }
 }
 
 impl Material for Lambertian {
     fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<ScatterResult> {
-        let mut dir = rec.normal + rand_unit_vec3();
+        let normal = self.surface_detail.shading_normal(ray, rec);
+        let mut dir = normal + rand_unit_vec3();
         if dir.near_zero() {
-            dir = rec.normal;
+            dir = normal;
         }
         let ray = Ray::new(rec.position, dir, ray.time);
-        let attenuation = self.texture.sample(rec.uv, rec.position);
+        let attenuation = self.texture.sample(rec);
         Some(ScatterResult { ray, attenuation })
     }
 }
 
+impl DescribeMaterial for Lambertian {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        let (texture, color) = exporter.texture_or_color(&self.texture)?;
+        let SurfaceDetailFields {
+            normal_map,
+            bump_map,
+            bump_strength,
+        } = self.surface_detail.describe(exporter)?;
+        Ok(MaterialDescription::Lambertian {
+            texture,
+            color,
+            normal_map,
+            bump_map,
+            bump_strength,
+        })
+    }
+}
+
 pub struct Metal {
-    pub albedo: Color,
+    pub texture: Arc<dyn Texture>,
     pub fuzziness: f64,
+    pub surface_detail: SurfaceDetail,
 }
 
 impl Metal {
-    pub fn new(albedo: Color, fuzziness: f64) -> Self {
-        Self { albedo, fuzziness }
+    pub fn new(texture: Arc<dyn Texture>, fuzziness: f64) -> Self {
+        Self {
+            texture,
+            fuzziness,
+            surface_detail: SurfaceDetail::default(),
+        }
+    }
+
+    pub fn from_color(albedo: Color, fuzziness: f64) -> Self {
+        Self::new(Arc::new(SolidColor { color: albedo }), fuzziness)
+    }
+
+    pub fn with_surface_detail(self, surface_detail: SurfaceDetail) -> Self {
+        Self {
+            surface_detail,
+            ..self
+        }
     }
 }
 
 impl Material for Metal {
     fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<ScatterResult> {
-        let dir = reflect(ray.direction.normalize(), rec.normal);
+        let normal = self.surface_detail.shading_normal(ray, rec);
+        let dir = reflect(ray.direction.normalize(), normal);
         let fuzzed_dir = dir + self.fuzziness * rand_unit_vec3();
         let ray = Ray::new(rec.position, fuzzed_dir, ray.time);
-        let attenuation = self.albedo;
+        let attenuation = self.texture.sample(rec);
         Some(ScatterResult { ray, attenuation })
     }
 }
 
+impl DescribeMaterial for Metal {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        let (texture, color) = exporter.texture_or_color(&self.texture)?;
+        let SurfaceDetailFields {
+            normal_map,
+            bump_map,
+            bump_strength,
+        } = self.surface_detail.describe(exporter)?;
+        Ok(MaterialDescription::Metal {
+            texture,
+            color,
+            fuzziness: self.fuzziness,
+            normal_map,
+            bump_map,
+            bump_strength,
+        })
+    }
+}
+
 pub struct Dialectric {
     refraction_index: f64,
+    surface_detail: SurfaceDetail,
 }
 
 impl Dialectric {
     pub fn new(refraction_index: f64) -> Self {
-        Self { refraction_index }
+        Self {
+            refraction_index,
+            surface_detail: SurfaceDetail::default(),
+        }
+    }
+
+    pub fn with_surface_detail(self, surface_detail: SurfaceDetail) -> Self {
+        Self {
+            surface_detail,
+            ..self
+        }
     }
 }
 
@@ -92,15 +175,16 @@ impl Material for Dialectric {
             self.refraction_index
         };
         let unit_dir = ray.direction.normalize();
+        let normal = self.surface_detail.shading_normal(ray, rec);
 
-        let cos_theta = (-unit_dir).dot(rec.normal).min(1.0);
+        let cos_theta = (-unit_dir).dot(normal).min(1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
         let cannot_refract = ratio * sin_theta > 1.0;
         let should_reflect = reflectance(cos_theta, ratio) > rand_f64(0.0, 1.0);
         let dir = if cannot_refract || should_reflect {
-            reflect(unit_dir, rec.normal)
+            reflect(unit_dir, normal)
         } else {
-            refract(unit_dir, rec.normal, ratio)
+            refract(unit_dir, normal, ratio)
         };
         let ray = Ray::new(rec.position, dir, ray.time);
 
@@ -108,11 +192,31 @@ impl Material for Dialectric {
     }
 }
 
+impl DescribeMaterial for Dialectric {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        let SurfaceDetailFields {
+            normal_map,
+            bump_map,
+            bump_strength,
+        } = self.surface_detail.describe(exporter)?;
+        Ok(MaterialDescription::Dialectric {
+            refraction_index: self.refraction_index,
+            normal_map,
+            bump_map,
+            bump_strength,
+        })
+    }
+}
+
 pub struct DiffuseLight {
     texture: Arc<dyn Texture>,
 }
 
 impl DiffuseLight {
+    pub fn new(texture: Arc<dyn Texture>) -> Self {
+        Self { texture }
+    }
+
     pub fn from_color(color: Color) -> Self {
         Self {
             texture: Arc::new(SolidColor { color }),
@@ -125,8 +229,15 @@ impl Material for DiffuseLight {
         None
     }
 
-    fn emit(&self, uv: TexCoord, point: Vec3) -> Color {
-        self.texture.sample(uv, point)
+    fn emit(&self, rec: &HitRecord) -> Color {
+        self.texture.sample(rec)
+    }
+}
+
+impl DescribeMaterial for DiffuseLight {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        let (texture, color) = exporter.texture_or_color(&self.texture)?;
+        Ok(MaterialDescription::DiffuseLight { texture, color })
     }
 }
 
@@ -135,6 +246,10 @@ pub struct Isotropic {
 }
 
 impl Isotropic {
+    pub fn new(texture: Arc<dyn Texture>) -> Self {
+        Self { texture }
+    }
+
     pub fn from_color(color: Color) -> Self {
         Self {
             texture: Arc::new(SolidColor { color }),
@@ -145,10 +260,17 @@ impl Isotropic {
 impl Material for Isotropic {
     fn scatter(&self, ray: &Ray, rec: &HitRecord) -> Option<ScatterResult> {
         let scattered = Ray::new(rec.position, rand_unit_vec3(), ray.time);
-        let attenuation = self.texture.sample(rec.uv, rec.position);
+        let attenuation = self.texture.sample(rec);
         Some(ScatterResult {
             ray: scattered,
             attenuation,
         })
     }
 }
+
+impl DescribeMaterial for Isotropic {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<MaterialDescription, ExportError> {
+        let (texture, color) = exporter.texture_or_color(&self.texture)?;
+        Ok(MaterialDescription::Isotropic { texture, color })
+    }
+}
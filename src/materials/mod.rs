@@ -1,2 +1,3 @@
 pub mod material;
+pub mod normal_map;
 pub mod texture;
@@ -0,0 +1,271 @@
+use std::path::{Path, PathBuf};
+
+use image::{ImageBuffer, ImageResult, Luma, Rgb};
+
+use crate::{
+    materials::texture::TexCoord,
+    objects::hittable::HitRecord,
+    ray::Ray,
+    scene_file::{error::ExportError, exporter::SceneExporter},
+    vec3::Vec3,
+};
+
+/// Returns the four texels around `uv` in an image of `width` by `height` texels together
+/// with their weights for bilinear interpolation. Like image textures, `v` runs upwards.
+fn bilinear_texels(uv: TexCoord, width: u32, height: u32) -> [(u32, u32, f64); 4] {
+    // Texel centers lie halfway between the integer coordinates.
+    let x = (uv.u.clamp(0.0, 1.0) * width as f64 - 0.5).clamp(0.0, (width - 1) as f64);
+    let y = ((1.0 - uv.v.clamp(0.0, 1.0)) * height as f64 - 0.5).clamp(0.0, (height - 1) as f64);
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - x0 as f64, y - y0 as f64);
+    [
+        (x0, y0, (1.0 - fx) * (1.0 - fy)),
+        (x1, y0, fx * (1.0 - fy)),
+        (x0, y1, (1.0 - fx) * fy),
+        (x1, y1, fx * fy),
+    ]
+}
+
+/// A tangent-space normal map. Its red, green and blue channels give the shading normal
+/// along the tangent, the bitangent and the normal of the surface, mapped from [-1, 1]
+/// to the range of the image. Green points towards increasing `v`, as in OpenGL.
+pub struct NormalMap {
+    image: ImageBuffer<Rgb<u16>, Vec<u16>>,
+    /// File the image was loaded from, if any. Used when exporting the scene.
+    path: Option<PathBuf>,
+}
+
+impl NormalMap {
+    pub fn new(image: ImageBuffer<Rgb<u16>, Vec<u16>>) -> Self {
+        Self { image, path: None }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let path = path.as_ref();
+        let image = image::open(path)?.into_rgb16();
+        Ok(Self {
+            image,
+            path: Some(path.to_owned()),
+        })
+    }
+
+    /// Returns the normal at `uv` relative to the tangent frame.
+    fn sample(&self, uv: TexCoord) -> Vec3 {
+        let mut normal = Vec3(0.0, 0.0, 0.0);
+        for (x, y, weight) in bilinear_texels(uv, self.image.width(), self.image.height()) {
+            let [r, g, b] = self.image.get_pixel(x, y).0.map(|c| c as f64 / 65535.0);
+            normal += Vec3(r, g, b) * weight;
+        }
+        normal * 2.0 - Vec3(1.0, 1.0, 1.0)
+    }
+}
+
+/// A height map whose slopes tilt the shading normal as if the surface was displaced by it,
+/// without moving the surface itself.
+pub struct BumpMap {
+    heights: ImageBuffer<Luma<u16>, Vec<u16>>,
+    /// How much the normal tilts for a given change of brightness between neighbouring texels.
+    /// At 1, a change from black to white over one texel tilts it by 45°.
+    pub strength: f64,
+    /// File the image was loaded from, if any. Used when exporting the scene.
+    path: Option<PathBuf>,
+}
+
+impl BumpMap {
+    #[allow(unused)]
+    pub fn new(heights: ImageBuffer<Luma<u16>, Vec<u16>>, strength: f64) -> Self {
+        Self {
+            heights,
+            strength,
+            path: None,
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>, strength: f64) -> ImageResult<Self> {
+        let path = path.as_ref();
+        let heights = image::open(path)?.into_luma16();
+        Ok(Self {
+            heights,
+            strength,
+            path: Some(path.to_owned()),
+        })
+    }
+
+    fn height(&self, uv: TexCoord) -> f64 {
+        bilinear_texels(uv, self.heights.width(), self.heights.height())
+            .into_iter()
+            .map(|(x, y, weight)| self.heights.get_pixel(x, y).0[0] as f64 / 65535.0 * weight)
+            .sum()
+    }
+
+    /// Returns the change of height per texel along `u` and `v` at `uv`.
+    fn slope(&self, uv: TexCoord) -> (f64, f64) {
+        let du = 1.0 / self.heights.width() as f64;
+        let dv = 1.0 / self.heights.height() as f64;
+        let along_u = self.height(TexCoord::new(uv.u + du, uv.v))
+            - self.height(TexCoord::new(uv.u - du, uv.v));
+        let along_v = self.height(TexCoord::new(uv.u, uv.v + dv))
+            - self.height(TexCoord::new(uv.u, uv.v - dv));
+        (0.5 * along_u, 0.5 * along_v)
+    }
+}
+
+/// The fields of a material in scene files that describe its [`SurfaceDetail`].
+pub struct SurfaceDetailFields {
+    pub normal_map: Option<PathBuf>,
+    pub bump_map: Option<PathBuf>,
+    pub bump_strength: Option<f64>,
+}
+
+/// Normal and bump maps that perturb the shading normal of the surfaces a material is
+/// applied to, following their tangent frame. A bump map applies on top of a normal map.
+#[derive(Default)]
+pub struct SurfaceDetail {
+    pub normal_map: Option<NormalMap>,
+    pub bump_map: Option<BumpMap>,
+}
+
+impl SurfaceDetail {
+    /// Returns the normal a material shades the hit `rec` with, on the side the ray came from.
+    ///
+    /// Interpolated and perturbed normals can tilt below the actual surface or away from the ray,
+    /// such as at grazing angles, and would send scattered rays into the surface. Such normals
+    /// are replaced by the unperturbed normal, or failing that by the geometric normal.
+    pub fn shading_normal(&self, ray: &Ray, rec: &HitRecord) -> Vec3 {
+        let usable = |normal: Vec3| {
+            normal.dot(rec.geometric_normal) > 0.0 && ray.direction.dot(normal) < 0.0
+        };
+        let fallback = if usable(rec.normal) {
+            rec.normal
+        } else {
+            rec.geometric_normal
+        };
+        if self.normal_map.is_none() && self.bump_map.is_none() {
+            return fallback;
+        }
+
+        let mut normal = rec.outward_normal();
+        if let Some(map) = &self.normal_map {
+            let local = map.sample(rec.uv);
+            normal = rec.tangent * local.x() + rec.bitangent * local.y() + normal * local.z();
+        }
+        if let Some(map) = &self.bump_map {
+            let (along_u, along_v) = map.slope(rec.uv);
+            normal = normal - (rec.tangent * along_u + rec.bitangent * along_v) * map.strength;
+        }
+
+        if normal.near_zero() {
+            return fallback;
+        }
+        let normal = if rec.front_face { normal } else { -normal }.normalize();
+        if usable(normal) {
+            normal
+        } else {
+            fallback
+        }
+    }
+
+    pub fn describe(
+        &self,
+        exporter: &mut SceneExporter,
+    ) -> Result<SurfaceDetailFields, ExportError> {
+        let normal_map = self
+            .normal_map
+            .as_ref()
+            .map(|map| {
+                exporter.image_path(map.path.as_deref(), "normal map", |path| {
+                    map.image.save(path)
+                })
+            })
+            .transpose()?;
+        let bump_map = self
+            .bump_map
+            .as_ref()
+            .map(|map| {
+                exporter.image_path(map.path.as_deref(), "bump map", |path| {
+                    map.heights.save(path)
+                })
+            })
+            .transpose()?;
+        Ok(SurfaceDetailFields {
+            normal_map,
+            bump_map,
+            bump_strength: self.bump_map.as_ref().map(|map| map.strength),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Luma, Rgb};
+
+    use crate::{
+        materials::{material::MaterialRef, texture::TexCoord},
+        objects::hittable::HitRecord,
+        ray::Ray,
+        vec3::Vec3,
+    };
+
+    use super::{BumpMap, NormalMap, SurfaceDetail};
+
+    /// A hit on the floor from above, whose texture coordinates run along x and -z.
+    fn floor_hit(ray: &Ray) -> HitRecord {
+        HitRecord::with_face_normal(
+            *ray,
+            1.0,
+            Vec3(0.0, 0.0, 0.0),
+            TexCoord::new(0.5, 0.5),
+            Vec3(0.0, 1.0, 0.0),
+            MaterialRef(0),
+        )
+        .with_tangents(Vec3(1.0, 0.0, 0.0), Vec3(0.0, 0.0, -1.0))
+    }
+
+    #[test]
+    fn normal_map_tilts_along_tangent_frame() {
+        // Tilted by 45° towards increasing u.
+        let tilt = (0.5f64.sqrt() * 0.5 + 0.5) * 65535.0;
+        let image = ImageBuffer::from_pixel(4, 4, Rgb([tilt as u16, 32768, tilt as u16]));
+        let detail = SurfaceDetail {
+            normal_map: Some(NormalMap::new(image)),
+            bump_map: None,
+        };
+
+        let ray = Ray::new(Vec3(0.0, 1.0, 0.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        let normal = detail.shading_normal(&ray, &floor_hit(&ray));
+        let expected = Vec3(1.0, 1.0, 0.0).normalize();
+        assert!((normal - expected).length() < 1e-4);
+
+        // From below, the normal faces the ray and the tilt is mirrored with it.
+        let ray = Ray::new(Vec3(0.0, -1.0, 0.0), Vec3(0.0, 1.0, 0.0), 0.0);
+        let normal = detail.shading_normal(&ray, &floor_hit(&ray));
+        assert!((normal + expected).length() < 1e-4);
+    }
+
+    #[test]
+    fn bump_map_tilts_away_from_slope() {
+        // Heights rise from left to right, i.e. along u.
+        let image = ImageBuffer::from_fn(8, 8, |x, _| Luma([(x * 4000) as u16]));
+        let detail = SurfaceDetail {
+            normal_map: None,
+            bump_map: Some(BumpMap::new(image, 10.0)),
+        };
+
+        let ray = Ray::new(Vec3(0.0, 1.0, 0.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        let normal = detail.shading_normal(&ray, &floor_hit(&ray));
+        assert!(normal.x() < -0.1 && normal.y() > 0.0 && normal.z().abs() < 1e-9);
+        assert!((normal.length() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn normals_below_the_surface_fall_back_to_geometric_normal() {
+        // An interpolated normal that faces the oblique ray but tilts below the floor.
+        let ray = Ray::new(Vec3(-1.0, 1.0, 0.0), Vec3(1.0, -1.0, 0.0), 0.0);
+        let mut rec = floor_hit(&ray);
+        rec.normal = Vec3(-1.0, -0.1, 0.0).normalize();
+
+        let normal = SurfaceDetail::default().shading_normal(&ray, &rec);
+        assert!((normal - Vec3(0.0, 1.0, 0.0)).length() < 1e-9);
+    }
+}
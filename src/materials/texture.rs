@@ -1,8 +1,19 @@
-use std::sync::Arc;
-
-use image::RgbImage;
-
-use crate::vec3::{Color, Vec3};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use image::{ImageResult, RgbImage};
+
+use crate::{
+    objects::{hittable::HitRecord, triangle::interpolate},
+    scene_file::{
+        description::TextureDescription,
+        error::ExportError,
+        exporter::{DescribeTexture, SceneExporter},
+    },
+    vec3::Color,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct TexCoord {
@@ -16,8 +27,17 @@ impl TexCoord {
     }
 }
 
-pub trait Texture: Send + Sync {
-    fn sample(&self, uv: TexCoord, point: Vec3) -> Color;
+/// Colors of the vertices of a hit triangle together with the barycentric
+/// coordinates of the hit with respect to the second and third vertex.
+#[derive(Debug, Clone, Copy)]
+pub struct VertexColors {
+    pub colors: [Color; 3],
+    pub b1: f64,
+    pub b2: f64,
+}
+
+pub trait Texture: Send + Sync + DescribeTexture {
+    fn sample(&self, rec: &HitRecord) -> Color;
 }
 
 pub struct SolidColor {
@@ -25,21 +45,28 @@ pub struct SolidColor {
 }
 
 impl Texture for SolidColor {
-    fn sample(&self, _uv: TexCoord, _point: Vec3) -> Color {
+    fn sample(&self, _rec: &HitRecord) -> Color {
         self.color
     }
 }
 
+impl DescribeTexture for SolidColor {
+    fn describe(&self, _exporter: &mut SceneExporter) -> Result<TextureDescription, ExportError> {
+        Ok(TextureDescription::Solid { color: self.color })
+    }
+}
+
 pub struct CheckerTexture {
+    scale: f64,
     inv_scale: f64,
     even_texture: Arc<dyn Texture>,
     odd_texture: Arc<dyn Texture>,
 }
 
 impl CheckerTexture {
-    #[allow(unused)]
     pub fn new(scale: f64, even_texture: Arc<dyn Texture>, odd_texture: Arc<dyn Texture>) -> Self {
         Self {
+            scale,
             inv_scale: 1.0 / scale,
             even_texture,
             odd_texture,
@@ -48,6 +75,7 @@ impl CheckerTexture {
 
     pub fn from_colors(scale: f64, even_color: Color, odd_color: Color) -> Self {
         Self {
+            scale,
             inv_scale: 1.0 / scale,
             even_texture: Arc::new(SolidColor { color: even_color }),
             odd_texture: Arc::new(SolidColor { color: odd_color }),
@@ -56,7 +84,8 @@ impl CheckerTexture {
 }
 
 impl Texture for CheckerTexture {
-    fn sample(&self, uv: TexCoord, point: Vec3) -> Color {
+    fn sample(&self, rec: &HitRecord) -> Color {
+        let point = rec.position;
         let x_int = (self.inv_scale * point.x()).floor() as i64;
         let y_int = (self.inv_scale * point.y()).floor() as i64;
         let z_int = (self.inv_scale * point.z()).floor() as i64;
@@ -64,25 +93,48 @@ impl Texture for CheckerTexture {
         let is_even = (x_int + y_int + z_int) % 2 == 0;
 
         if is_even {
-            self.even_texture.sample(uv, point)
+            self.even_texture.sample(rec)
         } else {
-            self.odd_texture.sample(uv, point)
+            self.odd_texture.sample(rec)
         }
     }
 }
 
+impl DescribeTexture for CheckerTexture {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<TextureDescription, ExportError> {
+        Ok(TextureDescription::Checker {
+            scale: self.scale,
+            even: exporter.texture(&self.even_texture)?,
+            odd: exporter.texture(&self.odd_texture)?,
+        })
+    }
+}
+
 pub struct ImageTexture {
     image: RgbImage,
+    /// File the image was loaded from, if any. Used when exporting the scene.
+    path: Option<PathBuf>,
 }
 
 impl ImageTexture {
+    #[allow(unused)]
     pub fn new(image: RgbImage) -> Self {
-        Self { image }
+        Self { image, path: None }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let path = path.as_ref();
+        let image = image::open(path)?.into_rgb8();
+        Ok(Self {
+            image,
+            path: Some(path.to_owned()),
+        })
     }
 }
 
 impl Texture for ImageTexture {
-    fn sample(&self, uv: TexCoord, _point: Vec3) -> Color {
+    fn sample(&self, rec: &HitRecord) -> Color {
+        let uv = rec.uv;
         let u = uv.u.clamp(0.0, 1.0);
         let v = 1.0 - uv.v.clamp(0.0, 1.0);
 
@@ -95,3 +147,36 @@ impl Texture for ImageTexture {
         Color::new(rgb[0] as f64, rgb[1] as f64, rgb[2] as f64) * color_scale
     }
 }
+
+impl DescribeTexture for ImageTexture {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<TextureDescription, ExportError> {
+        let path = exporter.image_path(self.path.as_deref(), "image texture", |path| {
+            self.image.save(path)
+        })?;
+        Ok(TextureDescription::Image { path })
+    }
+}
+
+/// Interpolates the colors of the vertices of a triangle mesh across the hit triangle.
+///
+/// Objects without vertex colors are white.
+pub struct VertexColorTexture;
+
+impl Texture for VertexColorTexture {
+    fn sample(&self, rec: &HitRecord) -> Color {
+        match rec.vertex_colors {
+            Some(VertexColors {
+                colors: [c0, c1, c2],
+                b1,
+                b2,
+            }) => interpolate(c0, c1, c2, b1, b2),
+            None => Color::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl DescribeTexture for VertexColorTexture {
+    fn describe(&self, _exporter: &mut SceneExporter) -> Result<TextureDescription, ExportError> {
+        Ok(TextureDescription::VertexColor)
+    }
+}
@@ -0,0 +1,206 @@
+use std::ops::Mul;
+
+use crate::vec3::Vec3;
+
+/// A 4x4 matrix stored in row-major order, used for affine transformations.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Matrix4(pub [[f64; 4]; 4]);
+
+impl Matrix4 {
+    pub const IDENTITY: Self = Matrix4([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    pub fn translation(offset: Vec3) -> Self {
+        Matrix4([
+            [1.0, 0.0, 0.0, offset.0],
+            [0.0, 1.0, 0.0, offset.1],
+            [0.0, 0.0, 1.0, offset.2],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scaling(factors: Vec3) -> Self {
+        Matrix4([
+            [factors.0, 0.0, 0.0, 0.0],
+            [0.0, factors.1, 0.0, 0.0],
+            [0.0, 0.0, factors.2, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Rotation by `angle` radians about `axis`, counterclockwise when looking
+    /// against the direction of the axis.
+    pub fn rotation(axis: Vec3, angle: f64) -> Self {
+        let Vec3(x, y, z) = axis.normalize();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+
+        Matrix4([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Creates an affine transformation from the first three rows of its matrix.
+    pub fn from_affine_rows(rows: [[f64; 4]; 3]) -> Self {
+        Matrix4([rows[0], rows[1], rows[2], [0.0, 0.0, 0.0, 1.0]])
+    }
+
+    /// Returns the first three rows of the matrix, which fully describe an affine transformation.
+    pub fn affine_rows(&self) -> [[f64; 4]; 3] {
+        [self.0[0], self.0[1], self.0[2]]
+    }
+
+    /// Creates a matrix from its columns, the layout used by glTF.
+    pub fn from_columns(columns: [[f64; 4]; 4]) -> Self {
+        Matrix4(std::array::from_fn(|row| {
+            std::array::from_fn(|col| columns[col][row])
+        }))
+    }
+
+    pub fn transpose(&self) -> Self {
+        Matrix4(std::array::from_fn(|row| {
+            std::array::from_fn(|col| self.0[col][row])
+        }))
+    }
+
+    /// Transforms a point, including the translation part of the matrix.
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let m = &self.0;
+        Vec3(
+            m[0][0] * p.0 + m[0][1] * p.1 + m[0][2] * p.2 + m[0][3],
+            m[1][0] * p.0 + m[1][1] * p.1 + m[1][2] * p.2 + m[1][3],
+            m[2][0] * p.0 + m[2][1] * p.1 + m[2][2] * p.2 + m[2][3],
+        )
+    }
+
+    /// Transforms a direction, ignoring the translation part of the matrix.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.0;
+        Vec3(
+            m[0][0] * v.0 + m[0][1] * v.1 + m[0][2] * v.2,
+            m[1][0] * v.0 + m[1][1] * v.1 + m[1][2] * v.2,
+            m[2][0] * v.0 + m[2][1] * v.1 + m[2][2] * v.2,
+        )
+    }
+
+    /// Computes the inverse using cofactor expansion.
+    /// Returns `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.0;
+
+        let s0 = m[0][0] * m[1][1] - m[1][0] * m[0][1];
+        let s1 = m[0][0] * m[1][2] - m[1][0] * m[0][2];
+        let s2 = m[0][0] * m[1][3] - m[1][0] * m[0][3];
+        let s3 = m[0][1] * m[1][2] - m[1][1] * m[0][2];
+        let s4 = m[0][1] * m[1][3] - m[1][1] * m[0][3];
+        let s5 = m[0][2] * m[1][3] - m[1][2] * m[0][3];
+
+        let c5 = m[2][2] * m[3][3] - m[3][2] * m[2][3];
+        let c4 = m[2][1] * m[3][3] - m[3][1] * m[2][3];
+        let c3 = m[2][1] * m[3][2] - m[3][1] * m[2][2];
+        let c2 = m[2][0] * m[3][3] - m[3][0] * m[2][3];
+        let c1 = m[2][0] * m[3][2] - m[3][0] * m[2][2];
+        let c0 = m[2][0] * m[3][1] - m[3][0] * m[2][1];
+
+        let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        Some(Matrix4([
+            [
+                (m[1][1] * c5 - m[1][2] * c4 + m[1][3] * c3) * inv_det,
+                (-m[0][1] * c5 + m[0][2] * c4 - m[0][3] * c3) * inv_det,
+                (m[3][1] * s5 - m[3][2] * s4 + m[3][3] * s3) * inv_det,
+                (-m[2][1] * s5 + m[2][2] * s4 - m[2][3] * s3) * inv_det,
+            ],
+            [
+                (-m[1][0] * c5 + m[1][2] * c2 - m[1][3] * c1) * inv_det,
+                (m[0][0] * c5 - m[0][2] * c2 + m[0][3] * c1) * inv_det,
+                (-m[3][0] * s5 + m[3][2] * s2 - m[3][3] * s1) * inv_det,
+                (m[2][0] * s5 - m[2][2] * s2 + m[2][3] * s1) * inv_det,
+            ],
+            [
+                (m[1][0] * c4 - m[1][1] * c2 + m[1][3] * c0) * inv_det,
+                (-m[0][0] * c4 + m[0][1] * c2 - m[0][3] * c0) * inv_det,
+                (m[3][0] * s4 - m[3][1] * s2 + m[3][3] * s0) * inv_det,
+                (-m[2][0] * s4 + m[2][1] * s2 - m[2][3] * s0) * inv_det,
+            ],
+            [
+                (-m[1][0] * c3 + m[1][1] * c1 - m[1][2] * c0) * inv_det,
+                (m[0][0] * c3 - m[0][1] * c1 + m[0][2] * c0) * inv_det,
+                (-m[3][0] * s3 + m[3][1] * s1 - m[3][2] * s0) * inv_det,
+                (m[2][0] * s3 - m[2][1] * s1 + m[2][2] * s0) * inv_det,
+            ],
+        ]))
+    }
+}
+
+impl Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Matrix4) -> Self::Output {
+        Matrix4(std::array::from_fn(|row| {
+            std::array::from_fn(|col| (0..4).map(|k| self.0[row][k] * rhs.0[k][col]).sum())
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vec3::Vec3;
+
+    use super::Matrix4;
+
+    #[test]
+    fn rotation_follows_right_hand_rule() {
+        let m = Matrix4::rotation(Vec3(0.0, 0.0, 2.0), std::f64::consts::FRAC_PI_2);
+        let rotated = m.transform_vector(Vec3(1.0, 0.0, 0.0));
+        assert!((rotated - Vec3(0.0, 1.0, 0.0)).length() < 1e-12);
+
+        let m = Matrix4::rotation(Vec3(1.0, 0.0, 0.0), std::f64::consts::FRAC_PI_2);
+        let rotated = m.transform_vector(Vec3(0.0, 1.0, 0.0));
+        assert!((rotated - Vec3(0.0, 0.0, 1.0)).length() < 1e-12);
+    }
+
+    #[test]
+    fn inverse_of_product_is_identity() {
+        let m = Matrix4([
+            [2.0, 0.0, 1.0, 3.0],
+            [0.5, 1.0, 0.0, -2.0],
+            [0.0, -1.0, 3.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let product = m * m.inverse().unwrap();
+
+        for row in 0..4 {
+            for col in 0..4 {
+                let expected = if row == col { 1.0 } else { 0.0 };
+                assert!((product.0[row][col] - expected).abs() < 1e-12);
+            }
+        }
+    }
+}
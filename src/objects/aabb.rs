@@ -1,4 +1,4 @@
-use crate::{ray::Ray, util::Interval, vec3::Vec3};
+use crate::{matrix::Matrix4, ray::Ray, util::Interval, vec3::Vec3};
 
 #[derive(Clone, Copy)]
 pub struct Aabb {
@@ -8,6 +8,13 @@ pub struct Aabb {
 }
 
 impl Aabb {
+    /// Box of objects that extend infinitely, such as planes.
+    pub const UNIVERSE: Self = Self {
+        x: Interval::UNIVERSE,
+        y: Interval::UNIVERSE,
+        z: Interval::UNIVERSE,
+    };
+
     #[allow(unused)]
     pub fn empty() -> Self {
         Self {
@@ -42,6 +49,16 @@ impl Aabb {
         }
     }
 
+    /// Returns the box covered by both boxes. Boxes that do not overlap give a box of
+    /// zero size rather than an inverted one.
+    pub fn intersection(a: Aabb, b: Aabb) -> Self {
+        let overlap = |a: Interval, b: Interval| {
+            let min = a.0.max(b.0);
+            Interval(min, a.1.min(b.1).max(min))
+        };
+        Self::new(overlap(a.x, b.x), overlap(a.y, b.y), overlap(a.z, b.z))
+    }
+
     pub fn axis(&self, i: usize) -> Interval {
         match i {
             0 => self.x,
@@ -51,11 +68,86 @@ impl Aabb {
         }
     }
 
-    pub fn hit(&self, ray: &Ray, mut allowed_t: Interval) -> bool {
+    pub fn centroid(&self) -> Vec3 {
+        Vec3(
+            (self.x.0 + self.x.1) / 2.0,
+            (self.y.0 + self.y.1) / 2.0,
+            (self.z.0 + self.z.1) / 2.0,
+        )
+    }
+
+    /// Returns whether the box has a finite size, which objects need to be part of a BVH.
+    pub fn is_bounded(&self) -> bool {
+        [self.x, self.y, self.z]
+            .iter()
+            .all(|axis| axis.0.is_finite() && axis.1.is_finite())
+    }
+
+    /// Returns the smallest box containing the eight corners of this box transformed by `matrix`.
+    /// Unbounded boxes stay unbounded.
+    pub fn transform(&self, matrix: &Matrix4) -> Self {
+        if !self.is_bounded() {
+            return Self::UNIVERSE;
+        }
+
+        let mut min = Vec3(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut max = Vec3(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+
+        for i in 0..8 {
+            let corner = Vec3(
+                if i & 1 == 0 { self.x.0 } else { self.x.1 },
+                if i & 2 == 0 { self.y.0 } else { self.y.1 },
+                if i & 4 == 0 { self.z.0 } else { self.z.1 },
+            );
+            let corner = matrix.transform_point(corner);
+            for c in 0..3 {
+                min[c] = min[c].min(corner[c]);
+                max[c] = max[c].max(corner[c]);
+            }
+        }
+
+        Self::span_points(min, max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let (x, y, z) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (x * y + y * z + z * x)
+    }
+
+    #[allow(unused)]
+    pub fn hit(&self, ray: &Ray, allowed_t: Interval) -> bool {
+        self.clip(ray, allowed_t).is_some()
+    }
+
+    /// Returns the part of `allowed_t` during which the ray is inside of the box.
+    pub fn clip(&self, ray: &Ray, mut allowed_t: Interval) -> Option<Interval> {
         for i in 0..3 {
             let t0 = (self.axis(i).0 - ray.origin[i]) / ray.direction[i];
             let t1 = (self.axis(i).1 - ray.origin[i]) / ray.direction[i];
 
+            allowed_t.0 = t0.min(t1).max(allowed_t.0);
+            allowed_t.1 = t0.max(t1).min(allowed_t.1);
+
+            if allowed_t.1 <= allowed_t.0 {
+                return None;
+            }
+        }
+
+        Some(allowed_t)
+    }
+
+    /// Like `hit`, but takes the reciprocal of the ray direction, which can be computed
+    /// once per ray when testing it against many boxes.
+    pub fn hit_with_inverse_direction(
+        &self,
+        origin: Vec3,
+        inv_direction: Vec3,
+        mut allowed_t: Interval,
+    ) -> bool {
+        for i in 0..3 {
+            let t0 = (self.axis(i).0 - origin[i]) * inv_direction[i];
+            let t1 = (self.axis(i).1 - origin[i]) * inv_direction[i];
+
             let t_min = t0.min(t1);
             let t_max = t0.max(t1);
 
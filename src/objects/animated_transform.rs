@@ -0,0 +1,462 @@
+use std::sync::Arc;
+
+use crate::{
+    animation::{keyframe_weights, Interpolation, KeyframeWeights},
+    matrix::Matrix4,
+    objects::aabb::Aabb,
+    objects::hittable::{HitRecord, Hittable},
+    objects::transform::{hit_transformed, AffineStep},
+    ray::Ray,
+    scene_file::{
+        description::{ObjectDescription, TransformKeyframeDescription},
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// Minimum number of points in time between two keyframes at which the object is placed
+/// to find the bounding box of its motion.
+const BOUNDS_SAMPLES: usize = 32;
+
+/// Largest angle by which the rotations of the object may turn between two of these points.
+const MAX_SAMPLE_TURN: f64 = std::f64::consts::FRAC_PI_2;
+
+/// Upper limit on the number of points between two keyframes, reached after thousands of turns.
+const MAX_BOUNDS_SAMPLES: usize = 1 << 16;
+
+/// The steps of an affine transformation at a point in time.
+#[derive(Debug, Clone)]
+pub struct TransformKeyframe {
+    pub time: f64,
+    pub steps: Vec<AffineStep>,
+}
+
+/// An affine transformation changing over time.
+///
+/// All keyframes consist of the same kinds of steps in the same order, and the parameters of
+/// the steps are interpolated between keyframes. Before the first and after the last
+/// keyframe the transformation stays the same.
+#[derive(Debug, Clone)]
+pub struct TransformAnimation {
+    keyframes: Vec<TransformKeyframe>,
+    /// Times of the keyframes, kept apart to look up the keyframes around a time.
+    times: Vec<f64>,
+    interpolation: Interpolation,
+}
+
+impl TransformAnimation {
+    pub fn new(
+        keyframes: Vec<TransformKeyframe>,
+        interpolation: Interpolation,
+    ) -> Result<Self, String> {
+        let Some(first) = keyframes.first() else {
+            return Err("animation needs at least one keyframe".to_owned());
+        };
+
+        for pair in keyframes.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            if b.time <= a.time {
+                return Err(format!(
+                    "keyframe times must be increasing, but {} follows {}",
+                    b.time, a.time
+                ));
+            }
+        }
+
+        for keyframe in &keyframes {
+            let same_steps = keyframe.steps.len() == first.steps.len()
+                && keyframe
+                    .steps
+                    .iter()
+                    .zip(&first.steps)
+                    .all(|(a, b)| AffineStep::same_kind(a, b));
+            if !same_steps {
+                return Err(format!(
+                    "keyframe at time {} does not consist of the same steps as the first keyframe",
+                    keyframe.time
+                ));
+            }
+            if AffineStep::combine(&keyframe.steps).inverse().is_none() {
+                return Err(format!(
+                    "transform of the keyframe at time {} must be invertible",
+                    keyframe.time
+                ));
+            }
+        }
+
+        Ok(Self {
+            times: keyframes.iter().map(|keyframe| keyframe.time).collect(),
+            keyframes,
+            interpolation,
+        })
+    }
+
+    pub fn keyframes(&self) -> &[TransformKeyframe] {
+        &self.keyframes
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Returns the transformation at `time`.
+    pub fn matrix_at(&self, time: f64) -> Matrix4 {
+        let weights = keyframe_weights(&self.times, time, self.interpolation);
+        (0..self.keyframes[0].steps.len()).fold(Matrix4::IDENTITY, |matrix, i| {
+            self.step_at(i, &weights).matrix() * matrix
+        })
+    }
+
+    /// Returns the transformation at `time` together with its inverse, or `None` if it is not
+    /// invertible. The inverse is composed of the inverses of the steps, so that only steps
+    /// given as general matrices need to be inverted.
+    pub fn matrices_at(&self, time: f64) -> Option<(Matrix4, Matrix4)> {
+        let weights = keyframe_weights(&self.times, time, self.interpolation);
+        let mut matrix = Matrix4::IDENTITY;
+        let mut inverse = Matrix4::IDENTITY;
+        for i in 0..self.keyframes[0].steps.len() {
+            let step = self.step_at(i, &weights);
+            matrix = step.matrix() * matrix;
+            inverse = inverse * step.inverse_matrix()?;
+        }
+        Some((matrix, inverse))
+    }
+
+    /// Interpolates step `index` of the keyframes with the given weights.
+    fn step_at(&self, index: usize, weights: &KeyframeWeights) -> AffineStep {
+        let steps = weights
+            .iter()
+            .map(|&(keyframe, weight)| (&self.keyframes[keyframe].steps[index], weight));
+        AffineStep::weighted_sum(steps).expect("keyframes consist of the same steps")
+    }
+
+    /// Returns the times of the keyframes together with evenly spaced times in between,
+    /// close enough that the rotations turn by at most `MAX_SAMPLE_TURN` from one to the next.
+    fn sample_times(&self) -> Vec<f64> {
+        let mut times = vec![self.keyframes[0].time];
+        for (segment, pair) in self.keyframes.windows(2).enumerate() {
+            let (start, end) = (pair[0].time, pair[1].time);
+            let samples = ((self.segment_turn(segment) / MAX_SAMPLE_TURN).ceil() as usize)
+                .clamp(BOUNDS_SAMPLES, MAX_BOUNDS_SAMPLES);
+            times.extend((1..=samples).map(|i| start + (end - start) * i as f64 / samples as f64));
+        }
+        times
+    }
+
+    /// Returns an upper bound of the angle by which the rotation steps turn the object
+    /// between the keyframes `segment` and `segment + 1`.
+    fn segment_turn(&self, segment: usize) -> f64 {
+        // The keyframes weighted into the segment by `keyframe_weights`. Their spline weights
+        // change at most 3.5 times as fast as the linear ones, as the absolute derivatives of
+        // the Hermite basis functions sum up to at most 7.
+        let last = self.keyframes.len() - 1;
+        let (keyframes, speed) = match self.interpolation {
+            Interpolation::Linear => (&self.keyframes[segment..=segment + 1], 1.0),
+            Interpolation::Spline => (
+                &self.keyframes[segment.saturating_sub(1)..=(segment + 2).min(last)],
+                3.5,
+            ),
+        };
+
+        let mut turn = 0.0;
+        for step in 0..keyframes[0].steps.len() {
+            let rotations: Vec<(Vec3, f64)> = keyframes
+                .iter()
+                .filter_map(|keyframe| match keyframe.steps[step] {
+                    AffineStep::Rotate { axis, angle } => Some((axis.normalize(), angle)),
+                    _ => None,
+                })
+                .collect();
+            let mut angle_spread = 0.0_f64;
+            let mut axis_spread = 0.0_f64;
+            for &(axis_a, angle_a) in &rotations {
+                for &(axis_b, angle_b) in &rotations {
+                    angle_spread = angle_spread.max((angle_a - angle_b).abs());
+                    axis_spread = axis_spread.max(axis_a.dot(axis_b).clamp(-1.0, 1.0).acos());
+                }
+            }
+            // Tilting the axis by some angle turns the rotation by at most twice that angle.
+            turn += speed * (angle_spread + 2.0 * axis_spread);
+        }
+        turn
+    }
+}
+
+/// Places an object in the scene by a transformation that changes during the exposure,
+/// which blurs the object along its motion.
+pub struct AnimatedTransform {
+    object: Arc<dyn Hittable>,
+    animation: TransformAnimation,
+    bounding_box: Aabb,
+}
+
+impl AnimatedTransform {
+    pub fn new(object: Arc<dyn Hittable>, animation: TransformAnimation) -> Self {
+        let bounds = object.bounding_box();
+        if !bounds.is_bounded() {
+            return Self {
+                object,
+                animation,
+                bounding_box: Aabb::UNIVERSE,
+            };
+        }
+
+        let corners: Vec<Vec3> = (0..8)
+            .map(|i| {
+                Vec3(
+                    if i & 1 == 0 { bounds.x.0 } else { bounds.x.1 },
+                    if i & 2 == 0 { bounds.y.0 } else { bounds.y.1 },
+                    if i & 4 == 0 { bounds.z.0 } else { bounds.z.1 },
+                )
+            })
+            .collect();
+
+        // Between two samples a corner may move along a curve, e.g. when rotating. Such a curve
+        // strays less than the distance between the samples from the straight line between
+        // them, so growing the box by that distance covers the whole motion.
+        let mut bounding_box = Aabb::empty();
+        let mut max_step = 0.0_f64;
+        let mut previous: Option<Vec<Vec3>> = None;
+        for time in animation.sample_times() {
+            let matrix = animation.matrix_at(time);
+            let placed: Vec<Vec3> = corners.iter().map(|&c| matrix.transform_point(c)).collect();
+            for &corner in &placed {
+                bounding_box = Aabb::combine(bounding_box, Aabb::span_points(corner, corner));
+            }
+            if let Some(previous) = previous {
+                for (a, b) in previous.iter().zip(&placed) {
+                    max_step = max_step.max((*b - *a).length());
+                }
+            }
+            previous = Some(placed);
+        }
+        for axis in [
+            &mut bounding_box.x,
+            &mut bounding_box.y,
+            &mut bounding_box.z,
+        ] {
+            axis.expand(max_step);
+        }
+
+        Self {
+            object,
+            animation,
+            bounding_box,
+        }
+    }
+}
+
+impl Hittable for AnimatedTransform {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        // The transformation may pass through a singular one between two keyframes,
+        // e.g. when a scale changes sign, in which case the object is flat and cannot be hit.
+        let (matrix, inverse) = self.animation.matrices_at(ray.time)?;
+        hit_transformed(self.object.as_ref(), &matrix, &inverse, ray, allowed_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for AnimatedTransform {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::AnimatedTransform {
+            interpolation: self.animation.interpolation(),
+            keyframes: self
+                .animation
+                .keyframes()
+                .iter()
+                .map(|keyframe| TransformKeyframeDescription {
+                    time: keyframe.time,
+                    transform: keyframe.steps.iter().map(AffineStep::describe).collect(),
+                })
+                .collect(),
+            object: Box::new(self.object.describe(exporter)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        animation::Interpolation,
+        materials::material::MaterialRef,
+        matrix::Matrix4,
+        objects::{cube::cube, hittable::Hittable, sphere::Sphere, transform::AffineStep},
+        ray::Ray,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::{AnimatedTransform, TransformAnimation, TransformKeyframe};
+
+    #[test]
+    fn follows_keyframes_over_time() {
+        let sphere = Arc::new(Sphere::stationary(Vec3(0.0, 0.0, 0.0), 1.0, MaterialRef(0)));
+        let animation = TransformAnimation::new(
+            vec![
+                TransformKeyframe {
+                    time: 0.0,
+                    steps: vec![AffineStep::Translate(Vec3(0.0, 0.0, 0.0))],
+                },
+                TransformKeyframe {
+                    time: 1.0,
+                    steps: vec![AffineStep::Translate(Vec3(4.0, 0.0, 0.0))],
+                },
+            ],
+            Interpolation::Linear,
+        )
+        .unwrap();
+        let moving = AnimatedTransform::new(sphere, animation);
+
+        for (time, x) in [(0.0, 0.0), (0.25, 1.0), (1.0, 4.0), (2.0, 4.0)] {
+            let ray = Ray::new(Vec3(x, 0.0, 10.0), Vec3(0.0, 0.0, -1.0), time);
+            let rec = moving.hit(&ray, Interval(0.001, f64::INFINITY)).unwrap();
+            assert!((rec.t - 9.0).abs() < 1e-9);
+            assert!((rec.position - Vec3(x, 0.0, 1.0)).length() < 1e-9);
+        }
+
+        let bounds = moving.bounding_box();
+        assert!(bounds.x.0 <= -1.0 && bounds.x.1 >= 5.0);
+    }
+
+    #[test]
+    fn bounding_box_covers_rotation() {
+        let object = Arc::new(cube(
+            Vec3(1.0, -0.5, -0.5),
+            Vec3(3.0, 0.5, 0.5),
+            MaterialRef(0),
+        ));
+        let rotation = |angle| AffineStep::Rotate {
+            axis: Vec3(0.0, 1.0, 0.0),
+            angle,
+        };
+        let animation = TransformAnimation::new(
+            vec![
+                TransformKeyframe {
+                    time: 0.0,
+                    steps: vec![rotation(0.0)],
+                },
+                TransformKeyframe {
+                    time: 1.0,
+                    steps: vec![rotation(std::f64::consts::PI)],
+                },
+            ],
+            Interpolation::Linear,
+        )
+        .unwrap();
+        let bounds = AnimatedTransform::new(object, animation.clone()).bounding_box();
+
+        for i in 0..=1000 {
+            let matrix = animation.matrix_at(i as f64 / 1000.0);
+            let far_corner = matrix.transform_point(Vec3(3.0, 0.5, 0.5));
+            assert!(bounds.x.contains(far_corner.x()) && bounds.z.contains(far_corner.z()));
+        }
+    }
+
+    #[test]
+    fn bounds_cover_many_full_turns() {
+        // After a multiple of 32 turns every evenly spaced sample would find the object
+        // in the same place.
+        let object = Arc::new(Sphere::stationary(Vec3(3.0, 0.0, 0.0), 0.5, MaterialRef(0)));
+        let rotation = |angle| AffineStep::Rotate {
+            axis: Vec3(0.0, 1.0, 0.0),
+            angle,
+        };
+        let turns = 64.0 * std::f64::consts::TAU;
+        for interpolation in [Interpolation::Linear, Interpolation::Spline] {
+            let animation = TransformAnimation::new(
+                vec![
+                    TransformKeyframe {
+                        time: 0.0,
+                        steps: vec![rotation(0.0)],
+                    },
+                    TransformKeyframe {
+                        time: 1.0,
+                        steps: vec![rotation(turns)],
+                    },
+                ],
+                interpolation,
+            )
+            .unwrap();
+            let bounds = AnimatedTransform::new(object.clone(), animation).bounding_box();
+            assert!(bounds.x.0 <= -3.5 && bounds.z.0 <= -3.5 && bounds.z.1 >= 3.5);
+        }
+    }
+
+    #[test]
+    fn composed_inverse_matches_inverted_matrix() {
+        let keyframe = |time, scale: f64, angle, offset| TransformKeyframe {
+            time,
+            steps: vec![
+                AffineStep::Scale(Vec3(scale, 2.0, 0.5)),
+                AffineStep::Matrix(Matrix4::rotation(Vec3(1.0, 0.0, 0.0), angle)),
+                AffineStep::Rotate {
+                    axis: Vec3(1.0, 2.0, 3.0),
+                    angle,
+                },
+                AffineStep::Translate(offset),
+            ],
+        };
+        let keyframes = vec![
+            keyframe(0.0, 1.0, 0.0, Vec3(0.0, 0.0, 0.0)),
+            keyframe(0.5, 3.0, 1.0, Vec3(1.0, -2.0, 0.0)),
+            keyframe(1.5, 0.5, -2.0, Vec3(4.0, 0.0, 3.0)),
+        ];
+
+        for interpolation in [Interpolation::Linear, Interpolation::Spline] {
+            let animation = TransformAnimation::new(keyframes.clone(), interpolation).unwrap();
+            for i in -2..=20 {
+                let time = i as f64 / 10.0;
+                let (matrix, inverse) = animation.matrices_at(time).unwrap();
+                let expected = animation.matrix_at(time);
+                let expected_inverse = expected.inverse().unwrap();
+                for row in 0..4 {
+                    for col in 0..4 {
+                        assert!((matrix.0[row][col] - expected.0[row][col]).abs() < 1e-12);
+                        assert!((inverse.0[row][col] - expected_inverse.0[row][col]).abs() < 1e-9);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn flat_transformations_cannot_be_hit() {
+        let sphere = Arc::new(Sphere::stationary(Vec3(0.0, 0.0, 0.0), 1.0, MaterialRef(0)));
+        let scale = |x| TransformKeyframe {
+            time: x,
+            steps: vec![AffineStep::Scale(Vec3(x, 1.0, 1.0))],
+        };
+        let animation =
+            TransformAnimation::new(vec![scale(-1.0), scale(1.0)], Interpolation::Linear).unwrap();
+        assert!(animation.matrices_at(0.0).is_none());
+
+        let moving = AnimatedTransform::new(sphere, animation);
+        let ray = Ray::new(Vec3(0.0, 0.0, 10.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        assert!(moving.hit(&ray, Interval(0.001, f64::INFINITY)).is_none());
+    }
+
+    #[test]
+    fn rejects_mismatched_keyframes() {
+        let result = TransformAnimation::new(
+            vec![
+                TransformKeyframe {
+                    time: 0.0,
+                    steps: vec![AffineStep::Translate(Vec3(0.0, 0.0, 0.0))],
+                },
+                TransformKeyframe {
+                    time: 1.0,
+                    steps: vec![AffineStep::Scale(Vec3(1.0, 1.0, 1.0))],
+                },
+            ],
+            Interpolation::Linear,
+        );
+        assert!(result.is_err());
+    }
+}
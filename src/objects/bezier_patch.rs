@@ -0,0 +1,285 @@
+use crate::{
+    materials::{material::MaterialRef, texture::TexCoord},
+    objects::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        triangle::intersect_triangle,
+    },
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// Number of parts along each parameter the patch is split into for finding hits.
+const SUBDIVISIONS: usize = 8;
+/// Newton steps after which a hit that has not converged counts as a miss.
+const MAX_ITERATIONS: usize = 12;
+/// Distance between the surface and the ray below which they count as intersecting.
+const TOLERANCE: f64 = 1e-7;
+
+/// Cubic Bernstein polynomials and their derivatives at `t`.
+fn bernstein(t: f64) -> ([f64; 4], [f64; 4]) {
+    let s = 1.0 - t;
+    (
+        [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t],
+        [
+            -3.0 * s * s,
+            3.0 * s * s - 6.0 * t * s,
+            6.0 * t * s - 3.0 * t * t,
+            3.0 * t * t,
+        ],
+    )
+}
+
+/// Control points of the part of a cubic Bézier curve between `a` and `b`, by de Casteljau's algorithm.
+fn sub_curve(points: [Vec3; 4], a: f64, b: f64) -> [Vec3; 4] {
+    let lerp = |p: Vec3, q: Vec3, t: f64| p + (q - p) * t;
+    // Splitting at `b` and keeping the first part, then splitting that at `a` relative to `b`
+    // and keeping the second part.
+    let split = |[p0, p1, p2, p3]: [Vec3; 4], t: f64| {
+        let (q0, q1, q2) = (lerp(p0, p1, t), lerp(p1, p2, t), lerp(p2, p3, t));
+        let (r0, r1) = (lerp(q0, q1, t), lerp(q1, q2, t));
+        let s = lerp(r0, r1, t);
+        ([p0, q0, r0, s], [s, r1, q2, p3])
+    };
+
+    let (first, _) = split(points, b);
+    if b == 0.0 {
+        return first;
+    }
+    let (_, second) = split(first, a / b);
+    second
+}
+
+/// A part of a patch, with the box around its control points that contains all of it.
+struct SubPatch {
+    u: Interval,
+    v: Interval,
+    corners: [Vec3; 4],
+    bounding_box: Aabb,
+}
+
+/// A bicubic Bézier patch, e.g. one of the 32 patches of the Utah teapot.
+///
+/// The 16 control points are given in rows of four, each row along `u` and the rows along `v`.
+/// The patch is split into parts whose boxes narrow down where a ray may hit it. The two triangles
+/// between the corners of each part give a first guess of the hit, which Newton's method then
+/// moves onto the actual surface, so normals and texture coordinates are exact.
+pub struct BezierPatch {
+    pub points: [Vec3; 16],
+    pub material: MaterialRef,
+
+    parts: Vec<SubPatch>,
+    bounding_box: Aabb,
+}
+
+impl BezierPatch {
+    pub fn new(points: [Vec3; 16], material: MaterialRef) -> Self {
+        let mut patch = Self {
+            points,
+            material,
+            parts: vec![],
+            bounding_box: Aabb::empty(),
+        };
+
+        let step = 1.0 / SUBDIVISIONS as f64;
+        for i in 0..SUBDIVISIONS {
+            for j in 0..SUBDIVISIONS {
+                let u = Interval(j as f64 * step, (j + 1) as f64 * step);
+                let v = Interval(i as f64 * step, (i + 1) as f64 * step);
+
+                let rows: [[Vec3; 4]; 4] = std::array::from_fn(|row| {
+                    sub_curve(std::array::from_fn(|k| points[row * 4 + k]), u.0, u.1)
+                });
+                let bounding_box = (0..4)
+                    .flat_map(|k| sub_curve(rows.map(|row| row[k]), v.0, v.1))
+                    .map(|p| Aabb::span_points(p, p))
+                    .fold(Aabb::empty(), Aabb::combine);
+
+                let corners = [(u.0, v.0), (u.1, v.0), (u.1, v.1), (u.0, v.1)]
+                    .map(|(u, v)| patch.evaluate(u, v).0);
+                patch.bounding_box = Aabb::combine(patch.bounding_box, bounding_box);
+                patch.parts.push(SubPatch {
+                    u,
+                    v,
+                    corners,
+                    bounding_box,
+                });
+            }
+        }
+        patch
+    }
+
+    /// Returns the point at `(u, v)` and the derivatives along `u` and `v`.
+    fn evaluate(&self, u: f64, v: f64) -> (Vec3, Vec3, Vec3) {
+        let (bu, du) = bernstein(u);
+        let (bv, dv) = bernstein(v);
+        let mut point = Vec3(0.0, 0.0, 0.0);
+        let mut along_u = Vec3(0.0, 0.0, 0.0);
+        let mut along_v = Vec3(0.0, 0.0, 0.0);
+        for (i, row) in self.points.chunks_exact(4).enumerate() {
+            for (j, &p) in row.iter().enumerate() {
+                point += p * (bu[j] * bv[i]);
+                along_u += p * (du[j] * bv[i]);
+                along_v += p * (bu[j] * dv[i]);
+            }
+        }
+        (point, along_u, along_v)
+    }
+
+    /// Normal at `(u, v)`. At degenerate corners, such as the top of the teapot's lid where
+    /// a whole edge meets in one point, the normal is taken from slightly inside of the patch.
+    fn normal(&self, u: f64, v: f64) -> Vec3 {
+        let (_, along_u, along_v) = self.evaluate(u, v);
+        let normal = along_u.cross(along_v);
+        if normal.length_squared() > 1e-20 {
+            return normal.normalize();
+        }
+
+        let nudge = |t: f64| t + (0.5 - t) * 1e-4;
+        let (_, along_u, along_v) = self.evaluate(nudge(u), nudge(v));
+        let normal = along_u.cross(along_v);
+        if normal.near_zero() {
+            Vec3(0.0, 1.0, 0.0)
+        } else {
+            normal.normalize()
+        }
+    }
+
+    /// Moves a guess of a hit onto the surface by solving `P(u, v) = origin + t direction`
+    /// with Newton's method.
+    fn refine(&self, ray: &Ray, (mut u, mut v, mut t): (f64, f64, f64)) -> Option<(f64, f64, f64)> {
+        for _ in 0..MAX_ITERATIONS {
+            let (point, along_u, along_v) = self.evaluate(u, v);
+            let error = ray.at(t) - point;
+            if error.length_squared() < TOLERANCE * TOLERANCE {
+                return Some((u, v, t));
+            }
+
+            // Solves along_u du + along_v dv - direction dt = error by Cramer's rule.
+            let back = -ray.direction;
+            let det = along_u.dot(along_v.cross(back));
+            if det.abs() < 1e-14 {
+                return None;
+            }
+            u += error.dot(along_v.cross(back)) / det;
+            v += along_u.dot(error.cross(back)) / det;
+            t += along_u.dot(along_v.cross(error)) / det;
+        }
+        None
+    }
+}
+
+impl Hittable for BezierPatch {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let mut range = allowed_t;
+        let mut closest = None;
+
+        for part in &self.parts {
+            let Some(inside) = part.bounding_box.clip(ray, range) else {
+                continue;
+            };
+
+            // Guess from the triangles between the corners of the part, or its middle.
+            let [c00, c10, c11, c01] = part.corners;
+            let (du, dv) = (part.u.size(), part.v.size());
+            let guess = if let Some((t, b1, b2)) = intersect_triangle(c00, c10, c11, ray, inside) {
+                (part.u.0 + (b1 + b2) * du, part.v.0 + b2 * dv, t)
+            } else if let Some((t, b1, b2)) = intersect_triangle(c00, c11, c01, ray, inside) {
+                (part.u.0 + b1 * du, part.v.0 + (b1 + b2) * dv, t)
+            } else {
+                (
+                    part.u.0 + 0.5 * du,
+                    part.v.0 + 0.5 * dv,
+                    0.5 * (inside.0 + inside.1),
+                )
+            };
+
+            let Some((u, v, t)) = self.refine(ray, guess) else {
+                continue;
+            };
+            // Newton's method may wander off to another part of the surface or beyond its edges.
+            let margin = 1e-6;
+            let within = |t: f64, range: Interval| range.0 - margin <= t && t <= range.1 + margin;
+            if within(u, part.u) && within(v, part.v) && range.surrounds(t) {
+                closest = Some((u.clamp(0.0, 1.0), v.clamp(0.0, 1.0), t));
+                range.1 = t;
+            }
+        }
+
+        let (u, v, t) = closest?;
+        let (_, along_u, along_v) = self.evaluate(u, v);
+        Some(
+            HitRecord::with_face_normal(
+                *ray,
+                t,
+                ray.at(t),
+                TexCoord::new(u, v),
+                self.normal(u, v),
+                self.material,
+            )
+            .with_tangents(along_u, along_v),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for BezierPatch {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::BezierPatch {
+            points: self.points.to_vec(),
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef, objects::hittable::Hittable, ray::Ray, util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::BezierPatch;
+
+    #[test]
+    fn hits_curved_patch() {
+        // A patch over the unit square in x and z, bulging up to a height of 9/16 * 4/3 = 0.75
+        // in the middle from the inner control points at a height of 4/3.
+        let points = std::array::from_fn(|i| {
+            let (row, column) = (i / 4, i % 4);
+            let inner = (1..3).contains(&row) && (1..3).contains(&column);
+            Vec3(
+                column as f64 / 3.0,
+                if inner { 4.0 / 3.0 } else { 0.0 },
+                row as f64 / 3.0,
+            )
+        });
+        let patch = BezierPatch::new(points, MaterialRef(0));
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        let ray = Ray::new(Vec3(0.5, 5.0, 0.5), Vec3(0.0, -1.0, 0.0), 0.0);
+        let rec = patch.hit(&ray, allowed_t).unwrap();
+        assert!((rec.position.y() - 0.75).abs() < 1e-6);
+        assert!((rec.uv.u - 0.5).abs() < 1e-6 && (rec.uv.v - 0.5).abs() < 1e-6);
+        assert!((rec.normal - Vec3(0.0, 1.0, 0.0)).length() < 1e-6);
+
+        // Away from the middle the surface slopes down towards its edges.
+        let ray = Ray::new(Vec3(0.2, 5.0, 0.5), Vec3(0.0, -1.0, 0.0), 0.0);
+        let rec = patch.hit(&ray, allowed_t).unwrap();
+        assert!(rec.normal.x() < 0.0 && rec.normal.y() > 0.0);
+        let bounds = patch.bounding_box();
+        assert!((0..3).all(|i| bounds.axis(i).contains(rec.position[i])));
+
+        let beside = Ray::new(Vec3(1.5, 5.0, 0.5), Vec3(0.0, -1.0, 0.0), 0.0);
+        assert!(patch.hit(&beside, allowed_t).is_none());
+    }
+}
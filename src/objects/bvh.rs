@@ -1,113 +1,849 @@
-use std::{cmp::Ordering, sync::Arc};
+use std::{
+    fmt,
+    ops::Range,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use rand::Rng;
+use rayon::prelude::*;
 
 use crate::{
     objects::aabb::Aabb,
     objects::hittable::{HitRecord, Hittable},
     ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
     util::Interval,
+    vec3::Vec3,
 };
 
+/// Estimated cost of testing a ray against the bounding box of a node, relative to
+/// the cost of intersecting an object. Used by the surface area heuristic.
+const TRAVERSAL_COST: f64 = 1.0;
+const INTERSECTION_COST: f64 = 1.0;
+
+/// Number of bins the objects are sorted into along each axis when evaluating SAH splits.
+const BIN_COUNT: usize = 16;
+
+/// Maximum number of objects the scene BVH puts into a single leaf.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Maximum depth up to which the SAH builder is used. Below it primitives are split in half,
+/// which bounds the depth of the tree and with it the traversal stack.
+const MAX_SAH_DEPTH: usize = 48;
+
+/// Size of the traversal stack, enough for trees built from up to 2^32 primitives.
+const STACK_SIZE: usize = 96;
+
+/// Subtrees with fewer primitives than this are built on the current thread.
+const PARALLEL_THRESHOLD: usize = 1024;
+
+/// Strategy used to divide the objects of a node between its two children.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BvhBuilder {
+    /// Splits the objects in half along a randomly chosen axis.
+    Median,
+    /// Splits the objects where the surface area heuristic estimates the lowest cost
+    /// of tracing rays through the node. Produces the same tree on every run.
+    #[default]
+    Sah,
+}
+
+impl fmt::Display for BvhBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BvhBuilder::Median => write!(f, "median"),
+            BvhBuilder::Sah => write!(f, "sah"),
+        }
+    }
+}
+
+/// Statistics about the shape of a BVH, collected while building it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BvhStats {
+    /// Number of interior nodes.
+    pub nodes: usize,
+    pub leaves: usize,
+    pub objects: usize,
+    pub max_depth: usize,
+    pub min_leaf_size: usize,
+    pub max_leaf_size: usize,
+    /// Expected cost of tracing a ray through the tree, relative to intersecting a single object.
+    pub sah_cost: f64,
+    /// Time it took to build the tree.
+    pub build_time: Duration,
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "BVH nodes: {}, leaves: {}, depth: {}",
+            self.nodes, self.leaves, self.max_depth
+        )?;
+        writeln!(
+            f,
+            "BVH leaf sizes: min {}, max {}, average {:.2}",
+            self.min_leaf_size,
+            self.max_leaf_size,
+            self.objects as f64 / self.leaves.max(1) as f64
+        )?;
+        write!(f, "BVH estimated SAH cost: {:.2}", self.sah_cost)
+    }
+}
+
+impl BvhStats {
+    /// Adds the statistics of a separate tree, such as the BVH of a mesh in the scene.
+    /// The SAH cost stays that of `self`, as the costs of different trees are relative to
+    /// their own bounds.
+    pub fn merge(&mut self, other: &BvhStats) {
+        if other.leaves == 0 {
+            self.build_time += other.build_time;
+            return;
+        }
+        if self.leaves == 0 {
+            self.min_leaf_size = other.min_leaf_size;
+        }
+        self.nodes += other.nodes;
+        self.leaves += other.leaves;
+        self.objects += other.objects;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.min_leaf_size = self.min_leaf_size.min(other.min_leaf_size);
+        self.max_leaf_size = self.max_leaf_size.max(other.max_leaf_size);
+        self.build_time += other.build_time;
+    }
+
+    fn leaf(size: usize, area: f64, depth: usize) -> Self {
+        Self {
+            nodes: 0,
+            leaves: 1,
+            objects: size,
+            max_depth: depth,
+            min_leaf_size: size,
+            max_leaf_size: size,
+            sah_cost: INTERSECTION_COST * size as f64 * area,
+            build_time: Duration::ZERO,
+        }
+    }
+
+    fn interior(area: f64, first: Self, second: Self) -> Self {
+        Self {
+            nodes: first.nodes + second.nodes + 1,
+            leaves: first.leaves + second.leaves,
+            objects: first.objects + second.objects,
+            max_depth: first.max_depth.max(second.max_depth),
+            min_leaf_size: first.min_leaf_size.min(second.min_leaf_size),
+            max_leaf_size: first.max_leaf_size.max(second.max_leaf_size),
+            sah_cost: first.sah_cost + second.sah_cost + TRAVERSAL_COST * area,
+            build_time: Duration::ZERO,
+        }
+    }
+}
+
+/// Node of a flattened BVH. The first child of an interior node directly follows its parent,
+/// leaves refer to a contiguous range of primitives.
+#[derive(Clone, Copy)]
+pub struct BvhNode {
+    pub bounding_box: Aabb,
+    /// For leaves the index of the first primitive, for interior nodes the index of the second child.
+    pub offset: u32,
+    /// Number of primitives in a leaf, zero for interior nodes.
+    pub count: u32,
+    /// Axis along which the children of an interior node were split.
+    pub axis: u8,
+}
+
+/// A bounding volume hierarchy over arbitrary objects, stored as a flat list of nodes.
 pub struct Bvh {
-    left: Arc<dyn Hittable>,
-    right: Arc<dyn Hittable>,
-    bounding_box: Aabb,
+    /// The objects, ordered such that every leaf refers to a contiguous range.
+    objects: Vec<Arc<dyn Hittable>>,
+    nodes: Vec<BvhNode>,
+    /// Objects without a finite bounding box, such as planes, which are kept out of
+    /// the tree and tested against every ray.
+    unbounded: Vec<Arc<dyn Hittable>>,
 }
 
 impl Bvh {
-    pub fn new(objects: &[Arc<dyn Hittable>]) -> Self {
+    /// Builds a BVH over `objects` using the given strategy.
+    pub fn build(objects: &[Arc<dyn Hittable>], builder: BvhBuilder) -> (Self, BvhStats) {
+        let start = Instant::now();
         assert!(
             !objects.is_empty(),
             "cannot create BVH from empty list of objects"
         );
 
-        let mut objects = objects.to_vec();
+        let (objects, unbounded): (Vec<_>, Vec<_>) = objects
+            .iter()
+            .cloned()
+            .partition(|object| object.bounding_box().is_bounded());
+        if objects.is_empty() {
+            let bvh = Bvh {
+                objects,
+                nodes: vec![],
+                unbounded,
+            };
+            let stats = BvhStats {
+                build_time: start.elapsed(),
+                ..BvhStats::default()
+            };
+            return (bvh, stats);
+        }
 
-        let axis = rand::thread_rng().gen_range(0..=2);
-        let comparator = match axis {
-            0 => box_x_compare,
-            1 => box_y_compare,
-            2 => box_z_compare,
-            _ => unreachable!(),
-        };
+        let bounds: Vec<Aabb> = objects.iter().map(|o| o.bounding_box()).collect();
+        let (nodes, order, mut stats) = build_nodes(&bounds, builder, MAX_LEAF_SIZE);
+        let objects = order
+            .iter()
+            .map(|&i| Arc::clone(&objects[i as usize]))
+            .collect();
+        stats.build_time = start.elapsed();
 
-        let left;
-        let right;
+        (
+            Bvh {
+                objects,
+                nodes,
+                unbounded,
+            },
+            stats,
+        )
+    }
+}
 
-        if objects.len() == 1 {
-            left = Arc::clone(&objects[0]);
-            right = Arc::clone(&objects[0]);
-        } else if objects.len() == 2 {
-            if comparator(objects[0].as_ref(), objects[1].as_ref()).is_lt() {
-                left = Arc::clone(&objects[0]);
-                right = Arc::clone(&objects[1]);
-            } else {
-                left = Arc::clone(&objects[1]);
-                right = Arc::clone(&objects[0]);
-            }
+/// Builds a flattened BVH over primitives with the given bounding boxes.
+///
+/// Returns the nodes, the indices of the primitives in the order the leaves refer to them
+/// and statistics about the tree, including the time it took. Large subtrees are built in parallel.
+pub fn build_nodes(
+    bounds: &[Aabb],
+    builder: BvhBuilder,
+    max_leaf_size: usize,
+) -> (Vec<BvhNode>, Vec<u32>, BvhStats) {
+    let start = Instant::now();
+    let mut primitives: Vec<Primitive> = bounds
+        .par_iter()
+        .enumerate()
+        .map(|(index, bounding_box)| Primitive {
+            bounding_box: *bounding_box,
+            centroid: bounding_box.centroid(),
+            index: index as u32,
+        })
+        .collect();
+
+    // A tree over n primitives has at most 2n - 1 nodes. Reserving that many up front
+    // gives every subtree a fixed range of nodes, so that subtrees can be built in parallel.
+    let unused = BvhNode {
+        bounding_box: Aabb::empty(),
+        offset: 0,
+        count: 0,
+        axis: 0,
+    };
+    let mut nodes = vec![unused; 2 * primitives.len() - 1];
+
+    let builder = Builder {
+        kind: builder,
+        max_leaf_size,
+    };
+    let bounds = Bounds::of(&primitives);
+    let mut stats = builder.build(&mut primitives, bounds, &mut nodes, 0, 0, 0);
+    stats.sah_cost /= nodes[0].bounding_box.surface_area();
+
+    // Leaves with multiple primitives leave some of the reserved nodes unused.
+    let mut compacted = Vec::with_capacity(stats.nodes + stats.leaves);
+    compact(&nodes, 0, &mut compacted);
+
+    let order = primitives.iter().map(|p| p.index).collect();
+    stats.build_time = start.elapsed();
+
+    (compacted, order, stats)
+}
+
+/// Copies the subtree starting at `nodes[index]` to the end of `compacted`.
+fn compact(nodes: &[BvhNode], index: usize, compacted: &mut Vec<BvhNode>) {
+    let node = nodes[index];
+    let new_index = compacted.len();
+    compacted.push(node);
+
+    if node.count == 0 {
+        compact(nodes, index + 1, compacted);
+        compacted[new_index].offset = compacted.len() as u32;
+        compact(nodes, node.offset as usize, compacted);
+    }
+}
+
+struct Primitive {
+    bounding_box: Aabb,
+    centroid: Vec3,
+    /// Index of the primitive in the list passed to `build_nodes`.
+    index: u32,
+}
+
+/// Bounding box of a set of primitives together with the bounds of their centroids.
+#[derive(Clone, Copy)]
+struct Bounds {
+    bounding_box: Aabb,
+    centroids: Aabb,
+}
+
+impl Bounds {
+    const EMPTY: Self = Bounds {
+        bounding_box: Aabb {
+            x: Interval::EMPTY,
+            y: Interval::EMPTY,
+            z: Interval::EMPTY,
+        },
+        centroids: Aabb {
+            x: Interval::EMPTY,
+            y: Interval::EMPTY,
+            z: Interval::EMPTY,
+        },
+    };
+
+    fn of(primitives: &[Primitive]) -> Self {
+        let fold = |primitives: &[Primitive]| {
+            primitives.iter().fold(Self::EMPTY, |mut bounds, p| {
+                bounds.add(p);
+                bounds
+            })
+        };
+        if primitives.len() >= PARALLEL_THRESHOLD {
+            primitives
+                .par_chunks(PARALLEL_THRESHOLD)
+                .map(fold)
+                .reduce(|| Self::EMPTY, Self::combine)
         } else {
-            objects.sort_by(|a, b| comparator(a.as_ref(), b.as_ref()));
-            let mid = objects.len() / 2;
-            left = Arc::new(Bvh::new(&objects[..mid]));
-            right = Arc::new(Bvh::new(&objects[mid..]));
+            fold(primitives)
         }
+    }
 
-        let bounding_box = Aabb::combine(left.bounding_box(), right.bounding_box());
+    fn add(&mut self, primitive: &Primitive) {
+        let c = primitive.centroid;
+        self.bounding_box = Aabb::combine(self.bounding_box, primitive.bounding_box);
+        self.centroids = Aabb::combine(
+            self.centroids,
+            Aabb {
+                x: Interval(c.x(), c.x()),
+                y: Interval(c.y(), c.y()),
+                z: Interval(c.z(), c.z()),
+            },
+        );
+    }
 
+    fn combine(a: Self, b: Self) -> Self {
         Self {
-            left,
-            right,
-            bounding_box,
+            bounding_box: Aabb::combine(a.bounding_box, b.bounding_box),
+            centroids: Aabb::combine(a.centroids, b.centroids),
         }
     }
 }
 
-impl Hittable for Bvh {
-    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
-        if !self.bounding_box.hit(ray, allowed_t) {
-            return None;
-        }
+/// The primitives to be split and the bounds of both children.
+struct Split {
+    mid: usize,
+    axis: usize,
+    first: Bounds,
+    second: Bounds,
+}
+
+struct Builder {
+    kind: BvhBuilder,
+    max_leaf_size: usize,
+}
 
-        let hit_left = self.left.hit(ray, allowed_t);
+impl Builder {
+    /// Builds the subtree over `primitives` into `nodes`, which must have room for
+    /// `2 * primitives.len() - 1` nodes. `first_primitive` and `first_node` are the indices
+    /// of the first primitive and node of the subtree in the complete tree.
+    fn build(
+        &self,
+        primitives: &mut [Primitive],
+        bounds: Bounds,
+        nodes: &mut [BvhNode],
+        first_primitive: usize,
+        first_node: usize,
+        depth: usize,
+    ) -> BvhStats {
+        let bounding_box = bounds.bounding_box;
+        let area = bounding_box.surface_area();
 
-        // If the left bounding volume was hit we still need to
-        // check the right one in case it contains an object that is further in front.
-        // But we can set its max distance to be where we hit the left volume already.
-        let max = if let Some(ref rec) = hit_left {
-            rec.t
+        let Some(split) = self.split(primitives, &bounds, depth) else {
+            nodes[0] = BvhNode {
+                bounding_box,
+                offset: first_primitive as u32,
+                count: primitives.len() as u32,
+                axis: 0,
+            };
+            return BvhStats::leaf(primitives.len(), area, depth);
+        };
+
+        let mid = split.mid;
+        let parallel = primitives.len() >= PARALLEL_THRESHOLD;
+        let (node, children) = nodes.split_first_mut().unwrap();
+        let (first_nodes, second_nodes) = children.split_at_mut(2 * mid - 1);
+        let (first, second) = primitives.split_at_mut(mid);
+        *node = BvhNode {
+            bounding_box,
+            offset: (first_node + 2 * mid) as u32,
+            count: 0,
+            axis: split.axis as u8,
+        };
+
+        let mut build_first = || {
+            self.build(
+                first,
+                split.first,
+                first_nodes,
+                first_primitive,
+                first_node + 1,
+                depth + 1,
+            )
+        };
+        let mut build_second = || {
+            self.build(
+                second,
+                split.second,
+                second_nodes,
+                first_primitive + mid,
+                first_node + 2 * mid,
+                depth + 1,
+            )
+        };
+        let (first, second) = if parallel {
+            rayon::join(build_first, build_second)
         } else {
-            allowed_t.1
+            (build_first(), build_second())
         };
-        let hit_right = self.right.hit(ray, Interval(allowed_t.0, max));
 
-        if let Some(rec) = hit_right {
-            Some(rec)
+        BvhStats::interior(area, first, second)
+    }
+
+    /// Reorders `primitives` so that the primitives of the first child come first,
+    /// or returns `None` if they should be kept in a single leaf.
+    fn split(&self, primitives: &mut [Primitive], bounds: &Bounds, depth: usize) -> Option<Split> {
+        if primitives.len() == 1 {
+            return None;
+        }
+
+        match self.kind {
+            BvhBuilder::Sah if depth < MAX_SAH_DEPTH => {
+                sah_split(primitives, bounds, self.max_leaf_size)
+            }
+            _ if primitives.len() <= self.max_leaf_size => None,
+            BvhBuilder::Sah => {
+                let axis = (0..3)
+                    .max_by(|&a, &b| {
+                        let (a, b) = (bounds.centroids.axis(a), bounds.centroids.axis(b));
+                        a.size().total_cmp(&b.size())
+                    })
+                    .unwrap();
+                let mid = primitives.len() / 2;
+                primitives.select_nth_unstable_by(mid, |a, b| {
+                    a.centroid[axis].total_cmp(&b.centroid[axis])
+                });
+                Some(split_at(primitives, mid, axis))
+            }
+            BvhBuilder::Median => {
+                let axis = rand::thread_rng().gen_range(0..=2);
+                let mid = primitives.len() / 2;
+                primitives.select_nth_unstable_by(mid, |a, b| {
+                    let (a, b) = (a.bounding_box.axis(axis), b.bounding_box.axis(axis));
+                    a.0.total_cmp(&b.0)
+                });
+                Some(split_at(primitives, mid, axis))
+            }
+        }
+    }
+}
+
+fn split_at(primitives: &[Primitive], mid: usize, axis: usize) -> Split {
+    Split {
+        mid,
+        axis,
+        first: Bounds::of(&primitives[..mid]),
+        second: Bounds::of(&primitives[mid..]),
+    }
+}
+
+/// Bounding box and number of primitives of every bin along each axis.
+type Bins = [[(Aabb, usize); BIN_COUNT]; 3];
+
+/// Finds the split with the lowest estimated cost among the boundaries of
+/// equally sized bins along each axis of the centroid bounds.
+fn sah_split(primitives: &mut [Primitive], bounds: &Bounds, max_leaf_size: usize) -> Option<Split> {
+    if primitives.len() <= BIN_COUNT {
+        return sweep_split(primitives, bounds, max_leaf_size);
+    }
+
+    let centroids = bounds.centroids;
+    let scale = [0, 1, 2].map(|axis| {
+        let size = centroids.axis(axis).size();
+        if size > 0.0 {
+            BIN_COUNT as f64 / size
         } else {
-            hit_left
+            0.0
+        }
+    });
+    let bin_index = |centroid: Vec3, axis: usize| {
+        let offset = (centroid[axis] - centroids.axis(axis).0) * scale[axis];
+        (offset as usize).min(BIN_COUNT - 1)
+    };
+
+    let fill_bins = |primitives: &[Primitive]| {
+        let mut bins: Bins = [[(Aabb::empty(), 0); BIN_COUNT]; 3];
+        for primitive in primitives {
+            for (axis, axis_bins) in bins.iter_mut().enumerate() {
+                let bin = &mut axis_bins[bin_index(primitive.centroid, axis)];
+                bin.0 = Aabb::combine(bin.0, primitive.bounding_box);
+                bin.1 += 1;
+            }
+        }
+        bins
+    };
+    let bins = if primitives.len() >= PARALLEL_THRESHOLD {
+        primitives
+            .par_chunks(PARALLEL_THRESHOLD)
+            .map(fill_bins)
+            .reduce_with(|mut a, b| {
+                for (a, b) in a.iter_mut().flatten().zip(b.iter().flatten()) {
+                    *a = (Aabb::combine(a.0, b.0), a.1 + b.1);
+                }
+                a
+            })
+            .unwrap()
+    } else {
+        fill_bins(primitives)
+    };
+
+    // Cost, axis and index of the first bin on the right side of the best split.
+    let mut best: Option<(f64, usize, usize)> = None;
+
+    let inv_area = 1.0 / bounds.bounding_box.surface_area();
+    for (axis, bins) in bins.iter().enumerate() {
+        if scale[axis] == 0.0 {
+            continue;
+        }
+
+        // Surface area and primitive count of everything right of each bin boundary.
+        let mut right = [(0.0, 0usize); BIN_COUNT];
+        let (mut right_box, mut right_count) = (Aabb::empty(), 0);
+        for i in (1..BIN_COUNT).rev() {
+            right_box = Aabb::combine(right_box, bins[i].0);
+            right_count += bins[i].1;
+            right[i] = (right_box.surface_area(), right_count);
+        }
+
+        let (mut left_box, mut left_count) = (Aabb::empty(), 0);
+        for i in 1..BIN_COUNT {
+            left_box = Aabb::combine(left_box, bins[i - 1].0);
+            left_count += bins[i - 1].1;
+
+            let (right_area, right_count) = right[i];
+            if left_count == 0 || right_count == 0 {
+                continue;
+            }
+
+            let cost = TRAVERSAL_COST
+                + INTERSECTION_COST
+                    * (left_box.surface_area() * left_count as f64
+                        + right_area * right_count as f64)
+                    * inv_area;
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, i));
+            }
         }
     }
 
-    fn bounding_box(&self) -> Aabb {
-        self.bounding_box
+    let leaf_cost = INTERSECTION_COST * primitives.len() as f64;
+    let fits_leaf = primitives.len() <= max_leaf_size;
+
+    match best {
+        Some((cost, axis, split)) if !fits_leaf || cost < leaf_cost => {
+            // Move the primitives left of the split to the front, collecting the bounds
+            // of both sides on the way.
+            let (mut first, mut second) = (Bounds::EMPTY, Bounds::EMPTY);
+            let mut mid = 0;
+            for i in 0..primitives.len() {
+                if bin_index(primitives[i].centroid, axis) < split {
+                    first.add(&primitives[i]);
+                    primitives.swap(i, mid);
+                    mid += 1;
+                } else {
+                    second.add(&primitives[i]);
+                }
+            }
+            Some(Split {
+                mid,
+                axis,
+                first,
+                second,
+            })
+        }
+        Some(_) => None,
+        // All centroids coincide, so no split separates the primitives better than another.
+        None if !fits_leaf => Some(split_at(primitives, primitives.len() / 2, 0)),
+        None => None,
     }
 }
 
-fn box_compare(a: &dyn Hittable, b: &dyn Hittable, axis_idx: usize) -> Ordering {
-    a.bounding_box()
-        .axis(axis_idx)
-        .0
-        .total_cmp(&b.bounding_box().axis(axis_idx).0)
+/// Finds the split with the lowest estimated cost between any two neighbouring primitives
+/// sorted along each axis. Used instead of binning for nodes with few primitives.
+fn sweep_split(
+    primitives: &mut [Primitive],
+    bounds: &Bounds,
+    max_leaf_size: usize,
+) -> Option<Split> {
+    let n = primitives.len();
+    let inv_area = 1.0 / bounds.bounding_box.surface_area();
+    let sort = |primitives: &mut [Primitive], axis: usize| {
+        primitives.sort_unstable_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]))
+    };
+
+    // Cost, axis and number of primitives on the left side of the best split.
+    let mut best: Option<(f64, usize, usize)> = None;
+
+    for axis in 0..3 {
+        if bounds.centroids.axis(axis).size() <= 0.0 {
+            continue;
+        }
+        sort(primitives, axis);
+
+        let mut right_areas = [0.0; BIN_COUNT];
+        let mut right_box = Aabb::empty();
+        for i in (1..n).rev() {
+            right_box = Aabb::combine(right_box, primitives[i].bounding_box);
+            right_areas[i] = right_box.surface_area();
+        }
+
+        let mut left_box = Aabb::empty();
+        for i in 1..n {
+            left_box = Aabb::combine(left_box, primitives[i - 1].bounding_box);
+            let cost = TRAVERSAL_COST
+                + INTERSECTION_COST
+                    * (left_box.surface_area() * i as f64 + right_areas[i] * (n - i) as f64)
+                    * inv_area;
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, i));
+            }
+        }
+    }
+
+    let leaf_cost = INTERSECTION_COST * n as f64;
+    let fits_leaf = n <= max_leaf_size;
+
+    match best {
+        Some((cost, axis, mid)) if !fits_leaf || cost < leaf_cost => {
+            sort(primitives, axis);
+            Some(split_at(primitives, mid, axis))
+        }
+        Some(_) => None,
+        None if !fits_leaf => Some(split_at(primitives, n / 2, 0)),
+        None => None,
+    }
 }
 
-fn box_x_compare(a: &dyn Hittable, b: &dyn Hittable) -> Ordering {
-    box_compare(a, b, 0)
+/// Visits the leaves of a flattened BVH whose bounding boxes are hit by `ray`, visiting the
+/// child on the side the ray comes from first. `hit_leaf` is called with the primitive range
+/// of every leaf and the interval in which hits are still of interest, and returns the
+/// distance of the closest hit it found, which shrinks the interval for the remaining nodes.
+pub fn traverse(
+    nodes: &[BvhNode],
+    ray: &Ray,
+    allowed_t: Interval,
+    mut hit_leaf: impl FnMut(Range<usize>, Interval) -> Option<f64>,
+) {
+    let inv_direction = Vec3(
+        1.0 / ray.direction.x(),
+        1.0 / ray.direction.y(),
+        1.0 / ray.direction.z(),
+    );
+    let direction_is_negative = [
+        inv_direction.x() < 0.0,
+        inv_direction.y() < 0.0,
+        inv_direction.z() < 0.0,
+    ];
+
+    let mut closest = allowed_t.1;
+
+    let mut stack = [0u32; STACK_SIZE];
+    let mut stack_len = 1;
+
+    while stack_len > 0 {
+        stack_len -= 1;
+        let node_index = stack[stack_len] as usize;
+        let node = &nodes[node_index];
+
+        if !node.bounding_box.hit_with_inverse_direction(
+            ray.origin,
+            inv_direction,
+            Interval(allowed_t.0, closest),
+        ) {
+            continue;
+        }
+
+        if node.count > 0 {
+            let start = node.offset as usize;
+            if let Some(t) = hit_leaf(
+                start..start + node.count as usize,
+                Interval(allowed_t.0, closest),
+            ) {
+                closest = t;
+            }
+        } else {
+            // Hits in the nearer child shrink the interval in which the other child is searched.
+            let first = node_index as u32 + 1;
+            let second = node.offset;
+            let (near, far) = if direction_is_negative[node.axis as usize] {
+                (second, first)
+            } else {
+                (first, second)
+            };
+            stack[stack_len] = far;
+            stack[stack_len + 1] = near;
+            stack_len += 2;
+        }
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let mut closest_hit = None;
+        let mut closest = allowed_t.1;
+
+        for object in &self.unbounded {
+            if let Some(rec) = object.hit(ray, Interval(allowed_t.0, closest)) {
+                closest = rec.t;
+                closest_hit = Some(rec);
+            }
+        }
+
+        if self.nodes.is_empty() {
+            return closest_hit;
+        }
+
+        traverse(
+            &self.nodes,
+            ray,
+            Interval(allowed_t.0, closest),
+            |objects, interval| {
+                let mut closest = None;
+                for object in &self.objects[objects] {
+                    let max = closest.unwrap_or(interval.1);
+                    if let Some(rec) = object.hit(ray, Interval(interval.0, max)) {
+                        closest = Some(rec.t);
+                        closest_hit = Some(rec);
+                    }
+                }
+                closest
+            },
+        );
+
+        closest_hit
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        if !self.unbounded.is_empty() {
+            return Aabb::UNIVERSE;
+        }
+        self.nodes[0].bounding_box
+    }
 }
 
-fn box_y_compare(a: &dyn Hittable, b: &dyn Hittable) -> Ordering {
-    box_compare(a, b, 1)
+impl DescribeObject for Bvh {
+    /// The hierarchy itself is not part of the description, all the objects
+    /// in it are collected into a single group.
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        let mut objects = vec![];
+
+        for object in self.objects.iter().chain(&self.unbounded) {
+            match object.describe(exporter)? {
+                ObjectDescription::Group { objects: nested } => objects.extend(nested),
+                object => objects.push(object),
+            }
+        }
+
+        Ok(ObjectDescription::Group { objects })
+    }
 }
 
-fn box_z_compare(a: &dyn Hittable, b: &dyn Hittable) -> Ordering {
-    box_compare(a, b, 2)
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        materials::material::MaterialRef,
+        objects::{hittable::Hittable, sphere::Sphere},
+        ray::Ray,
+        test_util::TestRng,
+        util::Interval,
+    };
+
+    use super::{Bvh, BvhBuilder, BvhStats, PARALLEL_THRESHOLD};
+
+    fn random_spheres(
+        rng: &mut TestRng,
+        count: usize,
+        radius: (f64, f64),
+    ) -> Vec<Arc<dyn Hittable>> {
+        (0..count)
+            .map(|_| {
+                Arc::new(Sphere::stationary(
+                    rng.vec3(-10.0, 10.0),
+                    rng.f64(radius.0, radius.1),
+                    MaterialRef(0),
+                )) as Arc<dyn Hittable>
+            })
+            .collect()
+    }
+
+    fn assert_hits_match_brute_force(rng: &mut TestRng, objects: &[Arc<dyn Hittable>], bvh: &Bvh) {
+        for _ in 0..1000 {
+            let ray = Ray::new(rng.vec3(-15.0, 15.0), rng.unit_vec3(), 0.0);
+            let allowed_t = Interval(0.001, f64::INFINITY);
+
+            let expected = objects
+                .iter()
+                .filter_map(|object| object.hit(&ray, allowed_t))
+                .map(|rec| rec.t)
+                .min_by(f64::total_cmp);
+            assert_eq!(bvh.hit(&ray, allowed_t).map(|rec| rec.t), expected);
+        }
+    }
+
+    #[test]
+    fn hits_match_brute_force() {
+        let mut rng = TestRng::new(1);
+        let objects = random_spheres(&mut rng, 200, (0.1, 1.0));
+
+        let (sah, stats) = Bvh::build(&objects, BvhBuilder::Sah);
+        let (_, rebuilt_stats) = Bvh::build(&objects, BvhBuilder::Sah);
+        assert_eq!(stats.objects, objects.len());
+        assert_eq!(stats.nodes, rebuilt_stats.nodes);
+        assert_eq!(stats.sah_cost, rebuilt_stats.sah_cost);
+
+        let (median, _) = Bvh::build(&objects, BvhBuilder::Median);
+
+        assert_hits_match_brute_force(&mut rng, &objects, &sah);
+        assert_hits_match_brute_force(&mut rng, &objects, &median);
+    }
+
+    #[test]
+    fn parallel_build_matches_brute_force() {
+        let mut rng = TestRng::new(2);
+        let objects = random_spheres(&mut rng, 4 * PARALLEL_THRESHOLD, (0.05, 0.2));
+
+        for builder in [BvhBuilder::Sah, BvhBuilder::Median] {
+            let (bvh, stats) = Bvh::build(&objects, builder);
+            assert_eq!(stats.objects, objects.len());
+            assert_eq!(stats.leaves, stats.nodes + 1);
+            assert_hits_match_brute_force(&mut rng, &objects, &bvh);
+        }
+    }
+
+    #[test]
+    fn stats_without_leaves_have_zero_average() {
+        let stats = BvhStats::default().to_string();
+        assert!(stats.contains("average 0.00"), "{stats}");
+    }
 }
@@ -0,0 +1,192 @@
+//! Compares the flat BVH with the tree of separately allocated nodes it replaced, on the built-in
+//! scenes. The benchmark is ignored by default, run it with
+//!
+//! ```text
+//! cargo test --release bvh_layout -- --ignored --nocapture
+//! ```
+//!
+//! Every scene is traced with the same seeded rays: one jittered camera ray per sample and one
+//! diffuse bounce from wherever it hits. Only the top level hierarchy of the scene is replaced,
+//! hierarchies inside objects such as meshes and groups are flat in both cases.
+
+use std::{cmp::Ordering, sync::Arc, time::Instant};
+
+use clap::ValueEnum;
+
+use crate::{
+    builtin_scene,
+    objects::{
+        aabb::Aabb,
+        bvh::{Bvh, BvhBuilder},
+        hittable::{HitRecord, Hittable},
+    },
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    test_util::TestRng,
+    util::{deg2rad, Interval},
+    Scene,
+};
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 180;
+const SAMPLES: u32 = 4;
+/// Every layout traces the rays this many times, the fastest run is reported.
+const RUNS: usize = 5;
+
+/// The BVH as it was before it was flattened: a binary tree of hittables, split at the median
+/// along a random axis, that always visits the left child first.
+struct TreeBvh {
+    left: Arc<dyn Hittable>,
+    right: Arc<dyn Hittable>,
+    bounding_box: Aabb,
+}
+
+impl TreeBvh {
+    fn new(objects: &[Arc<dyn Hittable>], rng: &mut TestRng) -> Self {
+        let mut objects = objects.to_vec();
+        let axis = rng.f64(0.0, 3.0) as usize;
+        let compare = |a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>| -> Ordering {
+            a.bounding_box()
+                .axis(axis)
+                .0
+                .total_cmp(&b.bounding_box().axis(axis).0)
+        };
+
+        let (left, right): (Arc<dyn Hittable>, Arc<dyn Hittable>) = match objects.len() {
+            1 => (Arc::clone(&objects[0]), Arc::clone(&objects[0])),
+            2 if compare(&objects[0], &objects[1]).is_lt() => {
+                (Arc::clone(&objects[0]), Arc::clone(&objects[1]))
+            }
+            2 => (Arc::clone(&objects[1]), Arc::clone(&objects[0])),
+            _ => {
+                objects.sort_by(compare);
+                let mid = objects.len() / 2;
+                (
+                    Arc::new(TreeBvh::new(&objects[..mid], rng)),
+                    Arc::new(TreeBvh::new(&objects[mid..], rng)),
+                )
+            }
+        };
+        let bounding_box = Aabb::combine(left.bounding_box(), right.bounding_box());
+
+        Self {
+            left,
+            right,
+            bounding_box,
+        }
+    }
+}
+
+impl Hittable for TreeBvh {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        if !self.bounding_box.hit(ray, allowed_t) {
+            return None;
+        }
+
+        let hit_left = self.left.hit(ray, allowed_t);
+        let max = hit_left.as_ref().map_or(allowed_t.1, |rec| rec.t);
+        self.right.hit(ray, Interval(allowed_t.0, max)).or(hit_left)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for TreeBvh {
+    fn describe(&self, _exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        unreachable!("the tree BVH is only used by the benchmark")
+    }
+}
+
+/// Jittered camera rays through every pixel of the scene's default view, followed by one
+/// diffuse bounce from each point they hit.
+fn rays(scene: &dyn crate::scenes::scene::Scene, bvh: &Bvh, rng: &mut TestRng) -> Vec<Ray> {
+    let settings = scene.default_settings();
+    let w = (settings.camera_eye - settings.camera_target).normalize();
+    let u = settings.camera_up.cross(w).normalize();
+    let v = w.cross(u);
+    let h = (deg2rad(settings.fov) / 2.0).tan();
+    let aspect_ratio = WIDTH as f64 / HEIGHT as f64;
+
+    let mut rays = vec![];
+    for row in 0..HEIGHT {
+        for col in 0..WIDTH {
+            for _ in 0..SAMPLES {
+                let x = 2.0 * (col as f64 + rng.f64(0.0, 1.0)) / WIDTH as f64 - 1.0;
+                let y = 1.0 - 2.0 * (row as f64 + rng.f64(0.0, 1.0)) / HEIGHT as f64;
+                let direction = -w + u * (x * h * aspect_ratio) + v * (y * h);
+                rays.push(Ray::new(
+                    settings.camera_eye,
+                    direction,
+                    rng.f64(settings.shutter_open, settings.shutter_close),
+                ));
+            }
+        }
+    }
+
+    let bounces: Vec<Ray> = rays
+        .iter()
+        .filter_map(|ray| {
+            let rec = bvh.hit(ray, Interval(0.001, f64::INFINITY))?;
+            Some(Ray::new(
+                rec.position,
+                rec.normal + rng.unit_vec3(),
+                ray.time,
+            ))
+        })
+        .collect();
+    rays.extend(bounces);
+    rays
+}
+
+/// Seconds of the fastest of [`RUNS`] runs tracing all `rays` through `object`.
+fn trace(object: &dyn Hittable, rays: &[Ray]) -> f64 {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let hits = rays
+                .iter()
+                .filter(|ray| object.hit(ray, Interval(0.001, f64::INFINITY)).is_some())
+                .count();
+            std::hint::black_box(hits);
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+#[test]
+#[ignore = "benchmark, run in release mode with --ignored --nocapture"]
+fn bvh_layout() {
+    println!(
+        "{WIDTH}x{HEIGHT} pixels, {SAMPLES} samples, one bounce, fastest of {RUNS} runs on one thread"
+    );
+    println!(
+        "{:<28} {:>8} {:>10} {:>12} {:>10}",
+        "scene", "rays", "tree (s)", "median (s)", "sah (s)"
+    );
+
+    for &variant in Scene::value_variants() {
+        let scene = builtin_scene(variant);
+        let world = scene.world(BvhBuilder::default());
+        let mut rng = TestRng::new(1);
+
+        let tree = TreeBvh::new(world.objects(), &mut rng);
+        let (median, _) = Bvh::build(world.objects(), BvhBuilder::Median);
+        let (sah, _) = Bvh::build(world.objects(), BvhBuilder::Sah);
+        let rays = rays(scene, &sah, &mut rng);
+
+        println!(
+            "{:<28} {:>8} {:>10.3} {:>12.3} {:>10.3}",
+            variant.to_string(),
+            rays.len(),
+            trace(&tree, &rays),
+            trace(&median, &rays),
+            trace(&sah, &rays),
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    materials::{material::MaterialRef, texture::TexCoord},
+    objects::{
+        aabb::Aabb,
+        disk::{disk_uv, hit_local_disk},
+        frame::{turn_around_axis, Frame},
+        hittable::{HitRecord, Hittable},
+    },
+    polynomial::solve_quadratic,
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// A finite cone narrowing from a circular base to its apex, optionally closed by a disk at the base.
+pub struct Cone {
+    pub base: Vec3,
+    pub apex: Vec3,
+    pub radius: f64,
+    pub capped: bool,
+    pub material: MaterialRef,
+
+    frame: Frame,
+    height: f64,
+    bounding_box: Aabb,
+}
+
+impl Cone {
+    pub fn new(base: Vec3, apex: Vec3, radius: f64, capped: bool, material: MaterialRef) -> Self {
+        let frame = Frame::new(base, apex - base);
+        let extent = frame.circle_extent(radius);
+        let bounding_box = Aabb::combine(
+            Aabb::span_points(base - extent, base + extent),
+            Aabb::span_points(apex, apex),
+        );
+
+        Self {
+            base,
+            apex,
+            radius,
+            capped,
+            material,
+
+            frame,
+            height: (apex - base).length(),
+            bounding_box,
+        }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let local = self.frame.local_ray(ray);
+        let (o, d) = (local.origin, local.direction);
+
+        // Closest hit so far as distance, local normal and texture coordinates.
+        let mut closest: Option<(f64, Vec3, TexCoord, (Vec3, Vec3))> = None;
+        let mut range = allowed_t;
+
+        // Points on the cone satisfy x² + y² = k² (h - z)², where k is the slope of its side.
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let h = self.height - o.z();
+        let a = d.x() * d.x() + d.y() * d.y() - k2 * d.z() * d.z();
+        let b = 2.0 * (o.x() * d.x() + o.y() * d.y() + k2 * h * d.z());
+        let c = o.x() * o.x() + o.y() * o.y() - k2 * h * h;
+        for t in solve_quadratic(a, b, c) {
+            let point = local.at(t);
+            // The equation also describes the mirrored cone above the apex.
+            if range.surrounds(t) && (0.0..=self.height).contains(&point.z()) {
+                let normal = Vec3(point.x(), point.y(), k2 * (self.height - point.z()));
+                let normal = if normal.near_zero() {
+                    Vec3(0.0, 0.0, 1.0)
+                } else {
+                    normal.normalize()
+                };
+                let uv = TexCoord::new(turn_around_axis(point), point.z() / self.height);
+                let along_u = Vec3(-point.y(), point.x(), 0.0);
+                closest = Some((t, normal, uv, (along_u, Vec3(0.0, 0.0, 1.0))));
+                range.1 = t;
+                break;
+            }
+        }
+
+        if self.capped {
+            if let Some((t, point)) = hit_local_disk(&local, 0.0, self.radius, range) {
+                let uv = disk_uv(point, self.radius);
+                let tangents = (Vec3(1.0, 0.0, 0.0), Vec3(0.0, 1.0, 0.0));
+                closest = Some((t, Vec3(0.0, 0.0, -1.0), uv, tangents));
+            }
+        }
+
+        let (t, normal, uv, (along_u, along_v)) = closest?;
+        Some(
+            HitRecord::with_face_normal(
+                *ray,
+                t,
+                ray.at(t),
+                uv,
+                self.frame.world_vector(normal),
+                self.material,
+            )
+            .with_tangents(
+                self.frame.world_vector(along_u),
+                self.frame.world_vector(along_v),
+            ),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for Cone {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Cone {
+            base: self.base,
+            apex: self.apex,
+            radius: self.radius,
+            capped: self.capped,
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef, objects::hittable::Hittable, ray::Ray, util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::Cone;
+
+    #[test]
+    fn normals_are_perpendicular_to_the_side() {
+        // Base radius equal to the height gives a side at 45 degrees.
+        let cone = Cone::new(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            1.0,
+            true,
+            MaterialRef(0),
+        );
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        let ray = Ray::new(Vec3(3.0, 0.5, 0.0), Vec3(-1.0, 0.0, 0.0), 0.0);
+        let rec = cone.hit(&ray, allowed_t).unwrap();
+        assert!((rec.t - 2.5).abs() < 1e-9);
+        let expected = Vec3(1.0, 1.0, 0.0).normalize();
+        assert!((rec.normal - expected).length() < 1e-9);
+
+        // Passes above the apex, where only the mirrored cone would be hit.
+        let above = Ray::new(Vec3(3.0, 1.5, 0.0), Vec3(-1.0, 0.0, 0.0), 0.0);
+        assert!(cone.hit(&above, allowed_t).is_none());
+
+        let below = Ray::new(Vec3(0.5, -2.0, 0.0), Vec3(0.0, 1.0, 0.0), 0.0);
+        let rec = cone.hit(&below, allowed_t).unwrap();
+        assert!((rec.t - 2.0).abs() < 1e-9);
+        assert!((rec.normal - Vec3(0.0, -1.0, 0.0)).length() < 1e-9);
+    }
+}
@@ -1,8 +1,13 @@
-use std::{f64::INFINITY, sync::Arc};
+use std::sync::Arc;
 
 use crate::{
     materials::{material::MaterialRef, texture::TexCoord},
     ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
     util::{rand_f64, Interval},
     vec3::Vec3,
 };
@@ -41,7 +46,7 @@ impl Hittable for ConstantVolume {
         // Now that we know the entrance point check for another hit as the exit point.
         let mut rec2 = self
             .boundary
-            .hit(ray, Interval(rec1.t + 0.0001, INFINITY))?;
+            .hit(ray, Interval(rec1.t + 0.0001, f64::INFINITY))?;
 
         // Previously we checked for hits anywhere, but now we need to constrain
         // it to the allowed interval for t.
@@ -72,14 +77,18 @@ impl Hittable for ConstantVolume {
         // Otherwise calculate the appropriate t based on the distance travelled.
         let t = rec1.t + hit_distance / ray_length;
 
-        // The normal vector, uv and front_face values are arbitrary.
+        // The normal vector, tangents, uv and front_face values are arbitrary.
         Some(HitRecord {
             position: ray.at(t),
             normal: Vec3(1.0, 0.0, 0.0),
+            geometric_normal: Vec3(1.0, 0.0, 0.0),
+            tangent: Vec3(0.0, 1.0, 0.0),
+            bitangent: Vec3(0.0, 0.0, 1.0),
             t,
             uv: TexCoord::new(0.0, 0.0),
             front_face: true,
             material: self.phase_function,
+            vertex_colors: None,
         })
     }
 
@@ -87,3 +96,13 @@ impl Hittable for ConstantVolume {
         self.boundary.bounding_box()
     }
 }
+
+impl DescribeObject for ConstantVolume {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::ConstantVolume {
+            boundary: Box::new(self.boundary.describe(exporter)?),
+            density: -1.0 / self.neg_inv_density,
+            material: exporter.material(self.phase_function),
+        })
+    }
+}
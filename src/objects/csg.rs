@@ -0,0 +1,213 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    objects::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+    },
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+};
+
+/// How constructive solid geometry combines the volumes of two objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsgOperation {
+    /// Everything inside either object.
+    Union,
+    /// Everything inside both objects.
+    Intersection,
+    /// Everything inside the left object but not inside the right one.
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Combines the volumes of two closed objects, e.g. a cube with a spherical hole.
+///
+/// The surfaces of the objects are found along the ray with [`Hittable::hit_all`], and those
+/// at which the ray enters or leaves the combined volume are its surface.
+pub struct Csg {
+    operation: CsgOperation,
+    left: Arc<dyn Hittable>,
+    right: Arc<dyn Hittable>,
+    bounding_box: Aabb,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        let (a, b) = (left.bounding_box(), right.bounding_box());
+        let bounding_box = match operation {
+            CsgOperation::Union => Aabb::combine(a, b),
+            CsgOperation::Intersection => Aabb::intersection(a, b),
+            CsgOperation::Difference => a,
+        };
+
+        Self {
+            operation,
+            left,
+            right,
+            bounding_box,
+        }
+    }
+
+    /// Returns the surface hits of the combined volume along the ray in order, stopping after
+    /// the first one if `first_only` is set.
+    fn boundary_hits(&self, ray: &Ray, allowed_t: Interval, first_only: bool) -> Vec<HitRecord> {
+        // Whether the ray starts inside an object is only known from its first hit,
+        // so the objects are hit along the whole ray.
+        let everything = Interval(allowed_t.0, f64::INFINITY);
+        let left = self.left.hit_all(ray, everything);
+        let right = self.right.hit_all(ray, everything);
+
+        // Leaving an object is the first hit when starting inside of it.
+        let starts_inside = |hits: &[HitRecord]| hits.first().is_some_and(|rec| !rec.front_face);
+        let mut in_left = starts_inside(&left);
+        let mut in_right = starts_inside(&right);
+
+        let mut hits = vec![];
+        let (mut l, mut r) = (left.into_iter().peekable(), right.into_iter().peekable());
+        loop {
+            let from_left = match (l.peek(), r.peek()) {
+                (Some(a), Some(b)) => a.t <= b.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            let mut rec = if from_left {
+                l.next().unwrap()
+            } else {
+                r.next().unwrap()
+            };
+            if rec.t > allowed_t.1 {
+                break;
+            }
+
+            let was_inside = self.operation.contains(in_left, in_right);
+            if from_left {
+                in_left = rec.front_face;
+            } else {
+                in_right = rec.front_face;
+            }
+            let is_inside = self.operation.contains(in_left, in_right);
+
+            if was_inside != is_inside {
+                // The normal of the hit already faces the ray, only whether the ray enters the
+                // combined volume differs, e.g. at the surface of a hole.
+                rec.front_face = is_inside;
+                hits.push(rec);
+                if first_only {
+                    break;
+                }
+            }
+        }
+        hits
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        self.boundary_hits(ray, allowed_t, true).pop()
+    }
+
+    fn hit_all(&self, ray: &Ray, allowed_t: Interval) -> Vec<HitRecord> {
+        self.boundary_hits(ray, allowed_t, false)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for Csg {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Csg {
+            operation: self.operation,
+            left: Box::new(self.left.describe(exporter)?),
+            right: Box::new(self.right.describe(exporter)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        materials::material::MaterialRef,
+        objects::{cube::cube, hittable::Hittable, sphere::Sphere},
+        ray::Ray,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::{Csg, CsgOperation};
+
+    fn sphere(z: f64) -> Arc<dyn Hittable> {
+        Arc::new(Sphere::stationary(Vec3(0.0, 0.0, z), 1.0, MaterialRef(0)))
+    }
+
+    #[test]
+    fn combines_overlapping_spheres() {
+        let ray = Ray::new(Vec3(0.0, 0.0, 5.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let allowed_t = Interval(0.001, f64::INFINITY);
+        let surface = |operation| {
+            Csg::new(operation, sphere(0.0), sphere(1.0))
+                .hit_all(&ray, allowed_t)
+                .iter()
+                .map(|rec| (rec.t, rec.front_face, rec.normal.z()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            surface(CsgOperation::Union),
+            vec![(3.0, true, 1.0), (6.0, false, 1.0)]
+        );
+        assert_eq!(
+            surface(CsgOperation::Intersection),
+            vec![(4.0, true, 1.0), (5.0, false, 1.0)]
+        );
+        // Enters through the surface of the hole, which the right sphere leaves.
+        assert_eq!(
+            surface(CsgOperation::Difference),
+            vec![(5.0, true, 1.0), (6.0, false, 1.0)]
+        );
+    }
+
+    #[test]
+    fn cube_minus_sphere_has_a_hole() {
+        let block = Arc::new(cube(
+            Vec3(-1.0, -1.0, -1.0),
+            Vec3(1.0, 1.0, 1.0),
+            MaterialRef(0),
+        ));
+        let hole = Arc::new(Sphere::stationary(Vec3(0.0, 0.0, 1.0), 0.5, MaterialRef(1)));
+        let csg = Csg::new(CsgOperation::Difference, block, hole);
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        let into_hole = Ray::new(Vec3(0.0, 0.0, 5.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = csg.hit(&into_hole, allowed_t).unwrap();
+        assert!((rec.t - 4.5).abs() < 1e-9);
+        assert_eq!(rec.material.0, 1);
+
+        let beside_hole = Ray::new(Vec3(0.8, 0.0, 5.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = csg.hit(&beside_hole, allowed_t).unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert_eq!(rec.material.0, 0);
+    }
+}
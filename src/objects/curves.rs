@@ -0,0 +1,473 @@
+use std::ops::{Add, Mul};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    materials::{material::MaterialRef, texture::TexCoord},
+    objects::{
+        aabb::Aabb,
+        bvh::{build_nodes, traverse, BvhBuilder, BvhNode, BvhStats},
+        frame::{turn_around_axis, Frame},
+        hittable::{HitRecord, Hittable},
+    },
+    polynomial::solve_quadratic,
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// How the control points of a strand describe its center line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveBasis {
+    /// Straight lines between the points.
+    #[default]
+    Linear,
+    /// Cubic Bézier curves, each starting at the end of the previous one, so that
+    /// a strand of `n` curves has `3n + 1` points.
+    Bezier,
+}
+
+/// The cross section of the strands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveShape {
+    /// Tubes around the center line.
+    #[default]
+    Round,
+    /// Flat strips that always face the ray, which look the same for thin hair and fur.
+    Ribbon,
+}
+
+/// A single hair or other strand of [`Curves`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Strand {
+    pub points: Vec<Vec3>,
+    /// Radius at every point, or a single radius for the whole strand. Bézier curves
+    /// interpolate them like their points.
+    pub radii: Vec<f64>,
+}
+
+impl Strand {
+    pub fn validate(&self, basis: CurveBasis) -> Result<(), String> {
+        match basis {
+            CurveBasis::Linear if self.points.len() < 2 => {
+                return Err("linear strands need at least two points".to_owned());
+            }
+            CurveBasis::Bezier if self.points.len() < 4 || self.points.len() % 3 != 1 => {
+                return Err(format!(
+                    "Bézier strands need 3n + 1 points, but one has {}",
+                    self.points.len()
+                ));
+            }
+            _ => {}
+        }
+        if self.radii.len() != 1 && self.radii.len() != self.points.len() {
+            return Err(format!(
+                "strand has {} radii but {} points",
+                self.radii.len(),
+                self.points.len()
+            ));
+        }
+        if self.radii.iter().any(|r| !(r.is_finite() && *r >= 0.0)) {
+            return Err("strand radii must not be negative".to_owned());
+        }
+        Ok(())
+    }
+
+    fn radius(&self, point: usize) -> f64 {
+        if self.radii.len() == 1 {
+            self.radii[0]
+        } else {
+            self.radii[point]
+        }
+    }
+}
+
+/// Maximum number of straight pieces a single Bézier curve is split into.
+const MAX_PIECES: usize = 64;
+/// Maximum distance between a Bézier curve and its pieces relative to its radius.
+const FLATNESS: f64 = 0.05;
+/// Maximum number of pieces stored in a single leaf of the curves BVH.
+const MAX_PIECES_PER_LEAF: usize = 4;
+
+/// A straight part of a strand, whose radius changes linearly between its ends.
+struct Piece {
+    start: Vec3,
+    end: Vec3,
+    radii: [f64; 2],
+    /// Texture coordinate `u` at both ends, running from 0 to 1 along the strand.
+    u: [f64; 2],
+}
+
+fn cubic<T: Copy + Add<Output = T> + Mul<f64, Output = T>>([p0, p1, p2, p3]: [T; 4], t: f64) -> T {
+    let s = 1.0 - t;
+    p0 * (s * s * s) + p1 * (3.0 * s * s * t) + p2 * (3.0 * s * t * t) + p3 * (t * t * t)
+}
+
+impl Piece {
+    fn bounding_box(&self) -> Aabb {
+        let [r0, r1] = self.radii;
+        Aabb::combine(
+            Aabb::span_points(self.start - Vec3(r0, r0, r0), self.start + Vec3(r0, r0, r0)),
+            Aabb::span_points(self.end - Vec3(r1, r1, r1), self.end + Vec3(r1, r1, r1)),
+        )
+    }
+
+    /// Returns the unit axis, the length and the change of the radius along the length.
+    fn axis(&self) -> (Vec3, f64, f64) {
+        let length = (self.end - self.start).length();
+        let axis = if length > 0.0 {
+            (self.end - self.start) / length
+        } else {
+            Vec3(0.0, 0.0, 1.0)
+        };
+        let slope = if length > 0.0 {
+            (self.radii[1] - self.radii[0]) / length
+        } else {
+            0.0
+        };
+        (axis, length, slope)
+    }
+
+    /// Intersects the tube of the piece, a cone between its ends closed by a ball at each end,
+    /// and returns the distance, the outward normal and the distance along the axis.
+    fn hit_round(&self, ray: &Ray, allowed_t: Interval) -> Option<(f64, Vec3, f64)> {
+        let (axis, length, slope) = self.axis();
+        let r0 = self.radii[0];
+        let mut range = allowed_t;
+        let mut closest = None;
+
+        // Points on the cone satisfy |w|² - s² = (r0 + slope s)², where s is the distance
+        // along the axis of the offset w from the start.
+        let w0 = ray.origin - self.start;
+        let d = ray.direction;
+        let (s0, ds) = (w0.dot(axis), d.dot(axis));
+        let k = 1.0 + slope * slope;
+        let roots = solve_quadratic(
+            d.length_squared() - k * ds * ds,
+            2.0 * (w0.dot(d) - k * s0 * ds - slope * r0 * ds),
+            w0.length_squared() - s0 * s0 - (r0 + slope * s0) * (r0 + slope * s0),
+        );
+        for t in roots {
+            let s = s0 + t * ds;
+            if range.surrounds(t) && (0.0..=length).contains(&s) {
+                let w = w0 + d * t;
+                let normal = w - axis * (s + slope * (r0 + slope * s));
+                closest = Some((t, normal.normalize(), s));
+                range.1 = t;
+                break;
+            }
+        }
+
+        for (center, radius, s) in [(self.start, r0, 0.0), (self.end, self.radii[1], length)] {
+            if radius <= 0.0 {
+                continue;
+            }
+            let oc = ray.origin - center;
+            let roots = solve_quadratic(
+                d.length_squared(),
+                2.0 * oc.dot(d),
+                oc.length_squared() - radius * radius,
+            );
+            if let Some(t) = roots.into_iter().find(|&t| range.surrounds(t)) {
+                closest = Some((t, (ray.at(t) - center) / radius, s));
+                range.1 = t;
+            }
+        }
+        closest
+    }
+
+    /// Intersects the ribbon of the piece turned towards the ray, and returns the distance,
+    /// the normal, the distance along the axis and the offset across the ribbon.
+    fn hit_ribbon(&self, ray: &Ray, allowed_t: Interval) -> Option<(f64, Vec3, f64, f64)> {
+        let (axis, length, slope) = self.axis();
+        let normal = axis * ray.direction.dot(axis) - ray.direction;
+        if normal.near_zero() {
+            return None;
+        }
+        let normal = normal.normalize();
+
+        let denom = normal.dot(ray.direction);
+        if denom.abs() < f64::EPSILON {
+            return None;
+        }
+        let t = normal.dot(self.start - ray.origin) / denom;
+        if !allowed_t.surrounds(t) {
+            return None;
+        }
+
+        let w = ray.at(t) - self.start;
+        let s = w.dot(axis);
+        let across = w.dot(axis.cross(normal));
+        let radius = self.radii[0] + slope * s;
+        if !(0.0..=length).contains(&s) || across.abs() > radius || radius <= 0.0 {
+            return None;
+        }
+        Some((t, normal, s, across / radius))
+    }
+}
+
+/// Hair, fur or grass made of many strands of varying radius, with their own bounding volume
+/// hierarchy over the straight pieces of the strands.
+///
+/// Bézier curves are split into pieces that stay close to the curve relative to its radius,
+/// following Wang's formula. Round strands close the gaps at the joints between pieces with
+/// a ball around every point.
+pub struct Curves {
+    pub strands: Vec<Strand>,
+    pub basis: CurveBasis,
+    pub shape: CurveShape,
+    pub material: MaterialRef,
+
+    pieces: Vec<Piece>,
+    /// Piece indices ordered such that every leaf refers to a contiguous range.
+    order: Vec<u32>,
+    nodes: Vec<BvhNode>,
+}
+
+impl Curves {
+    /// Creates the curves and builds their BVH with `builder`, failing if there are no strands
+    /// or one of them is invalid according to [`Strand::validate`].
+    pub fn build(
+        strands: Vec<Strand>,
+        basis: CurveBasis,
+        shape: CurveShape,
+        material: MaterialRef,
+        builder: BvhBuilder,
+    ) -> Result<(Self, BvhStats), String> {
+        if strands.is_empty() {
+            return Err("curves need at least one strand".to_owned());
+        }
+        for strand in &strands {
+            strand.validate(basis)?;
+        }
+
+        let pieces: Vec<Piece> = strands
+            .par_iter()
+            .flat_map_iter(|strand| match basis {
+                CurveBasis::Linear => linear_pieces(strand),
+                CurveBasis::Bezier => bezier_pieces(strand),
+            })
+            .collect();
+        let bounds: Vec<Aabb> = pieces.par_iter().map(Piece::bounding_box).collect();
+        let (nodes, order, stats) = build_nodes(&bounds, builder, MAX_PIECES_PER_LEAF);
+
+        let curves = Self {
+            strands,
+            basis,
+            shape,
+            material,
+            pieces,
+            order,
+            nodes,
+        };
+        Ok((curves, stats))
+    }
+}
+
+fn linear_pieces(strand: &Strand) -> Vec<Piece> {
+    let count = strand.points.len() - 1;
+    (0..count)
+        .map(|i| Piece {
+            start: strand.points[i],
+            end: strand.points[i + 1],
+            radii: [strand.radius(i), strand.radius(i + 1)],
+            u: [i as f64 / count as f64, (i + 1) as f64 / count as f64],
+        })
+        .collect()
+}
+
+fn bezier_pieces(strand: &Strand) -> Vec<Piece> {
+    let curves = strand.points.len() / 3;
+    let mut pieces = vec![];
+    for curve in 0..curves {
+        let points: [Vec3; 4] = std::array::from_fn(|i| strand.points[3 * curve + i]);
+        let radii: [f64; 4] = std::array::from_fn(|i| strand.radius(3 * curve + i));
+
+        // Wang's formula bounds the distance between a cubic curve and a polyline through
+        // `n` evenly spaced points on it by 3/4 of its largest second difference over n².
+        let [p0, p1, p2, p3] = points;
+        let bend = (p0 - p1 * 2.0 + p2)
+            .length()
+            .max((p1 - p2 * 2.0 + p3).length());
+        let tolerance = FLATNESS * radii.iter().copied().fold(0.0, f64::max);
+        let count = if tolerance > 0.0 {
+            ((0.75 * bend / tolerance).sqrt().ceil() as usize).clamp(1, MAX_PIECES)
+        } else {
+            MAX_PIECES
+        };
+
+        for i in 0..count {
+            let (a, b) = (i as f64 / count as f64, (i + 1) as f64 / count as f64);
+            pieces.push(Piece {
+                start: cubic(points, a),
+                end: cubic(points, b),
+                radii: [cubic(radii, a), cubic(radii, b)],
+                u: [
+                    (curve as f64 + a) / curves as f64,
+                    (curve as f64 + b) / curves as f64,
+                ],
+            });
+        }
+    }
+    pieces
+}
+
+impl Hittable for Curves {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let mut closest_hit = None;
+
+        traverse(&self.nodes, ray, allowed_t, |pieces, interval| {
+            let mut closest = None;
+            for &piece in &self.order[pieces] {
+                let piece = &self.pieces[piece as usize];
+                let range = Interval(interval.0, closest.unwrap_or(interval.1));
+                let hit = match self.shape {
+                    CurveShape::Round => piece
+                        .hit_round(ray, range)
+                        .map(|(t, normal, s)| (t, normal, s, None)),
+                    CurveShape::Ribbon => piece
+                        .hit_ribbon(ray, range)
+                        .map(|(t, normal, s, across)| (t, normal, s, Some(across))),
+                };
+                if let Some((t, normal, s, across)) = hit {
+                    closest = Some(t);
+                    closest_hit = Some((piece, t, normal, s, across));
+                }
+            }
+            closest
+        });
+
+        let (piece, t, normal, s, across) = closest_hit?;
+        let (axis, length, _) = piece.axis();
+        let along = if length > 0.0 { s / length } else { 0.0 };
+        let u = piece.u[0] + (piece.u[1] - piece.u[0]) * along;
+
+        // Around round strands `v` runs once around the axis, across ribbons from one edge
+        // to the other.
+        let (v, along_v) = match across {
+            Some(across) => (0.5 + 0.5 * across, axis.cross(normal)),
+            None => {
+                let frame = Frame::new(piece.start, axis);
+                (
+                    turn_around_axis(frame.local_vector(normal)),
+                    axis.cross(normal),
+                )
+            }
+        };
+
+        Some(
+            HitRecord::with_face_normal(
+                *ray,
+                t,
+                ray.at(t),
+                TexCoord::new(u, v),
+                normal,
+                self.material,
+            )
+            .with_tangents(axis, along_v),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.nodes[0].bounding_box
+    }
+}
+
+impl DescribeObject for Curves {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Curves {
+            basis: self.basis,
+            shape: self.shape,
+            strands: self.strands.clone(),
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef,
+        objects::{bvh::BvhBuilder, hittable::Hittable},
+        ray::Ray,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::{CurveBasis, CurveShape, Curves, Strand};
+
+    #[test]
+    fn round_strand_tapers() {
+        // A strand along x whose radius shrinks from 0.5 to 0.1.
+        let strand = Strand {
+            points: vec![Vec3(0.0, 0.0, 0.0), Vec3(4.0, 0.0, 0.0)],
+            radii: vec![0.5, 0.1],
+        };
+        let curves = Curves::build(
+            vec![strand],
+            CurveBasis::Linear,
+            CurveShape::Round,
+            MaterialRef(0),
+            BvhBuilder::default(),
+        )
+        .unwrap()
+        .0;
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        // Halfway along, the radius is 0.3 and the normal leans towards the thin end.
+        let ray = Ray::new(Vec3(2.0, 5.0, 0.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        let rec = curves.hit(&ray, allowed_t).unwrap();
+        assert!((rec.position.y() - 0.3).abs() < 1e-9);
+        assert!(rec.normal.x() > 0.0 && rec.normal.y() > 0.9);
+        assert!((rec.uv.u - 0.5).abs() < 0.05);
+
+        // The ball at the start rounds off the end of the strand.
+        let ray = Ray::new(Vec3(-5.0, 0.0, 0.0), Vec3(1.0, 0.0, 0.0), 0.0);
+        let rec = curves.hit(&ray, allowed_t).unwrap();
+        assert!((rec.position.x() + 0.5).abs() < 1e-9);
+
+        let beside = Ray::new(Vec3(3.9, 5.0, 0.3), Vec3(0.0, -1.0, 0.0), 0.0);
+        assert!(curves.hit(&beside, allowed_t).is_none());
+    }
+
+    #[test]
+    fn bezier_ribbon_follows_curve() {
+        // An arc from (0, 0, 0) to (3, 0, 0) bulging up to a height of 0.75 in the middle.
+        let strand = Strand {
+            points: vec![
+                Vec3(0.0, 0.0, 0.0),
+                Vec3(1.0, 1.0, 0.0),
+                Vec3(2.0, 1.0, 0.0),
+                Vec3(3.0, 0.0, 0.0),
+            ],
+            radii: vec![0.1],
+        };
+        let curves = Curves::build(
+            vec![strand],
+            CurveBasis::Bezier,
+            CurveShape::Ribbon,
+            MaterialRef(0),
+            BvhBuilder::default(),
+        )
+        .unwrap()
+        .0;
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        // Seen from the front, the ribbon faces the ray and spans 0.75 ± 0.1 at the top.
+        let ray = |y: f64| Ray::new(Vec3(1.5, y, 5.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = curves.hit(&ray(0.8), allowed_t).unwrap();
+        assert!((rec.normal - Vec3(0.0, 0.0, 1.0)).length() < 1e-9);
+        assert!((rec.uv.u - 0.5).abs() < 0.05);
+        assert!(curves.hit(&ray(0.9), allowed_t).is_none());
+        assert!(curves.hit(&ray(0.6), allowed_t).is_none());
+    }
+}
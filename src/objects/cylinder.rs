@@ -0,0 +1,169 @@
+use crate::{
+    materials::{material::MaterialRef, texture::TexCoord},
+    objects::{
+        aabb::Aabb,
+        disk::{disk_uv, hit_local_disk},
+        frame::{turn_around_axis, Frame},
+        hittable::{HitRecord, Hittable},
+    },
+    polynomial::solve_quadratic,
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// A finite cylinder between the centers of its two ends, optionally closed by disks.
+pub struct Cylinder {
+    pub base: Vec3,
+    pub top: Vec3,
+    pub radius: f64,
+    pub capped: bool,
+    pub material: MaterialRef,
+
+    frame: Frame,
+    height: f64,
+    bounding_box: Aabb,
+}
+
+impl Cylinder {
+    pub fn new(base: Vec3, top: Vec3, radius: f64, capped: bool, material: MaterialRef) -> Self {
+        let frame = Frame::new(base, top - base);
+        let extent = frame.circle_extent(radius);
+        let bounding_box = Aabb::combine(
+            Aabb::span_points(base - extent, base + extent),
+            Aabb::span_points(top - extent, top + extent),
+        );
+
+        Self {
+            base,
+            top,
+            radius,
+            capped,
+            material,
+
+            frame,
+            height: (top - base).length(),
+            bounding_box,
+        }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let local = self.frame.local_ray(ray);
+        let (o, d) = (local.origin, local.direction);
+
+        // Closest hit so far as distance, local normal and texture coordinates.
+        let mut closest: Option<(f64, Vec3, TexCoord, (Vec3, Vec3))> = None;
+        let mut range = allowed_t;
+
+        let a = d.x() * d.x() + d.y() * d.y();
+        let b = 2.0 * (o.x() * d.x() + o.y() * d.y());
+        let c = o.x() * o.x() + o.y() * o.y() - self.radius * self.radius;
+        for t in solve_quadratic(a, b, c) {
+            let point = local.at(t);
+            if range.surrounds(t) && (0.0..=self.height).contains(&point.z()) {
+                let normal = Vec3(point.x(), point.y(), 0.0) / self.radius;
+                let uv = TexCoord::new(turn_around_axis(point), point.z() / self.height);
+                let along_u = Vec3(-point.y(), point.x(), 0.0);
+                closest = Some((t, normal, uv, (along_u, Vec3(0.0, 0.0, 1.0))));
+                range.1 = t;
+                break;
+            }
+        }
+
+        if self.capped {
+            for (z, normal) in [(0.0, -1.0), (self.height, 1.0)] {
+                if let Some((t, point)) = hit_local_disk(&local, z, self.radius, range) {
+                    let uv = disk_uv(point, self.radius);
+                    let tangents = (Vec3(1.0, 0.0, 0.0), Vec3(0.0, 1.0, 0.0));
+                    closest = Some((t, Vec3(0.0, 0.0, normal), uv, tangents));
+                    range.1 = t;
+                }
+            }
+        }
+
+        let (t, normal, uv, (along_u, along_v)) = closest?;
+        Some(
+            HitRecord::with_face_normal(
+                *ray,
+                t,
+                ray.at(t),
+                uv,
+                self.frame.world_vector(normal),
+                self.material,
+            )
+            .with_tangents(
+                self.frame.world_vector(along_u),
+                self.frame.world_vector(along_v),
+            ),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for Cylinder {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Cylinder {
+            base: self.base,
+            top: self.top,
+            radius: self.radius,
+            capped: self.capped,
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef, objects::hittable::Hittable, ray::Ray, util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::Cylinder;
+
+    #[test]
+    fn hits_side_and_caps() {
+        let cylinder = Cylinder::new(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(0.0, 2.0, 0.0),
+            0.5,
+            true,
+            MaterialRef(0),
+        );
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        let side = Ray::new(Vec3(3.0, 1.0, 0.0), Vec3(-1.0, 0.0, 0.0), 0.0);
+        let rec = cylinder.hit(&side, allowed_t).unwrap();
+        assert!((rec.t - 2.5).abs() < 1e-9);
+        assert!((rec.normal - Vec3(1.0, 0.0, 0.0)).length() < 1e-9);
+        assert!((rec.uv.v - 0.5).abs() < 1e-9);
+
+        let top = Ray::new(Vec3(0.2, 5.0, 0.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        let rec = cylinder.hit(&top, allowed_t).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert!((rec.normal - Vec3(0.0, 1.0, 0.0)).length() < 1e-9);
+
+        let open = Cylinder::new(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(0.0, 2.0, 0.0),
+            0.5,
+            false,
+            MaterialRef(0),
+        );
+        assert!(open.hit(&top, allowed_t).is_none());
+
+        let bounds = cylinder.bounding_box();
+        // The boxes of the flat ends are padded slightly.
+        assert!((bounds.x.0 + 0.5).abs() < 1e-9 && (bounds.y.1 - 2.0).abs() < 1e-3);
+    }
+}
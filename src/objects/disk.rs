@@ -0,0 +1,184 @@
+use crate::{
+    materials::{material::MaterialRef, texture::TexCoord},
+    objects::{
+        aabb::Aabb,
+        frame::Frame,
+        hittable::{HitRecord, Hittable},
+    },
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// A flat circular disk.
+pub struct Disk {
+    pub center: Vec3,
+    pub normal: Vec3,
+    pub radius: f64,
+    pub material: MaterialRef,
+
+    frame: Frame,
+    bounding_box: Aabb,
+}
+
+impl Disk {
+    pub fn new(center: Vec3, normal: Vec3, radius: f64, material: MaterialRef) -> Self {
+        let frame = Frame::new(center, normal);
+        let extent = frame.circle_extent(radius);
+        let bounding_box = Aabb::span_points(center - extent, center + extent);
+
+        Self {
+            center,
+            normal: frame.w,
+            radius,
+            material,
+
+            frame,
+            bounding_box,
+        }
+    }
+}
+
+/// Intersects a ray in the local space of a [`Frame`] with the disk of `radius` around the
+/// axis at height `z`, returning the distance along the ray and the local hit point.
+pub fn hit_local_disk(ray: &Ray, z: f64, radius: f64, allowed_t: Interval) -> Option<(f64, Vec3)> {
+    if ray.direction.z().abs() < f64::EPSILON {
+        return None;
+    }
+
+    let t = (z - ray.origin.z()) / ray.direction.z();
+    if !allowed_t.surrounds(t) {
+        return None;
+    }
+
+    let point = ray.at(t);
+    if point.x() * point.x() + point.y() * point.y() > radius * radius {
+        return None;
+    }
+    Some((t, point))
+}
+
+/// Maps a local point on a disk of `radius` into the unit square, so that an image
+/// covers the disk like a label.
+pub fn disk_uv(point: Vec3, radius: f64) -> TexCoord {
+    TexCoord::new(
+        0.5 + point.x() / (2.0 * radius),
+        0.5 + point.y() / (2.0 * radius),
+    )
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let local_ray = self.frame.local_ray(ray);
+        let (t, point) = hit_local_disk(&local_ray, 0.0, self.radius, allowed_t)?;
+
+        Some(
+            HitRecord::with_face_normal(
+                *ray,
+                t,
+                ray.at(t),
+                disk_uv(point, self.radius),
+                self.normal,
+                self.material,
+            )
+            .with_tangents(self.frame.u, self.frame.v),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for Disk {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Disk {
+            center: self.center,
+            normal: self.normal,
+            radius: self.radius,
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef, objects::hittable::Hittable, ray::Ray, util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::Disk;
+
+    fn tilted_disk() -> (Disk, Vec3) {
+        let disk = Disk::new(
+            Vec3(1.0, 2.0, 3.0),
+            Vec3(1.0, 1.0, 0.0),
+            2.0,
+            MaterialRef(0),
+        );
+        (disk, Vec3(1.0, 1.0, 0.0).normalize())
+    }
+
+    #[test]
+    fn normal_faces_the_ray() {
+        let (disk, normal) = tilted_disk();
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        let front = Ray::new(disk.center + normal * 5.0, -normal, 0.0);
+        let rec = disk.hit(&front, allowed_t).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert!((rec.normal - normal).length() < 1e-9);
+
+        let back = Ray::new(disk.center - normal * 5.0, normal, 0.0);
+        let rec = disk.hit(&back, allowed_t).unwrap();
+        assert!(!rec.front_face);
+        assert!((rec.normal + normal).length() < 1e-9);
+    }
+
+    #[test]
+    fn maps_uv_and_misses_beyond_rim() {
+        let (disk, normal) = tilted_disk();
+        let allowed_t = Interval(0.001, f64::INFINITY);
+        let towards_disk =
+            |offset: Vec3| Ray::new(disk.center + offset + normal * 5.0, -normal, 0.0);
+
+        let rec = disk
+            .hit(&towards_disk(Vec3(0.0, 0.0, 0.0)), allowed_t)
+            .unwrap();
+        assert!((rec.uv.u - 0.5).abs() < 1e-9 && (rec.uv.v - 0.5).abs() < 1e-9);
+
+        // Halfway to the rim along the first axis of its frame.
+        let rec = disk.hit(&towards_disk(disk.frame.u), allowed_t).unwrap();
+        assert!((rec.uv.u - 0.75).abs() < 1e-9 && (rec.uv.v - 0.5).abs() < 1e-9);
+        assert!((rec.tangent - disk.frame.u).length() < 1e-9);
+
+        assert!(disk
+            .hit(&towards_disk(disk.frame.v * 2.1), allowed_t)
+            .is_none());
+        let along = Ray::new(disk.center + normal * 0.5, disk.frame.u, 0.0);
+        assert!(disk.hit(&along, allowed_t).is_none());
+    }
+
+    #[test]
+    fn bounds_of_tilted_disk() {
+        let (disk, _) = tilted_disk();
+
+        // Tilted by 45°, the disk reaches sqrt(2) along x and y, but its full radius along z.
+        let bounds = disk.bounding_box();
+        let half = 2.0f64.sqrt();
+        assert!(
+            (bounds.x.0 - (1.0 - half)).abs() < 1e-9 && (bounds.x.1 - (1.0 + half)).abs() < 1e-9
+        );
+        assert!(
+            (bounds.y.0 - (2.0 - half)).abs() < 1e-9 && (bounds.y.1 - (2.0 + half)).abs() < 1e-9
+        );
+        assert!((bounds.z.0 - 1.0).abs() < 1e-9 && (bounds.z.1 - 5.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{ray::Ray, vec3::Vec3};
+
+/// An orthonormal coordinate system around an axis, used by primitives that are
+/// defined around the z axis of their own local space.
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    pub origin: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    /// Normalized direction of the axis, the local z axis.
+    pub w: Vec3,
+}
+
+impl Frame {
+    pub fn new(origin: Vec3, axis: Vec3) -> Self {
+        let w = axis.normalize();
+        // Any vector that is not parallel to the axis gives the other two directions.
+        let helper = if w.x().abs() > 0.9 {
+            Vec3(0.0, 1.0, 0.0)
+        } else {
+            Vec3(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(helper).normalize();
+        let u = v.cross(w);
+
+        Self { origin, u, v, w }
+    }
+
+    pub fn local_point(&self, point: Vec3) -> Vec3 {
+        self.local_vector(point - self.origin)
+    }
+
+    pub fn local_vector(&self, vector: Vec3) -> Vec3 {
+        Vec3(vector.dot(self.u), vector.dot(self.v), vector.dot(self.w))
+    }
+
+    pub fn world_vector(&self, vector: Vec3) -> Vec3 {
+        self.u * vector.x() + self.v * vector.y() + self.w * vector.z()
+    }
+
+    /// Returns the ray in local space. Distances along the ray stay the same.
+    pub fn local_ray(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            self.local_point(ray.origin),
+            self.local_vector(ray.direction),
+            ray.time,
+        )
+    }
+
+    /// Returns the half size of the box around a circle of `radius` perpendicular to the axis.
+    pub fn circle_extent(&self, radius: f64) -> Vec3 {
+        let extent = |w: f64| radius * (1.0 - w * w).max(0.0).sqrt();
+        Vec3(extent(self.w.x()), extent(self.w.y()), extent(self.w.z()))
+    }
+}
+
+/// Returns the angle of a local point around the axis as a fraction of a full turn,
+/// which serves as the `u` texture coordinate of surfaces around the axis.
+pub fn turn_around_axis(point: Vec3) -> f64 {
+    let pi = std::f64::consts::PI;
+    (point.y().atan2(point.x()) + pi) / (2.0 * pi)
+}
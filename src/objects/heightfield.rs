@@ -0,0 +1,383 @@
+use std::path::{Path, PathBuf};
+
+use image::{
+    error::{ParameterError, ParameterErrorKind},
+    ImageError, ImageResult,
+};
+
+use crate::{
+    materials::{material::MaterialRef, texture::TexCoord},
+    objects::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        triangle::{interpolate, intersect_triangle, triangle_hit_record},
+    },
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// Padding of the boxes around blocks of cells, which would be flat for level terrain.
+const PADDING: f64 = 1e-6;
+
+/// Minimum and maximum heights of the blocks of cells of one level of the hierarchy.
+struct MipLevel {
+    width: usize,
+    depth: usize,
+    bounds: Vec<(f32, f32)>,
+}
+
+impl MipLevel {
+    /// Merges blocks of 2x2 blocks into one.
+    fn coarser(&self) -> Self {
+        let width = self.width.div_ceil(2);
+        let depth = self.depth.div_ceil(2);
+        let mut bounds = vec![(f32::INFINITY, f32::NEG_INFINITY); width * depth];
+        for z in 0..self.depth {
+            for x in 0..self.width {
+                let (min, max) = self.bounds[z * self.width + x];
+                let block = &mut bounds[(z / 2) * width + x / 2];
+                *block = (block.0.min(min), block.1.max(max));
+            }
+        }
+
+        Self {
+            width,
+            depth,
+            bounds,
+        }
+    }
+}
+
+/// The closest hit so far: its distance, its cell, which of the two triangles of the cell
+/// was hit, and the barycentric coordinates within that triangle.
+type CellHit = (f64, usize, usize, bool, f64, f64);
+
+/// Terrain made of a grid of heights, e.g. from the brightness of a digital elevation map.
+///
+/// The grid is spanned along x and z starting at `corner`, with `scale` giving the extent
+/// of the terrain and the height of the brightest samples. Each cell is split into two
+/// triangles, but instead of storing them, rays traverse a hierarchy of the minimum and maximum
+/// heights of ever larger blocks of cells and only test the cells they pass closely.
+/// Texture coordinates span the whole grid, so an image of the same area covers it.
+pub struct Heightfield {
+    pub corner: Vec3,
+    pub scale: Vec3,
+    pub material: MaterialRef,
+    /// File the heights were loaded from, if any. Used when exporting the scene.
+    path: Option<PathBuf>,
+
+    /// Number of samples along x and z.
+    width: usize,
+    depth: usize,
+    /// Heights between 0 and 1 in rows along x, stored at single precision as DEMs can be large.
+    heights: Vec<f32>,
+    /// Starting with single cells up to a single block containing all cells.
+    levels: Vec<MipLevel>,
+    /// Scale from world space to grid space, where cells are 1 wide and heights between 0 and 1.
+    to_grid: Vec3,
+    bounding_box: Aabb,
+}
+
+impl Heightfield {
+    /// Creates a heightfield from rows of `width` heights between 0 and 1.
+    pub fn new(
+        heights: Vec<f32>,
+        width: usize,
+        corner: Vec3,
+        scale: Vec3,
+        material: MaterialRef,
+    ) -> Self {
+        let depth = heights.len() / width;
+        assert!(
+            width >= 2 && depth >= 2 && heights.len() == width * depth,
+            "heightfield needs a grid of at least 2x2 heights"
+        );
+
+        let (cells_x, cells_z) = (width - 1, depth - 1);
+        let mut bounds = Vec::with_capacity(cells_x * cells_z);
+        for z in 0..cells_z {
+            for x in 0..cells_x {
+                let corners = [
+                    heights[z * width + x],
+                    heights[z * width + x + 1],
+                    heights[(z + 1) * width + x],
+                    heights[(z + 1) * width + x + 1],
+                ];
+                let min = corners.into_iter().fold(f32::INFINITY, f32::min);
+                let max = corners.into_iter().fold(f32::NEG_INFINITY, f32::max);
+                bounds.push((min, max));
+            }
+        }
+        let mut levels = vec![MipLevel {
+            width: cells_x,
+            depth: cells_z,
+            bounds,
+        }];
+        while levels.last().unwrap().bounds.len() > 1 {
+            levels.push(levels.last().unwrap().coarser());
+        }
+
+        let (min, max) = levels.last().unwrap().bounds[0];
+        let bounding_box = Aabb::span_points(
+            corner + Vec3(0.0, scale.y() * min as f64, 0.0),
+            corner + Vec3(scale.x(), scale.y() * max as f64, scale.z()),
+        );
+
+        Self {
+            corner,
+            scale,
+            material,
+            path: None,
+
+            width,
+            depth,
+            heights,
+            levels,
+            to_grid: Vec3(
+                cells_x as f64 / scale.x(),
+                1.0 / scale.y(),
+                cells_z as f64 / scale.z(),
+            ),
+            bounding_box,
+        }
+    }
+
+    /// Loads the heights from the brightness of a grayscale image, whose first row is at the
+    /// smallest z. 16-bit images keep the full precision of elevation data.
+    pub fn open(
+        path: impl AsRef<Path>,
+        corner: Vec3,
+        scale: Vec3,
+        material: MaterialRef,
+    ) -> ImageResult<Self> {
+        let path = path.as_ref();
+        let image = image::open(path)?.into_luma16();
+        if image.width() < 2 || image.height() < 2 {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::DimensionMismatch,
+            )));
+        }
+
+        let width = image.width() as usize;
+        let heights = image
+            .into_raw()
+            .into_iter()
+            .map(|value| value as f32 / u16::MAX as f32)
+            .collect();
+        let mut heightfield = Self::new(heights, width, corner, scale, material);
+        heightfield.path = Some(path.to_owned());
+        Ok(heightfield)
+    }
+
+    fn vertex(&self, x: usize, z: usize) -> Vec3 {
+        Vec3(x as f64, self.heights[z * self.width + x] as f64, z as f64)
+    }
+
+    /// Normal in grid space at a sample, from the slopes between its neighbours.
+    fn vertex_normal(&self, x: usize, z: usize) -> Vec3 {
+        let height = |x: usize, z: usize| self.heights[z * self.width + x] as f64;
+        let (x0, x1) = (x.saturating_sub(1), (x + 1).min(self.width - 1));
+        let (z0, z1) = (z.saturating_sub(1), (z + 1).min(self.depth - 1));
+        let dx = (height(x1, z) - height(x0, z)) / (x1 - x0) as f64;
+        let dz = (height(x, z1) - height(x, z0)) / (z1 - z0) as f64;
+        Vec3(-dx, 1.0, -dz)
+    }
+
+    /// Corners of the two triangles of a cell, ordered so that their normals point upwards.
+    fn cell_triangles(&self, x: usize, z: usize) -> [[(usize, usize); 3]; 2] {
+        [
+            [(x, z), (x + 1, z + 1), (x + 1, z)],
+            [(x, z), (x, z + 1), (x + 1, z + 1)],
+        ]
+    }
+
+    /// Returns where the ray is inside of the box around a block of cells, if anywhere.
+    fn clip_block(
+        &self,
+        ray: &Ray,
+        level: usize,
+        x: usize,
+        z: usize,
+        range: Interval,
+    ) -> Option<f64> {
+        let mip = &self.levels[level];
+        let (min, max) = mip.bounds[z * mip.width + x];
+        let size = 1 << level;
+        let x_range = Interval(
+            (x * size) as f64 - PADDING,
+            ((x + 1) * size).min(self.width - 1) as f64 + PADDING,
+        );
+        let z_range = Interval(
+            (z * size) as f64 - PADDING,
+            ((z + 1) * size).min(self.depth - 1) as f64 + PADDING,
+        );
+        let y_range = Interval(min as f64 - PADDING, max as f64 + PADDING);
+        Aabb::new(x_range, y_range, z_range)
+            .clip(ray, range)
+            .map(|inside| inside.0)
+    }
+
+    /// Finds the closest hit within a block of cells that the ray is known to pass,
+    /// visiting the blocks inside of it from front to back.
+    fn hit_block(
+        &self,
+        ray: &Ray,
+        level: usize,
+        x: usize,
+        z: usize,
+        range: &mut Interval,
+        closest: &mut Option<CellHit>,
+    ) {
+        if level == 0 {
+            for (i, corners) in self.cell_triangles(x, z).into_iter().enumerate() {
+                let [a, b, c] = corners.map(|(x, z)| self.vertex(x, z));
+                if let Some((t, b1, b2)) = intersect_triangle(a, b, c, ray, *range) {
+                    *closest = Some((t, x, z, i == 1, b1, b2));
+                    range.1 = t;
+                }
+            }
+            return;
+        }
+
+        let finer = &self.levels[level - 1];
+        let mut children = [(f64::INFINITY, 0, 0); 4];
+        let mut count = 0;
+        for (dx, dz) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let (cx, cz) = (2 * x + dx, 2 * z + dz);
+            if cx >= finer.width || cz >= finer.depth {
+                continue;
+            }
+            if let Some(entry) = self.clip_block(ray, level - 1, cx, cz, *range) {
+                children[count] = (entry, cx, cz);
+                count += 1;
+            }
+        }
+
+        let children = &mut children[..count];
+        children.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for &(entry, cx, cz) in children.iter() {
+            // Blocks further away can not contain a closer hit.
+            if entry >= range.1 {
+                break;
+            }
+            self.hit_block(ray, level - 1, cx, cz, range, closest);
+        }
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        // Scaling along the axes keeps the distances along the ray unchanged.
+        let grid_ray = Ray::new(
+            (ray.origin - self.corner) * self.to_grid,
+            ray.direction * self.to_grid,
+            ray.time,
+        );
+
+        let top = self.levels.len() - 1;
+        self.clip_block(&grid_ray, top, 0, 0, allowed_t)?;
+        let mut range = allowed_t;
+        let mut closest = None;
+        self.hit_block(&grid_ray, top, 0, 0, &mut range, &mut closest);
+
+        let (t, x, z, second, b1, b2) = closest?;
+        let corners = self.cell_triangles(x, z)[second as usize];
+        let [a, b, c] = corners.map(|(x, z)| self.vertex(x, z));
+        let [na, nb, nc] = corners.map(|(x, z)| self.vertex_normal(x, z));
+
+        // Normals scale inversely to the grid coordinates.
+        let geometric_normal = ((b - a).cross(c - a) * self.to_grid).normalize();
+        let shading_normal = (interpolate(na, nb, nc, b1, b2) * self.to_grid).normalize();
+
+        let point = grid_ray.at(t);
+        let uv = TexCoord::new(
+            point.x() / (self.width - 1) as f64,
+            1.0 - point.z() / (self.depth - 1) as f64,
+        );
+
+        Some(triangle_hit_record(
+            ray,
+            t,
+            uv,
+            geometric_normal,
+            Some(shading_normal),
+            // Columns run along x and rows towards -z, like the pixels of the image.
+            (Vec3(1.0, 0.0, 0.0), Vec3(0.0, 0.0, -1.0)),
+            self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for Heightfield {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        let Some(path) = &self.path else {
+            return Err(ExportError::Unsupported(
+                "heightfield was not loaded from a file".to_owned(),
+            ));
+        };
+
+        // Like image textures, write an absolute path that works wherever the file ends up.
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        Ok(ObjectDescription::Heightfield {
+            path,
+            corner: self.corner,
+            scale: self.scale,
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef, objects::hittable::Hittable, ray::Ray, util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::Heightfield;
+
+    #[test]
+    fn hits_peak_and_valley() {
+        // A single peak in the middle of a flat 5x5 grid.
+        let mut heights = vec![0.0; 25];
+        heights[12] = 1.0;
+        let heightfield = Heightfield::new(
+            heights,
+            5,
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(8.0, 2.0, 8.0),
+            MaterialRef(0),
+        );
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        let peak = Ray::new(Vec3(4.0, 5.0, 4.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        let rec = heightfield.hit(&peak, allowed_t).unwrap();
+        assert!((rec.t - 3.0).abs() < 1e-9);
+        assert!((rec.uv.u - 0.5).abs() < 1e-9 && (rec.uv.v - 0.5).abs() < 1e-9);
+
+        let flat = Ray::new(Vec3(1.0, 5.0, 7.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        let rec = heightfield.hit(&flat, allowed_t).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-9);
+        assert!((rec.normal - Vec3(0.0, 1.0, 0.0)).length() < 1e-9);
+        assert!(rec.front_face);
+
+        // Passes along the terrain and hits the side of the peak facing it.
+        let slope = Ray::new(Vec3(-1.0, 1.0, 4.0), Vec3(1.0, 0.0, 0.0), 0.0);
+        let rec = heightfield.hit(&slope, allowed_t).unwrap();
+        assert!((rec.position.x() - 3.0).abs() < 1e-9);
+        assert!(rec.normal.x() < 0.0);
+
+        let above = Ray::new(Vec3(-1.0, 2.5, 4.0), Vec3(1.0, 0.0, 0.0), 0.0);
+        assert!(heightfield.hit(&above, allowed_t).is_none());
+    }
+}
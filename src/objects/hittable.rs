@@ -1,95 +1,174 @@
-use std::sync::Arc;
-
-use crate::materials::material::MaterialRef;
-use crate::materials::texture::TexCoord;
-use crate::objects::aabb::Aabb;
-use crate::ray::Ray;
-use crate::util::Interval;
-use crate::vec3::Vec3;
-
-#[derive(Clone)]
-pub struct HitRecord {
-    pub position: Vec3,
-    pub normal: Vec3,
-    pub t: f64,
-    pub uv: TexCoord,
-    pub front_face: bool,
-    pub material: MaterialRef,
-}
-
-impl HitRecord {
-    pub fn with_face_normal(
-        ray: Ray,
-        t: f64,
-        position: Vec3,
-        uv: TexCoord,
-        outward_normal: Vec3,
-        material: MaterialRef,
-    ) -> Self {
-        let front_face = ray.direction.dot(outward_normal) < 0.0;
-        Self {
-            position,
-            normal: if front_face {
-                outward_normal
-            } else {
-                -outward_normal
-            },
-            t,
-            uv,
-            front_face,
-            material,
-        }
-    }
-}
-
-pub trait Hittable: Send + Sync {
-    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord>;
-    fn bounding_box(&self) -> Aabb;
-}
-
-pub struct HittableList {
-    objects: Vec<Arc<dyn Hittable>>,
-    bounding_box: Aabb,
-}
-
-impl HittableList {
-    #[allow(unused)]
-    pub fn from_slice(objects: &[Arc<dyn Hittable>]) -> Self {
-        let mut bounding_box = Aabb::empty();
-        for object in objects {
-            bounding_box = Aabb::combine(bounding_box, object.bounding_box());
-        }
-
-        Self {
-            objects: objects.to_vec(),
-            bounding_box,
-        }
-    }
-
-    #[allow(unused)]
-    pub fn add(&mut self, object: Arc<dyn Hittable>) {
-        self.bounding_box = Aabb::combine(self.bounding_box, object.bounding_box());
-        self.objects.push(object);
-    }
-}
-
-impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
-        let mut maybe_rec = None;
-        let min = allowed_t.0;
-        let mut closest = allowed_t.1;
-
-        for object in &self.objects {
-            if let Some(rec) = object.hit(ray, Interval(min, closest)) {
-                closest = rec.t;
-                maybe_rec = Some(rec);
-            }
-        }
-
-        maybe_rec
-    }
-
-    fn bounding_box(&self) -> Aabb {
-        self.bounding_box
-    }
-}
+use std::sync::Arc;
+
+use crate::materials::material::MaterialRef;
+use crate::materials::texture::{TexCoord, VertexColors};
+use crate::objects::aabb::Aabb;
+use crate::objects::frame::Frame;
+use crate::ray::Ray;
+use crate::scene_file::{
+    description::ObjectDescription,
+    error::ExportError,
+    exporter::{DescribeObject, SceneExporter},
+};
+use crate::util::Interval;
+use crate::vec3::Vec3;
+
+#[derive(Clone)]
+pub struct HitRecord {
+    pub position: Vec3,
+    /// Normal used for shading, on the side of the surface the ray came from. Smooth meshes
+    /// interpolate it between their vertices and materials may perturb it further.
+    pub normal: Vec3,
+    /// Normal of the actual surface, on the side of the surface the ray came from.
+    pub geometric_normal: Vec3,
+    /// Unit direction in which `uv.u` increases, perpendicular to `normal`.
+    pub tangent: Vec3,
+    /// Unit direction in which `uv.v` increases, perpendicular to `normal` and `tangent`.
+    pub bitangent: Vec3,
+    pub t: f64,
+    pub uv: TexCoord,
+    pub front_face: bool,
+    pub material: MaterialRef,
+    /// Colors of the vertices of the hit triangle, if the object has any.
+    pub vertex_colors: Option<VertexColors>,
+}
+
+impl HitRecord {
+    pub fn with_face_normal(
+        ray: Ray,
+        t: f64,
+        position: Vec3,
+        uv: TexCoord,
+        outward_normal: Vec3,
+        material: MaterialRef,
+    ) -> Self {
+        let front_face = ray.direction.dot(outward_normal) < 0.0;
+        let normal = if front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+        // Objects with texture coordinates replace this arbitrary frame with `with_tangents`.
+        let frame = Frame::new(position, outward_normal);
+        Self {
+            position,
+            normal,
+            geometric_normal: normal,
+            tangent: frame.u,
+            bitangent: frame.v,
+            t,
+            uv,
+            front_face,
+            material,
+            vertex_colors: None,
+        }
+    }
+
+    /// Returns `normal` pointing out of the object, regardless of the side that was hit.
+    pub fn outward_normal(&self) -> Vec3 {
+        if self.front_face {
+            self.normal
+        } else {
+            -self.normal
+        }
+    }
+
+    /// Sets the tangent frame from the derivatives of the surface along the texture coordinates,
+    /// which need not be perpendicular to the normal or to each other. Where they are
+    /// degenerate, such as at the poles of a sphere, the previous frame is kept.
+    pub fn with_tangents(mut self, along_u: Vec3, along_v: Vec3) -> Self {
+        let outward = self.outward_normal();
+        let tangent = along_u - outward * along_u.dot(outward);
+        if tangent.length_squared() <= 1e-12 * along_u.length_squared() || tangent.near_zero() {
+            return self;
+        }
+
+        self.tangent = tangent.normalize();
+        let bitangent = outward.cross(self.tangent);
+        // Mirrored texture coordinates make the frame left-handed.
+        self.bitangent = if bitangent.dot(along_v) < 0.0 {
+            -bitangent
+        } else {
+            bitangent
+        };
+        self
+    }
+}
+
+pub trait Hittable: Send + Sync + DescribeObject {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord>;
+
+    /// Returns all intersections with the ray within `allowed_t`, ordered by distance.
+    ///
+    /// Constructive solid geometry needs them to track where the ray enters and leaves
+    /// objects, which `front_face` of each hit tells. By default the object is hit again
+    /// and again just beyond the previous hit.
+    fn hit_all(&self, ray: &Ray, allowed_t: Interval) -> Vec<HitRecord> {
+        let mut hits = vec![];
+        let mut min = allowed_t.0;
+        while let Some(rec) = self.hit(ray, Interval(min, allowed_t.1)) {
+            min = rec.t.next_up();
+            hits.push(rec);
+        }
+        hits
+    }
+
+    fn bounding_box(&self) -> Aabb;
+}
+
+pub struct HittableList {
+    objects: Vec<Arc<dyn Hittable>>,
+    bounding_box: Aabb,
+}
+
+impl HittableList {
+    pub fn from_slice(objects: &[Arc<dyn Hittable>]) -> Self {
+        let mut bounding_box = Aabb::empty();
+        for object in objects {
+            bounding_box = Aabb::combine(bounding_box, object.bounding_box());
+        }
+
+        Self {
+            objects: objects.to_vec(),
+            bounding_box,
+        }
+    }
+
+    #[allow(unused)]
+    pub fn add(&mut self, object: Arc<dyn Hittable>) {
+        self.bounding_box = Aabb::combine(self.bounding_box, object.bounding_box());
+        self.objects.push(object);
+    }
+}
+
+impl Hittable for HittableList {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let mut maybe_rec = None;
+        let min = allowed_t.0;
+        let mut closest = allowed_t.1;
+
+        for object in &self.objects {
+            if let Some(rec) = object.hit(ray, Interval(min, closest)) {
+                closest = rec.t;
+                maybe_rec = Some(rec);
+            }
+        }
+
+        maybe_rec
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for HittableList {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        let objects = self
+            .objects
+            .iter()
+            .map(|object| object.describe(exporter))
+            .collect::<Result<_, _>>()?;
+        Ok(ObjectDescription::Group { objects })
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use crate::{
+    matrix::Matrix4,
+    objects::aabb::Aabb,
+    objects::hittable::{HitRecord, Hittable},
+    objects::transform::Transform,
+    ray::Ray,
+    scene_file::{
+        description::{ObjectDescription, TransformStep},
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+};
+
+/// A copy of shared geometry placed in the scene by an affine transformation.
+///
+/// The geometry, usually a mesh or a `Bvh` with its own hierarchy, is only stored once
+/// no matter how many instances refer to it. Rays are transformed into the space of the
+/// geometry instead, so the BVH of the world only has to contain the instances.
+/// Unlike a plain `Transform` the geometry is exported once and referred to by name.
+pub struct Instance {
+    transform: Transform,
+}
+
+impl Instance {
+    /// Creates an instance of `object`. Panics if `transform` is not invertible.
+    pub fn new(object: Arc<dyn Hittable>, transform: Matrix4) -> Self {
+        Self {
+            transform: Transform::new(object, transform),
+        }
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        self.transform.hit(ray, allowed_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.transform.bounding_box()
+    }
+}
+
+impl DescribeObject for Instance {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Instance {
+            geometry: exporter.geometry(self.transform.object())?,
+            transform: vec![TransformStep::Matrix(self.transform.matrix().affine_rows())],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        materials::material::MaterialRef,
+        matrix::Matrix4,
+        objects::{hittable::Hittable, sphere::Sphere},
+        test_util::TestRng,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::Instance;
+
+    #[test]
+    fn hits_match_transformed_object() {
+        let unit_sphere = Arc::new(Sphere::stationary(Vec3(0.0, 0.0, 0.0), 1.0, MaterialRef(0)));
+        let center = Vec3(1.0, -2.0, 3.0);
+        let instance = Instance::new(
+            unit_sphere,
+            Matrix4::translation(center) * Matrix4::scaling(Vec3(2.0, 2.0, 2.0)),
+        );
+        let sphere = Sphere::stationary(center, 2.0, MaterialRef(0));
+
+        let mut rng = TestRng::new(1);
+        for _ in 0..1000 {
+            let ray = rng.ray_towards(center, 2.5, (3.0, 10.0));
+            let allowed_t = Interval(0.001, f64::INFINITY);
+
+            let expected = sphere.hit(&ray, allowed_t);
+            let actual = instance.hit(&ray, allowed_t);
+            assert_eq!(actual.is_some(), expected.is_some());
+
+            if let (Some(actual), Some(expected)) = (actual, expected) {
+                assert!((actual.t - expected.t).abs() < 1e-9);
+                assert!((actual.position - expected.position).length() < 1e-9);
+                assert!((actual.normal - expected.normal).length() < 1e-9);
+                assert_eq!(actual.front_face, expected.front_face);
+            }
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::{
+    materials::{
+        material::MaterialRef,
+        texture::{TexCoord, VertexColors},
+    },
+    objects::{
+        aabb::Aabb,
+        bvh::{build_nodes, traverse, BvhBuilder, BvhNode, BvhStats},
+        hittable::{HitRecord, Hittable},
+        triangle::{
+            interpolate, intersect_triangle, triangle_bounding_box, triangle_hit_record,
+            uv_tangents,
+        },
+    },
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::{Color, Vec3},
+};
+
+/// Vertex and index buffers of a triangle mesh.
+///
+/// The buffers are shared by all triangles of a mesh and can be shared
+/// between multiple meshes, e.g. to render the same geometry with different materials.
+pub struct MeshData {
+    pub positions: Vec<Vec3>,
+    /// Per-vertex normals, either empty or one for every position.
+    pub normals: Vec<Vec3>,
+    /// Per-vertex texture coordinates, either empty or one for every position.
+    pub uvs: Vec<TexCoord>,
+    /// Per-vertex colors, either empty or one for every position.
+    pub colors: Vec<Color>,
+    /// Indices into the vertex buffers, three for every triangle.
+    pub indices: Vec<[u32; 3]>,
+}
+
+impl MeshData {
+    pub fn new(positions: Vec<Vec3>, indices: Vec<[u32; 3]>) -> Self {
+        Self {
+            positions,
+            normals: vec![],
+            uvs: vec![],
+            colors: vec![],
+            indices,
+        }
+    }
+
+    /// Checks that all indices refer to existing vertices and that the optional
+    /// vertex attributes match the number of positions.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.indices.is_empty() {
+            return Err("mesh does not contain any triangles".to_owned());
+        }
+        if !self.normals.is_empty() && self.normals.len() != self.positions.len() {
+            return Err(format!(
+                "mesh has {} normals but {} positions",
+                self.normals.len(),
+                self.positions.len()
+            ));
+        }
+        if !self.uvs.is_empty() && self.uvs.len() != self.positions.len() {
+            return Err(format!(
+                "mesh has {} texture coordinates but {} positions",
+                self.uvs.len(),
+                self.positions.len()
+            ));
+        }
+        if !self.colors.is_empty() && self.colors.len() != self.positions.len() {
+            return Err(format!(
+                "mesh has {} vertex colors but {} positions",
+                self.colors.len(),
+                self.positions.len()
+            ));
+        }
+        if let Some(index) = self
+            .indices
+            .iter()
+            .flatten()
+            .find(|&&i| i as usize >= self.positions.len())
+        {
+            return Err(format!(
+                "mesh index {index} is out of range for {} positions",
+                self.positions.len()
+            ));
+        }
+        Ok(())
+    }
+
+    fn triangle(&self, i: u32) -> [Vec3; 3] {
+        self.indices[i as usize].map(|v| self.positions[v as usize])
+    }
+}
+
+/// Maximum number of triangles stored in a single leaf of the mesh BVH.
+const MAX_TRIANGLES_PER_LEAF: usize = 4;
+
+/// A triangle mesh with its own bounding volume hierarchy.
+///
+/// The triangles are not stored as individual hittables, instead the mesh keeps
+/// a flat list of BVH nodes over the indices of its triangles.
+pub struct TriangleMesh {
+    data: Arc<MeshData>,
+    material: MaterialRef,
+    /// Triangle indices ordered such that every leaf refers to a contiguous range.
+    triangles: Vec<u32>,
+    nodes: Vec<BvhNode>,
+}
+
+impl TriangleMesh {
+    /// Creates the mesh, building its BVH with `builder`.
+    pub fn build(
+        data: Arc<MeshData>,
+        material: MaterialRef,
+        builder: BvhBuilder,
+    ) -> (Self, BvhStats) {
+        if let Err(err) = data.validate() {
+            panic!("invalid mesh: {err}");
+        }
+
+        let bounds: Vec<Aabb> = (0..data.indices.len() as u32)
+            .into_par_iter()
+            .map(|i| {
+                let [a, b, c] = data.triangle(i);
+                triangle_bounding_box(a, b, c)
+            })
+            .collect();
+        let (nodes, triangles, stats) = build_nodes(&bounds, builder, MAX_TRIANGLES_PER_LEAF);
+
+        let mesh = Self {
+            data,
+            material,
+            triangles,
+            nodes,
+        };
+        (mesh, stats)
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let mut closest_hit = None;
+
+        traverse(&self.nodes, ray, allowed_t, |triangles, interval| {
+            let mut closest = None;
+            for &triangle in &self.triangles[triangles] {
+                let [a, b, c] = self.data.triangle(triangle);
+                let max = closest.unwrap_or(interval.1);
+                if let Some((t, b1, b2)) =
+                    intersect_triangle(a, b, c, ray, Interval(interval.0, max))
+                {
+                    closest = Some(t);
+                    closest_hit = Some((triangle, t, b1, b2));
+                }
+            }
+            closest
+        });
+
+        let (triangle, t, b1, b2) = closest_hit?;
+        let [i0, i1, i2] = self.data.indices[triangle as usize].map(|i| i as usize);
+        let [a, b, c] = self.data.triangle(triangle);
+
+        let (uv, tangents) = if self.data.uvs.is_empty() {
+            (TexCoord::new(b1, b2), (b - a, c - a))
+        } else {
+            let [uv0, uv1, uv2] = [self.data.uvs[i0], self.data.uvs[i1], self.data.uvs[i2]];
+            let uv = interpolate(
+                Vec3(uv0.u, uv0.v, 0.0),
+                Vec3(uv1.u, uv1.v, 0.0),
+                Vec3(uv2.u, uv2.v, 0.0),
+                b1,
+                b2,
+            );
+            let tangents = uv_tangents([a, b, c], [uv0, uv1, uv2]);
+            (TexCoord::new(uv.x(), uv.y()), tangents)
+        };
+
+        let shading_normal = if self.data.normals.is_empty() {
+            None
+        } else {
+            let normals = &self.data.normals;
+            Some(interpolate(normals[i0], normals[i1], normals[i2], b1, b2).normalize())
+        };
+
+        let mut rec = triangle_hit_record(
+            ray,
+            t,
+            uv,
+            (b - a).cross(c - a).normalize(),
+            shading_normal,
+            tangents,
+            self.material,
+        );
+        if !self.data.colors.is_empty() {
+            let colors = &self.data.colors;
+            rec.vertex_colors = Some(VertexColors {
+                colors: [colors[i0], colors[i1], colors[i2]],
+                b1,
+                b2,
+            });
+        }
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.nodes[0].bounding_box
+    }
+}
+
+impl DescribeObject for TriangleMesh {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Mesh {
+            positions: self.data.positions.clone(),
+            indices: self.data.indices.clone(),
+            normals: self.data.normals.clone(),
+            uvs: self.data.uvs.iter().map(|uv| [uv.u, uv.v]).collect(),
+            colors: self.data.colors.clone(),
+            // The mesh is already subdivided.
+            subdivision: None,
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        materials::material::MaterialRef,
+        objects::{bvh::BvhBuilder, hittable::Hittable, triangle::Triangle},
+        test_util::TestRng,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::{MeshData, TriangleMesh};
+
+    #[test]
+    fn hits_match_brute_force() {
+        let mut rng = TestRng::new(1);
+        let positions: Vec<Vec3> = (0..600).map(|_| rng.vec3(-5.0, 5.0)).collect();
+        let indices: Vec<[u32; 3]> = (0..200).map(|i| [3 * i, 3 * i + 1, 3 * i + 2]).collect();
+        let (mesh, _) = TriangleMesh::build(
+            Arc::new(MeshData::new(positions.clone(), indices.clone())),
+            MaterialRef(0),
+            BvhBuilder::default(),
+        );
+        let triangles: Vec<Triangle> = indices
+            .iter()
+            .map(|[a, b, c]| {
+                let [a, b, c] = [a, b, c].map(|&i| positions[i as usize]);
+                Triangle::new(a, b, c, MaterialRef(0))
+            })
+            .collect();
+
+        for _ in 0..1000 {
+            let ray = rng.ray_towards(Vec3(0.0, 0.0, 0.0), 5.0, (10.0, 20.0));
+            let allowed_t = Interval(0.001, f64::INFINITY);
+
+            let expected = triangles
+                .iter()
+                .filter_map(|triangle| triangle.hit(&ray, allowed_t))
+                .map(|rec| rec.t)
+                .min_by(f64::total_cmp);
+            let actual = mesh.hit(&ray, allowed_t).map(|rec| rec.t);
+
+            assert_eq!(actual, expected);
+        }
+    }
+}
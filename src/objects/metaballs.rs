@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    materials::material::MaterialRef,
+    objects::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        sphere::Sphere,
+    },
+    polynomial::{solve_polynomial_between, solve_quadratic},
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// A source of the field of [`Metaballs`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Metaball {
+    pub center: Vec3,
+    /// Distance at which the field of the ball falls off to zero.
+    pub radius: f64,
+    /// Field at the center, negative to carve into other balls.
+    #[serde(default = "unit_strength")]
+    pub strength: f64,
+}
+
+fn unit_strength() -> f64 {
+    1.0
+}
+
+impl Metaball {
+    /// Returns `1 - q` along the ray as coefficients of a quadratic in `t`, where `q` is the
+    /// squared distance from the center relative to the radius.
+    fn falloff_along(&self, ray: &Ray) -> [f64; 3] {
+        let offset = ray.origin - self.center;
+        let inv_sq_radius = 1.0 / (self.radius * self.radius);
+        [
+            -ray.direction.length_squared() * inv_sq_radius,
+            -2.0 * ray.direction.dot(offset) * inv_sq_radius,
+            1.0 - offset.length_squared() * inv_sq_radius,
+        ]
+    }
+}
+
+/// Blobs around a set of balls that melt into each other when they come close.
+///
+/// Each ball contributes the field `strength * (1 - d²/r²)³` to the points within its radius `r`,
+/// and the surface is where the sum of the fields reaches `threshold`. Along a ray the field
+/// is a polynomial of degree six between the points where it enters or leaves a ball,
+/// whose roots are the hits.
+pub struct Metaballs {
+    pub balls: Vec<Metaball>,
+    pub threshold: f64,
+    pub material: MaterialRef,
+
+    bounding_box: Aabb,
+}
+
+impl Metaballs {
+    pub fn new(balls: Vec<Metaball>, threshold: f64, material: MaterialRef) -> Self {
+        // Balls with negative strength can only remove parts of the surface.
+        let bounding_box = balls
+            .iter()
+            .filter(|ball| ball.strength > 0.0)
+            .map(|ball| {
+                let extent = Vec3(ball.radius, ball.radius, ball.radius);
+                Aabb::span_points(ball.center - extent, ball.center + extent)
+            })
+            .fold(Aabb::empty(), Aabb::combine);
+
+        Self {
+            balls,
+            threshold,
+            material,
+            bounding_box,
+        }
+    }
+
+    /// Direction in which the field falls off fastest.
+    fn normal(&self, point: Vec3) -> Vec3 {
+        let mut gradient = Vec3(0.0, 0.0, 0.0);
+        for ball in &self.balls {
+            let offset = point - ball.center;
+            let inv_sq_radius = 1.0 / (ball.radius * ball.radius);
+            let falloff = 1.0 - offset.length_squared() * inv_sq_radius;
+            if falloff > 0.0 {
+                gradient += offset * (-6.0 * ball.strength * falloff * falloff * inv_sq_radius);
+            }
+        }
+
+        if gradient.near_zero() {
+            Vec3(0.0, 1.0, 0.0)
+        } else {
+            -gradient.normalize()
+        }
+    }
+}
+
+/// Multiplies two polynomials given by their coefficients, starting with the highest power.
+fn multiply(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut product = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    product
+}
+
+impl Hittable for Metaballs {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        // Each ball contributes a cubed quadratic while the ray is inside of it.
+        let mut spans = vec![];
+        for ball in &self.balls {
+            let falloff = ball.falloff_along(ray);
+            if let [enter, leave] = solve_quadratic(falloff[0], falloff[1], falloff[2])[..] {
+                let span = Interval(enter.max(allowed_t.0), leave.min(allowed_t.1));
+                if span.0 < span.1 {
+                    let field = multiply(&multiply(&falloff, &falloff), &falloff);
+                    let field: Vec<f64> = field.iter().map(|c| c * ball.strength).collect();
+                    spans.push((span, field));
+                }
+            }
+        }
+
+        let mut bounds: Vec<f64> = spans
+            .iter()
+            .flat_map(|(span, _)| [span.0, span.1])
+            .collect();
+        bounds.sort_by(f64::total_cmp);
+
+        for pair in bounds.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            if start == end {
+                continue;
+            }
+
+            let middle = 0.5 * (start + end);
+            let mut field = [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -self.threshold];
+            for (span, coefficients) in &spans {
+                if span.contains(middle) {
+                    for (sum, c) in field.iter_mut().zip(coefficients) {
+                        *sum += c;
+                    }
+                }
+            }
+
+            let Some(&t) = solve_polynomial_between(&field, start, end)
+                .iter()
+                .find(|&&t| allowed_t.surrounds(t))
+            else {
+                continue;
+            };
+
+            let position = ray.at(t);
+            let normal = self.normal(position);
+            // Without a parametrization, images are mapped like onto a sphere.
+            let (along_u, along_v) = Sphere::get_tangents_for_point(normal);
+            return Some(
+                HitRecord::with_face_normal(
+                    *ray,
+                    t,
+                    position,
+                    Sphere::get_uv_for_point(normal),
+                    normal,
+                    self.material,
+                )
+                .with_tangents(along_u, along_v),
+            );
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for Metaballs {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Metaballs {
+            balls: self.balls.clone(),
+            threshold: self.threshold,
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef, objects::hittable::Hittable, ray::Ray, util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::{Metaball, Metaballs};
+
+    #[test]
+    fn balls_melt_together() {
+        let ball = |x: f64| Metaball {
+            center: Vec3(x, 0.0, 0.0),
+            radius: 1.0,
+            strength: 1.0,
+        };
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        // A single ball has its surface where (1 - d²)³ = 1/8, i.e. at d² = 1/2.
+        let single = Metaballs::new(vec![ball(0.0)], 0.125, MaterialRef(0));
+        let ray = Ray::new(Vec3(0.0, 0.0, 5.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = single.hit(&ray, allowed_t).unwrap();
+        assert!((rec.position.z() - 0.5f64.sqrt()).abs() < 1e-9);
+        assert!((rec.normal - Vec3(0.0, 0.0, 1.0)).length() < 1e-9);
+
+        // Between two balls, each contributes (1 - 0.64)³ ≈ 0.047, too little on its own
+        // but enough together to bridge the gap.
+        let ray = Ray::new(Vec3(0.0, 5.0, 0.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        let lonely = Metaballs::new(vec![ball(-0.8)], 0.09, MaterialRef(0));
+        assert!(lonely.hit(&ray, allowed_t).is_none());
+        let pair = Metaballs::new(vec![ball(-0.8), ball(0.8)], 0.09, MaterialRef(0));
+        let rec = pair.hit(&ray, allowed_t).unwrap();
+        assert!(rec.position.y() > 0.0 && rec.normal.y() > 0.9);
+
+        let bounds = pair.bounding_box();
+        assert!((bounds.x.0 + 1.8).abs() < 1e-9 && (bounds.x.1 - 1.8).abs() < 1e-9);
+    }
+}
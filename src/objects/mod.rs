@@ -1,9 +1,29 @@
 pub mod aabb;
+pub mod animated_transform;
+pub mod bezier_patch;
 pub mod bvh;
+#[cfg(test)]
+mod bvh_benchmark;
+pub mod cone;
 pub mod constant_volume;
+pub mod csg;
 pub mod cube;
+pub mod curves;
+pub mod cylinder;
+pub mod disk;
+pub mod frame;
+pub mod heightfield;
 pub mod hittable;
+pub mod instance;
+pub mod mesh;
+pub mod metaballs;
+pub mod plane;
+pub mod point_cloud;
 pub mod quad;
+pub mod sdf;
 pub mod sphere;
+pub mod subdivision;
+pub mod torus;
 pub mod transform;
+pub mod triangle;
 pub mod world;
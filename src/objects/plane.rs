@@ -0,0 +1,127 @@
+use crate::{
+    materials::{material::MaterialRef, texture::TexCoord},
+    objects::{
+        aabb::Aabb,
+        frame::Frame,
+        hittable::{HitRecord, Hittable},
+    },
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// An infinite plane through `point`, e.g. as the ground of a scene.
+///
+/// Its bounding box is unbounded, so BVHs keep it out of the tree and test it against every ray.
+pub struct Plane {
+    pub point: Vec3,
+    pub normal: Vec3,
+    /// Size of the square that the texture coordinates span once before repeating.
+    pub tile_size: f64,
+    pub material: MaterialRef,
+
+    frame: Frame,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, tile_size: f64, material: MaterialRef) -> Self {
+        let frame = Frame::new(point, normal);
+        Self {
+            point,
+            normal: frame.w,
+            tile_size,
+            material,
+
+            frame,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let denom = self.normal.dot(ray.direction);
+        if denom.abs() < f64::EPSILON {
+            return None;
+        }
+
+        let t = self.normal.dot(self.point - ray.origin) / denom;
+        if !allowed_t.surrounds(t) {
+            return None;
+        }
+
+        // Projecting the hit onto the plane removes rounding errors, which would otherwise
+        // flip solid textures such as checkers when the plane lies on one of their borders.
+        let position = ray.at(t);
+        let position = position - self.normal * self.normal.dot(position - self.point);
+        let local = self.frame.local_point(position) / self.tile_size;
+        let uv = TexCoord::new(local.x().rem_euclid(1.0), local.y().rem_euclid(1.0));
+
+        Some(
+            HitRecord::with_face_normal(*ray, t, position, uv, self.normal, self.material)
+                .with_tangents(self.frame.u, self.frame.v),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::UNIVERSE
+    }
+}
+
+impl DescribeObject for Plane {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Plane {
+            point: self.point,
+            normal: self.normal,
+            tile_size: self.tile_size,
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        materials::material::MaterialRef,
+        objects::{
+            bvh::{Bvh, BvhBuilder},
+            hittable::Hittable,
+            sphere::Sphere,
+        },
+        ray::Ray,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::Plane;
+
+    #[test]
+    fn bvh_keeps_plane_outside_the_tree() {
+        let plane: Arc<dyn Hittable> = Arc::new(Plane::new(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            1.0,
+            MaterialRef(0),
+        ));
+        let sphere: Arc<dyn Hittable> =
+            Arc::new(Sphere::stationary(Vec3(0.0, 1.0, 0.0), 1.0, MaterialRef(1)));
+        let (bvh, _) = Bvh::build(&[plane, sphere], BvhBuilder::default());
+        assert!(!bvh.bounding_box().is_bounded());
+
+        let allowed_t = Interval(0.001, f64::INFINITY);
+        let far_away = Ray::new(Vec3(1e6, 5.0, 3.0), Vec3(1.0, -1.0, 0.0), 0.0);
+        let rec = bvh.hit(&far_away, allowed_t).unwrap();
+        assert!((rec.position - Vec3(1e6 + 5.0, 0.0, 3.0)).length() < 1e-6);
+
+        let onto_sphere = Ray::new(Vec3(0.0, 5.0, 0.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        let rec = bvh.hit(&onto_sphere, allowed_t).unwrap();
+        assert_eq!(rec.material.0, 1);
+        assert!((rec.t - 3.0).abs() < 1e-9);
+    }
+}
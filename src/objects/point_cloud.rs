@@ -0,0 +1,339 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    materials::{material::MaterialRef, texture::VertexColors},
+    objects::{
+        aabb::Aabb,
+        bvh::{build_nodes, traverse, BvhBuilder, BvhNode, BvhStats},
+        disk::disk_uv,
+        frame::Frame,
+        hittable::{HitRecord, Hittable},
+        sphere::Sphere,
+    },
+    polynomial::solve_quadratic,
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::{Color, Vec3},
+};
+
+/// How the points of a [`PointCloud`] are drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointShape {
+    #[default]
+    Sphere,
+    /// Flat discs, facing along the normals of the points or towards the ray if there are none.
+    Disc,
+}
+
+impl PointShape {
+    pub fn is_sphere(&self) -> bool {
+        *self == PointShape::Sphere
+    }
+}
+
+/// Positions and optional attributes of the points of a point cloud, e.g. of a LiDAR scan.
+pub struct PointCloudData {
+    pub positions: Vec<Vec3>,
+    /// Per-point normals, either empty or one for every position.
+    pub normals: Vec<Vec3>,
+    /// Per-point colors, either empty or one for every position.
+    pub colors: Vec<Color>,
+}
+
+impl PointCloudData {
+    /// Checks that there are points, that the optional attributes match their number and
+    /// that the normals have a direction.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.positions.is_empty() {
+            return Err("point cloud does not contain any points".to_owned());
+        }
+        if !self.normals.is_empty() && self.normals.len() != self.positions.len() {
+            return Err(format!(
+                "point cloud has {} normals but {} points",
+                self.normals.len(),
+                self.positions.len()
+            ));
+        }
+        if !self.colors.is_empty() && self.colors.len() != self.positions.len() {
+            return Err(format!(
+                "point cloud has {} colors but {} points",
+                self.colors.len(),
+                self.positions.len()
+            ));
+        }
+        // Also rejects normals that became NaN when normalized.
+        if let Some(point) = self
+            .normals
+            .iter()
+            .position(|normal| normal.near_zero() || !normal.length_squared().is_finite())
+        {
+            return Err(format!("normal of point {point} has no direction"));
+        }
+        Ok(())
+    }
+}
+
+/// Maximum number of points stored in a single leaf of the point cloud BVH.
+const MAX_POINTS_PER_LEAF: usize = 4;
+
+/// Many small spheres or discs of the same radius with their own bounding volume hierarchy.
+///
+/// Like the triangles of a [`TriangleMesh`](crate::objects::mesh::TriangleMesh), the points
+/// are not individual hittables. The colors of the points can be shown with the `vertex_color`
+/// texture.
+pub struct PointCloud {
+    data: PointCloudData,
+    radius: f64,
+    shape: PointShape,
+    material: MaterialRef,
+    /// Point indices ordered such that every leaf refers to a contiguous range.
+    points: Vec<u32>,
+    nodes: Vec<BvhNode>,
+}
+
+impl PointCloud {
+    /// Creates a point cloud, normalizing the normals of the points and building its BVH with
+    /// `builder`. Fails if the points are invalid according to [`PointCloudData::validate`] or
+    /// the radius is not positive.
+    pub fn build(
+        mut data: PointCloudData,
+        radius: f64,
+        shape: PointShape,
+        material: MaterialRef,
+        builder: BvhBuilder,
+    ) -> Result<(Self, BvhStats), String> {
+        data.validate()?;
+        if !(radius.is_finite() && radius > 0.0) {
+            return Err("point radius must be positive".to_owned());
+        }
+        for normal in &mut data.normals {
+            *normal = normal.normalize();
+        }
+
+        let extent = Vec3(radius, radius, radius);
+        let bounds: Vec<Aabb> = data
+            .positions
+            .par_iter()
+            .map(|&p| Aabb::span_points(p - extent, p + extent))
+            .collect();
+        let (nodes, points, stats) = build_nodes(&bounds, builder, MAX_POINTS_PER_LEAF);
+
+        let cloud = Self {
+            data,
+            radius,
+            shape,
+            material,
+            points,
+            nodes,
+        };
+        Ok((cloud, stats))
+    }
+
+    /// Returns the normal of the disc of `point`, which faces the ray if the points have no normals.
+    fn disc_normal(&self, point: usize, ray: &Ray) -> Vec3 {
+        if self.data.normals.is_empty() {
+            -ray.direction
+        } else {
+            self.data.normals[point]
+        }
+    }
+
+    fn hit_point(&self, point: usize, ray: &Ray, allowed_t: Interval) -> Option<f64> {
+        let center = self.data.positions[point];
+        match self.shape {
+            PointShape::Sphere => {
+                let oc = ray.origin - center;
+                solve_quadratic(
+                    ray.direction.length_squared(),
+                    2.0 * oc.dot(ray.direction),
+                    oc.length_squared() - self.radius * self.radius,
+                )
+                .into_iter()
+                .find(|&t| allowed_t.surrounds(t))
+            }
+            PointShape::Disc => {
+                let normal = self.disc_normal(point, ray);
+                let denom = normal.dot(ray.direction);
+                if denom.abs() < f64::EPSILON {
+                    return None;
+                }
+                let t = normal.dot(center - ray.origin) / denom;
+                let inside = (ray.at(t) - center).length_squared() <= self.radius * self.radius;
+                (allowed_t.surrounds(t) && inside).then_some(t)
+            }
+        }
+    }
+}
+
+impl Hittable for PointCloud {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let mut closest_hit = None;
+
+        traverse(&self.nodes, ray, allowed_t, |points, interval| {
+            let mut closest = None;
+            for &point in &self.points[points] {
+                let max = closest.unwrap_or(interval.1);
+                if let Some(t) = self.hit_point(point as usize, ray, Interval(interval.0, max)) {
+                    closest = Some(t);
+                    closest_hit = Some((point as usize, t));
+                }
+            }
+            closest
+        });
+
+        let (point, t) = closest_hit?;
+        let center = self.data.positions[point];
+        let position = ray.at(t);
+        let mut rec = match self.shape {
+            PointShape::Sphere => {
+                let normal = (position - center) / self.radius;
+                let (along_u, along_v) = Sphere::get_tangents_for_point(normal);
+                HitRecord::with_face_normal(
+                    *ray,
+                    t,
+                    position,
+                    Sphere::get_uv_for_point(normal),
+                    normal,
+                    self.material,
+                )
+                .with_tangents(along_u, along_v)
+            }
+            PointShape::Disc => {
+                let frame = Frame::new(center, self.disc_normal(point, ray));
+                let uv = disk_uv(frame.local_point(position), self.radius);
+                HitRecord::with_face_normal(*ray, t, position, uv, frame.w, self.material)
+                    .with_tangents(frame.u, frame.v)
+            }
+        };
+        if !self.data.colors.is_empty() {
+            rec.vertex_colors = Some(VertexColors {
+                colors: [self.data.colors[point]; 3],
+                b1: 0.0,
+                b2: 0.0,
+            });
+        }
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.nodes[0].bounding_box
+    }
+}
+
+impl DescribeObject for PointCloud {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::PointCloud {
+            path: None,
+            points: self.data.positions.clone(),
+            normals: self.data.normals.clone(),
+            colors: self.data.colors.clone(),
+            radius: self.radius,
+            shape: self.shape,
+            material: Some(exporter.material(self.material)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef,
+        objects::{bvh::BvhBuilder, hittable::Hittable, sphere::Sphere},
+        ray::Ray,
+        test_util::TestRng,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::{PointCloud, PointCloudData, PointShape};
+
+    #[test]
+    fn sphere_hits_match_brute_force() {
+        let mut rng = TestRng::new(1);
+        let positions: Vec<Vec3> = (0..500).map(|_| rng.vec3(-5.0, 5.0)).collect();
+        let spheres: Vec<Sphere> = positions
+            .iter()
+            .map(|&p| Sphere::stationary(p, 0.2, MaterialRef(0)))
+            .collect();
+        let data = PointCloudData {
+            positions,
+            normals: vec![],
+            colors: vec![],
+        };
+        let (cloud, _) = PointCloud::build(
+            data,
+            0.2,
+            PointShape::Sphere,
+            MaterialRef(0),
+            BvhBuilder::default(),
+        )
+        .unwrap();
+
+        for _ in 0..1000 {
+            let ray = rng.ray_towards(Vec3(0.0, 0.0, 0.0), 5.0, (10.0, 20.0));
+            let allowed_t = Interval(0.001, f64::INFINITY);
+
+            let expected = spheres
+                .iter()
+                .filter_map(|sphere| sphere.hit(&ray, allowed_t))
+                .map(|rec| rec.t)
+                .min_by(f64::total_cmp);
+            let actual = cloud.hit(&ray, allowed_t).map(|rec| rec.t);
+            match (actual, expected) {
+                (Some(actual), Some(expected)) => assert!((actual - expected).abs() < 1e-9),
+                (actual, expected) => assert_eq!(actual, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn discs_without_normals_face_the_ray() {
+        let data = PointCloudData {
+            positions: vec![Vec3(0.0, 0.0, 0.0)],
+            normals: vec![],
+            colors: vec![],
+        };
+        let (cloud, _) = PointCloud::build(
+            data,
+            0.5,
+            PointShape::Disc,
+            MaterialRef(0),
+            BvhBuilder::default(),
+        )
+        .unwrap();
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        let ray = Ray::new(Vec3(3.0, 4.0, 0.0), Vec3(-3.0, -4.0, 0.0), 0.0);
+        let rec = cloud.hit(&ray, allowed_t).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-9);
+        assert!((rec.normal - Vec3(0.6, 0.8, 0.0)).length() < 1e-9);
+
+        let beside = Ray::new(Vec3(0.6, 5.0, 0.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        assert!(cloud.hit(&beside, allowed_t).is_none());
+    }
+
+    #[test]
+    fn rejects_normals_without_direction() {
+        let data = PointCloudData {
+            positions: vec![Vec3(0.0, 0.0, 0.0), Vec3(1.0, 0.0, 0.0)],
+            normals: vec![Vec3(0.0, 2.0, 0.0), Vec3(0.0, 0.0, 0.0)],
+            colors: vec![],
+        };
+        let result = PointCloud::build(
+            data,
+            0.5,
+            PointShape::Disc,
+            MaterialRef(0),
+            BvhBuilder::default(),
+        );
+        assert_eq!(result.err().unwrap(), "normal of point 1 has no direction");
+    }
+}
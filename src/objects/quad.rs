@@ -5,14 +5,64 @@ use crate::{
         hittable::{HitRecord, Hittable},
     },
     ray::Ray,
+    scene_file::{
+        description::{ObjectDescription, QuadShape},
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
     util::Interval,
     vec3::Vec3,
 };
 
+/// Region of the plane of a [`Quad`] that belongs to it, given in the coordinates `alpha` and
+/// `beta` of a point along the edges `u` and `v` from the origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlanarShape {
+    /// The parallelogram spanned by `u` and `v`.
+    Parallelogram,
+    /// The triangle between the origin and the ends of `u` and `v`.
+    Triangle,
+    /// The ellipse around the origin with the semi-axes `u` and `v`.
+    Ellipse,
+    /// An ellipse with an elliptical hole, whose size relative to the ellipse is `inner`.
+    Annulus { inner: f64 },
+}
+
+impl PlanarShape {
+    /// Returns the texture coordinates of the point if it lies inside the shape.
+    fn interior_uv(&self, alpha: f64, beta: f64) -> Option<TexCoord> {
+        let unit = 0.0..=1.0;
+        let inside = match *self {
+            PlanarShape::Parallelogram => unit.contains(&alpha) && unit.contains(&beta),
+            PlanarShape::Triangle => alpha >= 0.0 && beta >= 0.0 && alpha + beta <= 1.0,
+            PlanarShape::Ellipse => alpha * alpha + beta * beta <= 1.0,
+            PlanarShape::Annulus { inner } => {
+                (inner * inner..=1.0).contains(&(alpha * alpha + beta * beta))
+            }
+        };
+        if !inside {
+            return None;
+        }
+
+        Some(match self {
+            PlanarShape::Parallelogram | PlanarShape::Triangle => TexCoord::new(alpha, beta),
+            // Centered shapes span the texture from -1 to 1 along both axes.
+            PlanarShape::Ellipse | PlanarShape::Annulus { .. } => {
+                TexCoord::new((alpha + 1.0) / 2.0, (beta + 1.0) / 2.0)
+            }
+        })
+    }
+}
+
+/// A flat primitive in the plane spanned by the edges `u` and `v` from the origin,
+/// covering the region of the plane given by its shape.
 pub struct Quad {
-    pub starting_corner: Vec3,
+    /// Point the edges start from: a corner of parallelograms and triangles, but the center
+    /// of ellipses and annuli.
+    pub origin: Vec3,
     pub u: Vec3,
     pub v: Vec3,
+    pub shape: PlanarShape,
     pub material: MaterialRef,
 
     normal: Vec3,
@@ -23,20 +73,48 @@ pub struct Quad {
 }
 
 impl Quad {
-    pub fn new(starting_corner: Vec3, u: Vec3, v: Vec3, material: MaterialRef) -> Self {
-        let box1 = Aabb::span_points(starting_corner, starting_corner + u + v);
-        let box2 = Aabb::span_points(starting_corner + u, starting_corner + v);
-        let bbox = Aabb::combine(box1, box2);
+    pub fn new(origin: Vec3, u: Vec3, v: Vec3, material: MaterialRef) -> Self {
+        Self::with_shape(origin, u, v, PlanarShape::Parallelogram, material)
+    }
+
+    pub fn with_shape(
+        origin: Vec3,
+        u: Vec3,
+        v: Vec3,
+        shape: PlanarShape,
+        material: MaterialRef,
+    ) -> Self {
+        let bbox = match shape {
+            PlanarShape::Parallelogram => Aabb::combine(
+                Aabb::span_points(origin, origin + u + v),
+                Aabb::span_points(origin + u, origin + v),
+            ),
+            PlanarShape::Triangle => Aabb::combine(
+                Aabb::span_points(origin, origin + u),
+                Aabb::span_points(origin, origin + v),
+            ),
+            PlanarShape::Ellipse | PlanarShape::Annulus { .. } => {
+                // Along each axis the ellipse reaches as far as sqrt(u² + v²) from its center.
+                let extent = |u: f64, v: f64| (u * u + v * v).sqrt();
+                let extent = Vec3(
+                    extent(u.x(), v.x()),
+                    extent(u.y(), v.y()),
+                    extent(u.z(), v.z()),
+                );
+                Aabb::span_points(origin - extent, origin + extent)
+            }
+        };
 
         let n = u.cross(v);
         let normal = n.normalize();
         let w = n / n.dot(n);
-        let big_d = normal.dot(starting_corner);
+        let big_d = normal.dot(origin);
 
         Self {
-            starting_corner,
+            origin,
             u,
             v,
+            shape,
             material,
 
             normal,
@@ -69,21 +147,86 @@ impl Hittable for Quad {
         }
 
         let intersection = ray.at(t);
-        let p = intersection - self.starting_corner;
+        let p = intersection - self.origin;
         let alpha = self.w.dot(p.cross(self.v));
         let beta = self.w.dot(self.u.cross(p));
 
-        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
-            return None;
-        }
+        let uv = self.shape.interior_uv(alpha, beta)?;
+
+        Some(
+            HitRecord::with_face_normal(*ray, t, intersection, uv, self.normal, self.material)
+                .with_tangents(self.u, self.v),
+        )
+    }
+}
+
+impl DescribeObject for Quad {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        let material = exporter.material(self.material);
+        Ok(match self.shape {
+            PlanarShape::Parallelogram | PlanarShape::Triangle => ObjectDescription::Quad {
+                corner: self.origin,
+                u: self.u,
+                v: self.v,
+                shape: if self.shape == PlanarShape::Triangle {
+                    QuadShape::Triangle
+                } else {
+                    QuadShape::Parallelogram
+                },
+                material,
+            },
+            PlanarShape::Ellipse => ObjectDescription::Ellipse {
+                center: self.origin,
+                u: self.u,
+                v: self.v,
+                material,
+            },
+            PlanarShape::Annulus { inner } => ObjectDescription::Annulus {
+                center: self.origin,
+                u: self.u,
+                v: self.v,
+                inner,
+                material,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef, objects::hittable::Hittable, ray::Ray, util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::{PlanarShape, Quad};
+
+    #[test]
+    fn shapes_cover_their_interior() {
+        let shape = |shape| {
+            Quad::with_shape(
+                Vec3(0.0, 0.0, 0.0),
+                Vec3(2.0, 0.0, 0.0),
+                Vec3(0.0, 1.0, 0.0),
+                shape,
+                MaterialRef(0),
+            )
+        };
+        let hits = |quad: &Quad, x: f64, y: f64| {
+            let ray = Ray::new(Vec3(x, y, 5.0), Vec3(0.0, 0.0, -1.0), 0.0);
+            quad.hit(&ray, Interval(0.001, f64::INFINITY)).is_some()
+        };
+
+        let triangle = shape(PlanarShape::Triangle);
+        assert!(hits(&triangle, 0.5, 0.5) && !hits(&triangle, 1.5, 0.5));
+
+        let ellipse = shape(PlanarShape::Ellipse);
+        assert!(hits(&ellipse, -1.9, 0.0) && hits(&ellipse, 0.0, -0.9));
+        assert!(!hits(&ellipse, 1.5, 0.8));
+        let bounds = ellipse.bounding_box();
+        assert!((bounds.x.0 + 2.0).abs() < 1e-9 && (bounds.y.1 - 1.0).abs() < 1e-9);
 
-        Some(HitRecord::with_face_normal(
-            *ray,
-            t,
-            intersection,
-            TexCoord::new(alpha, beta),
-            self.normal,
-            self.material,
-        ))
+        let annulus = shape(PlanarShape::Annulus { inner: 0.5 });
+        assert!(!hits(&annulus, 0.5, 0.0) && hits(&annulus, 1.5, 0.0));
     }
 }
@@ -0,0 +1,461 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    materials::material::MaterialRef,
+    objects::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+        sphere::Sphere,
+    },
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// Distance below which a ray counts as touching the surface.
+const SURFACE_DISTANCE: f64 = 1e-4;
+/// Steps after which a ray that has not reached the surface counts as a miss,
+/// e.g. when passing closely along it.
+const MAX_STEPS: usize = 512;
+
+/// A signed distance function: negative inside of the surface, positive outside of it
+/// and never larger than the actual distance to the surface.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum DistanceField {
+    Sphere {
+        center: Vec3,
+        radius: f64,
+    },
+    /// An axis aligned box, with edges rounded by `rounding`.
+    Box {
+        center: Vec3,
+        half_size: Vec3,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        rounding: f64,
+    },
+    /// A ring around the y axis through its center.
+    Torus {
+        center: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+    },
+    /// A cylinder with rounded ends between `a` and `b`.
+    Capsule {
+        a: Vec3,
+        b: Vec3,
+        radius: f64,
+    },
+    /// The Mandelbulb fractal, about `scale` in radius around its center.
+    Mandelbulb {
+        center: Vec3,
+        scale: f64,
+        #[serde(default = "mandelbulb_power")]
+        power: f64,
+        #[serde(default = "mandelbulb_iterations")]
+        iterations: u32,
+    },
+    /// All of the shapes, blended together within `smoothness` of each other.
+    Union {
+        shapes: Vec<DistanceField>,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        smoothness: f64,
+    },
+    /// The part of all shapes that is inside of each of them.
+    Intersection {
+        shapes: Vec<DistanceField>,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        smoothness: f64,
+    },
+    /// The `base` shape with `removed` carved out of it.
+    Subtraction {
+        base: Box<DistanceField>,
+        removed: Box<DistanceField>,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        smoothness: f64,
+    },
+    /// Copies of the shape repeated every `period` along each axis, or not along axes
+    /// with a period of 0. The shape should fit into a single period around the origin.
+    Repeat {
+        period: Vec3,
+        shape: Box<DistanceField>,
+    },
+}
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
+}
+
+fn mandelbulb_power() -> f64 {
+    8.0
+}
+
+fn mandelbulb_iterations() -> u32 {
+    10
+}
+
+/// Blends two distances like `min`, rounding the seam within `k` of both.
+fn smooth_min(a: f64, b: f64, k: f64) -> f64 {
+    if k <= 0.0 {
+        return a.min(b);
+    }
+    let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+    b + (a - b) * h - k * h * (1.0 - h)
+}
+
+fn smooth_max(a: f64, b: f64, k: f64) -> f64 {
+    -smooth_min(-a, -b, k)
+}
+
+impl DistanceField {
+    pub fn distance(&self, p: Vec3) -> f64 {
+        match self {
+            DistanceField::Sphere { center, radius } => (p - *center).length() - radius,
+            DistanceField::Box {
+                center,
+                half_size,
+                rounding,
+            } => {
+                let d = p - *center;
+                let q = Vec3(
+                    d.x().abs() - half_size.x() + rounding,
+                    d.y().abs() - half_size.y() + rounding,
+                    d.z().abs() - half_size.z() + rounding,
+                );
+                let outside = Vec3(q.x().max(0.0), q.y().max(0.0), q.z().max(0.0)).length();
+                let inside = q.x().max(q.y()).max(q.z()).min(0.0);
+                outside + inside - rounding
+            }
+            DistanceField::Torus {
+                center,
+                major_radius,
+                minor_radius,
+            } => {
+                let d = p - *center;
+                let ring = (d.x() * d.x() + d.z() * d.z()).sqrt() - major_radius;
+                (ring * ring + d.y() * d.y()).sqrt() - minor_radius
+            }
+            DistanceField::Capsule { a, b, radius } => {
+                let (pa, ba) = (p - *a, *b - *a);
+                let h = (pa.dot(ba) / ba.length_squared()).clamp(0.0, 1.0);
+                (pa - ba * h).length() - radius
+            }
+            DistanceField::Mandelbulb {
+                center,
+                scale,
+                power,
+                iterations,
+            } => scale * mandelbulb((p - *center) / *scale, *power, *iterations),
+            DistanceField::Union { shapes, smoothness } => shapes
+                .iter()
+                .map(|shape| shape.distance(p))
+                .reduce(|a, b| smooth_min(a, b, *smoothness))
+                .unwrap_or(f64::INFINITY),
+            DistanceField::Intersection { shapes, smoothness } => shapes
+                .iter()
+                .map(|shape| shape.distance(p))
+                .reduce(|a, b| smooth_max(a, b, *smoothness))
+                .unwrap_or(f64::INFINITY),
+            DistanceField::Subtraction {
+                base,
+                removed,
+                smoothness,
+            } => smooth_max(base.distance(p), -removed.distance(p), *smoothness),
+            DistanceField::Repeat { period, shape } => {
+                let mut q = p;
+                for i in 0..3 {
+                    if period[i] > 0.0 {
+                        q[i] -= period[i] * (q[i] / period[i]).round();
+                    }
+                }
+                shape.distance(q)
+            }
+        }
+    }
+
+    /// Direction in which the distance grows fastest, found by central differences.
+    pub fn normal(&self, p: Vec3) -> Vec3 {
+        let h = SURFACE_DISTANCE * 0.5;
+        let gradient = Vec3(
+            self.distance(p + Vec3(h, 0.0, 0.0)) - self.distance(p - Vec3(h, 0.0, 0.0)),
+            self.distance(p + Vec3(0.0, h, 0.0)) - self.distance(p - Vec3(0.0, h, 0.0)),
+            self.distance(p + Vec3(0.0, 0.0, h)) - self.distance(p - Vec3(0.0, 0.0, h)),
+        );
+        if gradient.near_zero() {
+            Vec3(0.0, 1.0, 0.0)
+        } else {
+            gradient.normalize()
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            DistanceField::Sphere { radius, .. } | DistanceField::Capsule { radius, .. } => {
+                if *radius <= 0.0 {
+                    return Err("distance field radius must be positive".to_owned());
+                }
+            }
+            DistanceField::Box {
+                half_size,
+                rounding,
+                ..
+            } => {
+                let smallest = half_size.x().min(half_size.y()).min(half_size.z());
+                if smallest <= 0.0 {
+                    return Err("distance field box size must be positive".to_owned());
+                }
+                if *rounding < 0.0 || *rounding > smallest {
+                    return Err("box rounding must be between 0 and its half size".to_owned());
+                }
+            }
+            DistanceField::Torus {
+                major_radius,
+                minor_radius,
+                ..
+            } => {
+                if *major_radius <= 0.0 || *minor_radius <= 0.0 {
+                    return Err("distance field torus radii must be positive".to_owned());
+                }
+            }
+            DistanceField::Mandelbulb {
+                scale,
+                power,
+                iterations,
+                ..
+            } => {
+                if *scale <= 0.0 || *power < 2.0 || *iterations == 0 {
+                    return Err(
+                        "mandelbulb needs a positive scale, a power of at least 2 and iterations"
+                            .to_owned(),
+                    );
+                }
+            }
+            DistanceField::Union { shapes, smoothness }
+            | DistanceField::Intersection { shapes, smoothness } => {
+                if shapes.is_empty() {
+                    return Err("distance field union and intersection need shapes".to_owned());
+                }
+                if *smoothness < 0.0 {
+                    return Err("distance field smoothness must not be negative".to_owned());
+                }
+                for shape in shapes {
+                    shape.validate()?;
+                }
+            }
+            DistanceField::Subtraction {
+                base,
+                removed,
+                smoothness,
+            } => {
+                if *smoothness < 0.0 {
+                    return Err("distance field smoothness must not be negative".to_owned());
+                }
+                base.validate()?;
+                removed.validate()?;
+            }
+            DistanceField::Repeat { period, shape } => {
+                if period.x() < 0.0 || period.y() < 0.0 || period.z() < 0.0 {
+                    return Err("repetition period must not be negative".to_owned());
+                }
+                shape.validate()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Distance estimate of the Mandelbulb with its usual radius of about 1 around the origin.
+fn mandelbulb(p: Vec3, power: f64, iterations: u32) -> f64 {
+    let mut z = p;
+    let mut derivative = 1.0;
+    let mut r = z.length();
+    for _ in 0..iterations {
+        if !(1e-12..=2.0).contains(&r) {
+            break;
+        }
+
+        let theta = (z.z() / r).acos() * power;
+        let phi = z.y().atan2(z.x()) * power;
+        derivative = r.powf(power - 1.0) * power * derivative + 1.0;
+        let zr = r.powf(power);
+        z = Vec3(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            theta.cos(),
+        ) * zr
+            + p;
+        r = z.length();
+    }
+
+    if r < 1e-12 {
+        return 0.0;
+    }
+    0.5 * r.ln() * r / derivative
+}
+
+/// A surface given by a signed distance function, rendered by sphere tracing: a ray steps
+/// forward by the distance to the surface until it touches it.
+///
+/// Distance functions can describe fractals and blended shapes, but have no extent of their own,
+/// so the surface is only traced inside of the given bounding box.
+pub struct Sdf {
+    pub distance: DistanceField,
+    pub material: MaterialRef,
+
+    bounding_box: Aabb,
+}
+
+impl Sdf {
+    pub fn new(distance: DistanceField, bounding_box: Aabb, material: MaterialRef) -> Self {
+        Self {
+            distance,
+            material,
+            bounding_box,
+        }
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let range = self.bounding_box.clip(ray, allowed_t)?;
+        let speed = ray.direction.length();
+
+        let mut t = range.0;
+        let start = self.distance.distance(ray.at(t));
+        // Rays entering the bounding box come from outside of the surface. Rays leaving the
+        // surface, e.g. after a bounce, start right on it, and whether they trace its outside
+        // or inside depends on which way they are heading.
+        let side = if range.0 > allowed_t.0 {
+            1.0
+        } else if start.abs() < SURFACE_DISTANCE {
+            -self.distance.normal(ray.at(t)).dot(ray.direction).signum()
+        } else {
+            start.signum()
+        };
+        let mut leaving = range.0 == allowed_t.0 && side * start < SURFACE_DISTANCE;
+
+        for _ in 0..MAX_STEPS {
+            let position = ray.at(t);
+            let distance = side * self.distance.distance(position);
+            if distance < SURFACE_DISTANCE {
+                if !leaving {
+                    // Without a parametrization, images are mapped like onto a sphere.
+                    let normal = self.distance.normal(position);
+                    let (along_u, along_v) = Sphere::get_tangents_for_point(normal);
+                    return Some(
+                        HitRecord::with_face_normal(
+                            *ray,
+                            t,
+                            position,
+                            Sphere::get_uv_for_point(normal),
+                            normal,
+                            self.material,
+                        )
+                        .with_tangents(along_u, along_v),
+                    );
+                }
+            } else {
+                leaving = false;
+            }
+
+            t += distance.max(SURFACE_DISTANCE) / speed;
+            if t > range.1 {
+                return None;
+            }
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for Sdf {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        let b = self.bounding_box;
+        Ok(ObjectDescription::Sdf {
+            distance: self.distance.clone(),
+            bounds: [Vec3(b.x.0, b.y.0, b.z.0), Vec3(b.x.1, b.y.1, b.z.1)],
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef,
+        objects::{aabb::Aabb, hittable::Hittable},
+        ray::Ray,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::{DistanceField, Sdf};
+
+    fn sphere(x: f64) -> DistanceField {
+        DistanceField::Sphere {
+            center: Vec3(x, 0.0, 0.0),
+            radius: 1.0,
+        }
+    }
+
+    #[test]
+    fn traces_sphere_from_both_sides() {
+        let sdf = Sdf::new(
+            sphere(0.0),
+            Aabb::span_points(Vec3(-1.0, -1.0, -1.0), Vec3(1.0, 1.0, 1.0)),
+            MaterialRef(0),
+        );
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        let outside = Ray::new(Vec3(0.0, 0.0, 5.0), Vec3(0.0, 0.0, -2.0), 0.0);
+        let rec = sdf.hit(&outside, allowed_t).unwrap();
+        assert!((rec.position.z() - 1.0).abs() < 1e-3);
+        assert!((rec.normal - Vec3(0.0, 0.0, 1.0)).length() < 1e-3);
+        assert!(rec.front_face);
+
+        // Refracted into the sphere at its surface, the ray has to find the far side.
+        let refracted = Ray::new(rec.position, Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = sdf.hit(&refracted, allowed_t).unwrap();
+        assert!((rec.position.z() + 1.0).abs() < 1e-3);
+        assert!(!rec.front_face);
+
+        let reflected = Ray::new(Vec3(0.0, 0.0, 1.0), Vec3(0.0, 1.0, 1.0), 0.0);
+        assert!(sdf.hit(&reflected, allowed_t).is_none());
+    }
+
+    #[test]
+    fn combines_and_repeats_shapes() {
+        let smooth = DistanceField::Union {
+            shapes: vec![sphere(-0.9), sphere(0.9)],
+            smoothness: 0.5,
+        };
+        // Blending fills the gap between the spheres above the point where they touch.
+        assert!(smooth.distance(Vec3(0.0, 0.5, 0.0)) < 0.0);
+        assert!(sphere(-0.9).distance(Vec3(0.0, 0.5, 0.0)) > 0.0);
+
+        let hollow = DistanceField::Subtraction {
+            base: Box::new(sphere(0.0)),
+            removed: Box::new(DistanceField::Sphere {
+                center: Vec3(0.0, 0.0, 0.0),
+                radius: 0.5,
+            }),
+            smoothness: 0.0,
+        };
+        assert!((hollow.distance(Vec3(0.0, 0.0, 0.0)) - 0.5).abs() < 1e-9);
+
+        let repeated = DistanceField::Repeat {
+            period: Vec3(4.0, 0.0, 0.0),
+            shape: Box::new(sphere(0.0)),
+        };
+        assert!((repeated.distance(Vec3(41.0, 0.0, 0.0))).abs() < 1e-9);
+        assert!((repeated.distance(Vec3(0.0, 3.0, 0.0)) - 2.0).abs() < 1e-9);
+    }
+}
@@ -1,144 +1,213 @@
-use crate::materials::material::MaterialRef;
-use crate::materials::texture::TexCoord;
-use crate::objects::aabb::Aabb;
-use crate::objects::hittable::{HitRecord, Hittable};
-use crate::ray::Ray;
-use crate::util::Interval;
-use crate::vec3::Vec3;
-
-#[derive(Clone)]
-pub struct Sphere {
-    pub start_center: Vec3,
-    pub move_dir: Vec3,
-    pub radius: f64,
-    pub material: MaterialRef,
-    bounding_box: Aabb,
-}
-
-impl Sphere {
-    pub fn stationary(center: Vec3, radius: f64, material: MaterialRef) -> Self {
-        let r_vec = Vec3(radius, radius, radius);
-        let bounding_box = Aabb::span_points(center - r_vec, center + r_vec);
-
-        Self {
-            start_center: center,
-            move_dir: Vec3(0.0, 0.0, 0.0),
-            radius,
-            material,
-            bounding_box,
-        }
-    }
-
-    pub fn moving(
-        start_center: Vec3,
-        end_center: Vec3,
-        radius: f64,
-        material: MaterialRef,
-    ) -> Self {
-        let r_vec = Vec3(radius, radius, radius);
-
-        let box1 = Aabb::span_points(start_center - r_vec, start_center + r_vec);
-        let box2 = Aabb::span_points(end_center - r_vec, end_center + r_vec);
-        let bounding_box = Aabb::combine(box1, box2);
-
-        Self {
-            start_center,
-            move_dir: end_center - start_center,
-            radius,
-            material,
-            bounding_box,
-        }
-    }
-
-    pub fn get_center(&self, time: f64) -> Vec3 {
-        self.start_center + self.move_dir * time
-    }
-
-    pub fn get_uv_for_point(point: Vec3) -> TexCoord {
-        let pi = std::f64::consts::PI;
-
-        let theta = f64::acos(-point.y());
-        let phi = f64::atan2(-point.z(), point.x()) + pi;
-
-        TexCoord::new(phi / (2.0 * pi), theta / pi)
-    }
-}
-
-impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
-        let center = self.get_center(ray.time);
-
-        let oc = ray.origin - center;
-        let a = ray.direction.length_squared();
-        let half_b = oc.dot(ray.direction);
-        let c = oc.length_squared() - self.radius * self.radius;
-
-        let discriminant = half_b * half_b - a * c;
-        if discriminant < 0.0 {
-            return None;
-        }
-
-        let sqrt_d = discriminant.sqrt();
-
-        let mut root = (-half_b - sqrt_d) / a;
-        if !allowed_t.surrounds(root) {
-            root = (-half_b + sqrt_d) / a;
-            if !allowed_t.surrounds(root) {
-                return None;
-            }
-        }
-
-        let position = ray.at(root);
-        let outward_normal = (position - center) / self.radius;
-        Some(HitRecord::with_face_normal(
-            *ray,
-            root,
-            position,
-            Sphere::get_uv_for_point(outward_normal),
-            outward_normal,
-            self.material,
-        ))
-    }
-
-    fn bounding_box(&self) -> Aabb {
-        self.bounding_box
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::{materials::texture::TexCoord, objects::sphere::Sphere, vec3::Vec3};
-
-    #[test]
-    fn get_uv_for_point() {
-        assert_eq!(
-            Sphere::get_uv_for_point(Vec3(1.0, 0.0, 0.0)),
-            TexCoord::new(0.5, 0.5)
-        );
-
-        assert_eq!(
-            Sphere::get_uv_for_point(Vec3(0.0, 1.0, 0.0)),
-            TexCoord::new(0.5, 1.0)
-        );
-
-        assert_eq!(
-            Sphere::get_uv_for_point(Vec3(0.0, 0.0, 1.0)),
-            TexCoord::new(0.25, 0.5)
-        );
-
-        assert_eq!(
-            Sphere::get_uv_for_point(Vec3(-1.0, 0.0, 0.0)),
-            TexCoord::new(0.0, 0.5)
-        );
-
-        assert_eq!(
-            Sphere::get_uv_for_point(Vec3(0.0, -1.0, 0.0)),
-            TexCoord::new(0.5, 0.0)
-        );
-
-        assert_eq!(
-            Sphere::get_uv_for_point(Vec3(0.0, 0.0, -1.0)),
-            TexCoord::new(0.75, 0.5)
-        );
-    }
-}
+use crate::materials::material::MaterialRef;
+use crate::materials::texture::TexCoord;
+use crate::objects::aabb::Aabb;
+use crate::objects::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::scene_file::{
+    description::ObjectDescription,
+    error::ExportError,
+    exporter::{DescribeObject, SceneExporter},
+};
+use crate::util::Interval;
+use crate::vec3::Vec3;
+
+#[derive(Clone)]
+pub struct Sphere {
+    pub start_center: Vec3,
+    pub move_dir: Vec3,
+    pub radius: f64,
+    pub material: MaterialRef,
+    bounding_box: Aabb,
+}
+
+impl Sphere {
+    pub fn stationary(center: Vec3, radius: f64, material: MaterialRef) -> Self {
+        let r_vec = Vec3(radius, radius, radius);
+        let bounding_box = Aabb::span_points(center - r_vec, center + r_vec);
+
+        Self {
+            start_center: center,
+            move_dir: Vec3(0.0, 0.0, 0.0),
+            radius,
+            material,
+            bounding_box,
+        }
+    }
+
+    /// Creates a sphere that moves from `start_center` to `end_center` between the times
+    /// 0 and 1, which is the default exposure of a still image. Outside of that range it rests
+    /// at the nearer of the two centers, so that it stays in place over the later frames
+    /// of an animation, whose frame `n` is exposed at times from `n` on.
+    pub fn moving(
+        start_center: Vec3,
+        end_center: Vec3,
+        radius: f64,
+        material: MaterialRef,
+    ) -> Self {
+        let r_vec = Vec3(radius, radius, radius);
+
+        let box1 = Aabb::span_points(start_center - r_vec, start_center + r_vec);
+        let box2 = Aabb::span_points(end_center - r_vec, end_center + r_vec);
+        let bounding_box = Aabb::combine(box1, box2);
+
+        Self {
+            start_center,
+            move_dir: end_center - start_center,
+            radius,
+            material,
+            bounding_box,
+        }
+    }
+
+    /// Returns the center at `time`. A moving sphere travels from its start to its end center
+    /// between the times 0 and 1 and rests there before and after, like an animated transform
+    /// before its first and after its last keyframe.
+    pub fn get_center(&self, time: f64) -> Vec3 {
+        self.start_center + self.move_dir * time.clamp(0.0, 1.0)
+    }
+
+    pub fn get_uv_for_point(point: Vec3) -> TexCoord {
+        let pi = std::f64::consts::PI;
+
+        let theta = f64::acos(-point.y());
+        let phi = f64::atan2(-point.z(), point.x()) + pi;
+
+        TexCoord::new(phi / (2.0 * pi), theta / pi)
+    }
+
+    /// Returns the directions in which the coordinates of `get_uv_for_point` increase
+    /// at a point on the unit sphere.
+    pub fn get_tangents_for_point(point: Vec3) -> (Vec3, Vec3) {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        (Vec3(z, 0.0, -x), Vec3(-x * y, x * x + z * z, -y * z))
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let center = self.get_center(ray.time);
+
+        let oc = ray.origin - center;
+        let a = ray.direction.length_squared();
+        let half_b = oc.dot(ray.direction);
+        let c = oc.length_squared() - self.radius * self.radius;
+
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let sqrt_d = discriminant.sqrt();
+
+        let mut root = (-half_b - sqrt_d) / a;
+        if !allowed_t.surrounds(root) {
+            root = (-half_b + sqrt_d) / a;
+            if !allowed_t.surrounds(root) {
+                return None;
+            }
+        }
+
+        let position = ray.at(root);
+        let outward_normal = (position - center) / self.radius;
+        let (along_u, along_v) = Sphere::get_tangents_for_point(outward_normal);
+        Some(
+            HitRecord::with_face_normal(
+                *ray,
+                root,
+                position,
+                Sphere::get_uv_for_point(outward_normal),
+                outward_normal,
+                self.material,
+            )
+            .with_tangents(along_u, along_v),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for Sphere {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        let end_center = if self.move_dir.near_zero() {
+            None
+        } else {
+            Some(self.start_center + self.move_dir)
+        };
+
+        Ok(ObjectDescription::Sphere {
+            center: self.start_center,
+            end_center,
+            radius: self.radius,
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::{material::MaterialRef, texture::TexCoord},
+        objects::{hittable::Hittable, sphere::Sphere},
+        ray::Ray,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    #[test]
+    fn get_uv_for_point() {
+        assert_eq!(
+            Sphere::get_uv_for_point(Vec3(1.0, 0.0, 0.0)),
+            TexCoord::new(0.5, 0.5)
+        );
+
+        assert_eq!(
+            Sphere::get_uv_for_point(Vec3(0.0, 1.0, 0.0)),
+            TexCoord::new(0.5, 1.0)
+        );
+
+        assert_eq!(
+            Sphere::get_uv_for_point(Vec3(0.0, 0.0, 1.0)),
+            TexCoord::new(0.25, 0.5)
+        );
+
+        assert_eq!(
+            Sphere::get_uv_for_point(Vec3(-1.0, 0.0, 0.0)),
+            TexCoord::new(0.0, 0.5)
+        );
+
+        assert_eq!(
+            Sphere::get_uv_for_point(Vec3(0.0, -1.0, 0.0)),
+            TexCoord::new(0.5, 0.0)
+        );
+
+        assert_eq!(
+            Sphere::get_uv_for_point(Vec3(0.0, 0.0, -1.0)),
+            TexCoord::new(0.75, 0.5)
+        );
+    }
+
+    #[test]
+    fn moving_sphere_rests_after_moving() {
+        let sphere = Sphere::moving(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(0.0, 2.0, 0.0),
+            0.5,
+            MaterialRef(0),
+        );
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        // Later frames of an animation are exposed at times beyond 1.
+        for (time, y) in [(-1.0, 0.0), (0.5, 1.0), (1.0, 2.0), (3.25, 2.0)] {
+            let ray = Ray::new(Vec3(0.0, y, 5.0), Vec3(0.0, 0.0, -1.0), time);
+            let rec = sphere
+                .hit(&ray, allowed_t)
+                .expect("ray through the center must hit");
+            assert!((rec.t - 4.5).abs() < 1e-9, "time {time}");
+
+            let bounds = sphere.bounding_box();
+            assert!(bounds.y.0 <= rec.position.y() && rec.position.y() <= bounds.y.1);
+        }
+    }
+}
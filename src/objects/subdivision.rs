@@ -0,0 +1,493 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    materials::texture::TexCoord,
+    objects::mesh::MeshData,
+    vec3::{Color, Vec3},
+};
+
+/// The rules by which [`PolygonMesh::subdivide`] refines a mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubdivisionScheme {
+    /// Loop subdivision, which splits every triangle into four. Other polygons are split
+    /// into triangles first.
+    Loop,
+    /// Catmull-Clark subdivision, which splits every polygon into quads, one for each corner.
+    CatmullClark,
+}
+
+/// Smoothing of imported meshes at load time.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Subdivision {
+    pub scheme: SubdivisionScheme,
+    /// How often the mesh is subdivided, each time multiplying the number of faces by about four.
+    #[serde(default = "single_level")]
+    pub levels: u32,
+}
+
+fn single_level() -> u32 {
+    1
+}
+
+impl Subdivision {
+    /// Levels beyond this would rarely be visible but take a lot of memory.
+    pub const MAX_LEVELS: u32 = 6;
+}
+
+/// A mesh whose faces are polygons with any number of corners, with the same per-vertex
+/// attributes as [`MeshData`].
+pub struct PolygonMesh {
+    pub positions: Vec<Vec3>,
+    /// Per-vertex texture coordinates, either empty or one for every position.
+    pub uvs: Vec<TexCoord>,
+    /// Per-vertex colors, either empty or one for every position.
+    pub colors: Vec<Color>,
+    pub faces: Vec<Vec<u32>>,
+}
+
+impl PolygonMesh {
+    pub fn from_triangles(data: &MeshData) -> Self {
+        Self {
+            positions: data.positions.clone(),
+            uvs: data.uvs.clone(),
+            colors: data.colors.clone(),
+            faces: data
+                .indices
+                .iter()
+                .map(|triangle| triangle.to_vec())
+                .collect(),
+        }
+    }
+
+    /// Smooths the mesh by subdividing it, and returns the result as triangles with normals
+    /// averaged over the faces around each vertex.
+    ///
+    /// Vertices at the same position are treated as one point of the surface, so that seams
+    /// between different texture coordinates stay closed. Texture coordinates and colors are
+    /// interpolated linearly. Edges with only one face are kept as sharp boundaries.
+    pub fn subdivide(&self, subdivision: Subdivision) -> MeshData {
+        let mut mesh = Subdivider::new(self);
+        if subdivision.scheme == SubdivisionScheme::Loop {
+            mesh.triangulate();
+        }
+        for _ in 0..subdivision.levels {
+            mesh = match subdivision.scheme {
+                SubdivisionScheme::Loop => mesh.loop_step(),
+                SubdivisionScheme::CatmullClark => mesh.catmull_clark_step(),
+            };
+        }
+        mesh.to_mesh_data()
+    }
+}
+
+type EdgeKey = (usize, usize);
+
+fn edge_key(a: usize, b: usize) -> EdgeKey {
+    (a.min(b), a.max(b))
+}
+
+/// The faces around an edge between two points, in the order the edges are first found.
+struct Edges {
+    index: HashMap<EdgeKey, usize>,
+    keys: Vec<EdgeKey>,
+    faces: Vec<Vec<usize>>,
+}
+
+impl Edges {
+    fn is_boundary(&self, edge: usize) -> bool {
+        self.faces[edge].len() != 2
+    }
+}
+
+struct Subdivider {
+    points: Vec<Vec3>,
+    /// Point of every vertex. Vertices at the same point differ in their attributes.
+    vertex_points: Vec<usize>,
+    uvs: Vec<TexCoord>,
+    colors: Vec<Color>,
+    faces: Vec<Vec<usize>>,
+}
+
+impl Subdivider {
+    fn new(mesh: &PolygonMesh) -> Self {
+        let mut point_index = HashMap::new();
+        let mut points = vec![];
+        let vertex_points = mesh
+            .positions
+            .iter()
+            .map(|p| {
+                let bits = [p.x(), p.y(), p.z()].map(f64::to_bits);
+                *point_index.entry(bits).or_insert_with(|| {
+                    points.push(*p);
+                    points.len() - 1
+                })
+            })
+            .collect();
+
+        Self {
+            points,
+            vertex_points,
+            uvs: mesh.uvs.clone(),
+            colors: mesh.colors.clone(),
+            faces: mesh
+                .faces
+                .iter()
+                .map(|face| face.iter().map(|&v| v as usize).collect())
+                .collect(),
+        }
+    }
+
+    fn triangulate(&mut self) {
+        self.faces = self
+            .faces
+            .iter()
+            .flat_map(|face| (1..face.len() - 1).map(|i| vec![face[0], face[i], face[i + 1]]))
+            .collect();
+    }
+
+    fn point(&self, vertex: usize) -> Vec3 {
+        self.points[self.vertex_points[vertex]]
+    }
+
+    fn edges(&self) -> Edges {
+        let mut edges = Edges {
+            index: HashMap::new(),
+            keys: vec![],
+            faces: vec![],
+        };
+        for (f, face) in self.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                let a = self.vertex_points[face[i]];
+                let b = self.vertex_points[face[(i + 1) % face.len()]];
+                let key = edge_key(a, b);
+                let edge = *edges.index.entry(key).or_insert_with(|| {
+                    edges.keys.push(key);
+                    edges.faces.push(vec![]);
+                    edges.keys.len() - 1
+                });
+                edges.faces[edge].push(f);
+            }
+        }
+        edges
+    }
+
+    /// Moves points on the boundary of the mesh by the boundary rule shared by both schemes,
+    /// returning `None` for points inside of the mesh.
+    fn boundary_point(&self, point: usize, boundary_neighbours: &[usize]) -> Option<Vec3> {
+        match boundary_neighbours {
+            [] => None,
+            [a, b] => Some(self.points[point] * 0.75 + (self.points[*a] + self.points[*b]) * 0.125),
+            // Corners of several boundaries stay in place.
+            _ => Some(self.points[point]),
+        }
+    }
+
+    /// Starts the next level with the already moved points, keeping the existing vertices.
+    fn next_level(&self, points: Vec<Vec3>) -> Self {
+        Self {
+            points,
+            vertex_points: self.vertex_points.clone(),
+            uvs: self.uvs.clone(),
+            colors: self.colors.clone(),
+            faces: vec![],
+        }
+    }
+
+    /// Adds a vertex at `point` whose attributes are the average of those of `sources`.
+    fn add_vertex(&mut self, point: usize, sources: &[usize]) -> usize {
+        let weight = 1.0 / sources.len() as f64;
+        if !self.uvs.is_empty() {
+            let (u, v) = sources.iter().fold((0.0, 0.0), |(u, v), &s| {
+                (u + self.uvs[s].u * weight, v + self.uvs[s].v * weight)
+            });
+            self.uvs.push(TexCoord::new(u, v));
+        }
+        if !self.colors.is_empty() {
+            let color = sources.iter().fold(Color::new(0.0, 0.0, 0.0), |sum, &s| {
+                sum + self.colors[s] * weight
+            });
+            self.colors.push(color);
+        }
+        self.vertex_points.push(point);
+        self.vertex_points.len() - 1
+    }
+
+    fn catmull_clark_step(&self) -> Self {
+        let edges = self.edges();
+        let face_points: Vec<Vec3> = self
+            .faces
+            .iter()
+            .map(|face| {
+                face.iter()
+                    .fold(Vec3(0.0, 0.0, 0.0), |sum, &v| sum + self.point(v))
+                    / face.len() as f64
+            })
+            .collect();
+        let edge_points: Vec<Vec3> = (0..edges.keys.len())
+            .map(|e| {
+                let (a, b) = edges.keys[e];
+                let ends = self.points[a] + self.points[b];
+                if edges.is_boundary(e) {
+                    ends * 0.5
+                } else {
+                    let [f, g] = [edges.faces[e][0], edges.faces[e][1]];
+                    (ends + face_points[f] + face_points[g]) * 0.25
+                }
+            })
+            .collect();
+
+        // Averages of the surrounding face points and edge midpoints of every point.
+        let mut face_sums = vec![(Vec3(0.0, 0.0, 0.0), 0); self.points.len()];
+        let mut edge_sums = vec![(Vec3(0.0, 0.0, 0.0), 0); self.points.len()];
+        let mut boundary_neighbours = vec![vec![]; self.points.len()];
+        for (face, face_point) in self.faces.iter().zip(&face_points) {
+            for &v in face {
+                let sum = &mut face_sums[self.vertex_points[v]];
+                *sum = (sum.0 + *face_point, sum.1 + 1);
+            }
+        }
+        for (e, &(a, b)) in edges.keys.iter().enumerate() {
+            let middle = (self.points[a] + self.points[b]) * 0.5;
+            for (p, other) in [(a, b), (b, a)] {
+                edge_sums[p] = (edge_sums[p].0 + middle, edge_sums[p].1 + 1);
+                if edges.is_boundary(e) {
+                    boundary_neighbours[p].push(other);
+                }
+            }
+        }
+
+        let mut points: Vec<Vec3> = (0..self.points.len())
+            .map(|p| {
+                if let Some(point) = self.boundary_point(p, &boundary_neighbours[p]) {
+                    return point;
+                }
+                let (face_sum, face_count) = face_sums[p];
+                let (edge_sum, valence) = edge_sums[p];
+                if face_count == 0 {
+                    return self.points[p];
+                }
+                let n = valence as f64;
+                let faces = face_sum / face_count as f64;
+                let edges = edge_sum / n;
+                (faces + edges * 2.0 + self.points[p] * (n - 3.0)) / n
+            })
+            .collect();
+        let first_face_point = points.len();
+        points.extend(face_points);
+        let first_edge_point = points.len();
+        points.extend(edge_points);
+
+        let mut next = self.next_level(points);
+        let mut edge_vertices = HashMap::new();
+        let mut edge_vertex = |next: &mut Self, a: usize, b: usize| {
+            *edge_vertices.entry(edge_key(a, b)).or_insert_with(|| {
+                let key = edge_key(self.vertex_points[a], self.vertex_points[b]);
+                next.add_vertex(first_edge_point + edges.index[&key], &[a, b])
+            })
+        };
+
+        for (f, face) in self.faces.iter().enumerate() {
+            let center = next.add_vertex(first_face_point + f, face);
+            let n = face.len();
+            for i in 0..n {
+                let (previous, current, following) =
+                    (face[(i + n - 1) % n], face[i], face[(i + 1) % n]);
+                let quad = vec![
+                    current,
+                    edge_vertex(&mut next, current, following),
+                    center,
+                    edge_vertex(&mut next, previous, current),
+                ];
+                next.faces.push(quad);
+            }
+        }
+        next
+    }
+
+    fn loop_step(&self) -> Self {
+        let edges = self.edges();
+        let edge_points: Vec<Vec3> = (0..edges.keys.len())
+            .map(|e| {
+                let (a, b) = edges.keys[e];
+                let ends = self.points[a] + self.points[b];
+                if edges.is_boundary(e) {
+                    return ends * 0.5;
+                }
+                // The corners of both triangles opposite of the edge.
+                let opposite = edges.faces[e].iter().fold(Vec3(0.0, 0.0, 0.0), |sum, &f| {
+                    let corner = self.faces[f]
+                        .iter()
+                        .map(|&v| self.vertex_points[v])
+                        .find(|&p| p != a && p != b)
+                        .unwrap_or(a);
+                    sum + self.points[corner]
+                });
+                ends * 0.375 + opposite * 0.125
+            })
+            .collect();
+
+        let mut neighbours = vec![vec![]; self.points.len()];
+        let mut boundary_neighbours = vec![vec![]; self.points.len()];
+        for (e, &(a, b)) in edges.keys.iter().enumerate() {
+            for (p, other) in [(a, b), (b, a)] {
+                neighbours[p].push(other);
+                if edges.is_boundary(e) {
+                    boundary_neighbours[p].push(other);
+                }
+            }
+        }
+
+        let mut points: Vec<Vec3> = (0..self.points.len())
+            .map(|p| {
+                if let Some(point) = self.boundary_point(p, &boundary_neighbours[p]) {
+                    return point;
+                }
+                let n = neighbours[p].len();
+                if n == 0 {
+                    return self.points[p];
+                }
+                let beta = if n == 3 {
+                    3.0 / 16.0
+                } else {
+                    3.0 / (8.0 * n as f64)
+                };
+                let sum = neighbours[p]
+                    .iter()
+                    .fold(Vec3(0.0, 0.0, 0.0), |sum, &q| sum + self.points[q]);
+                self.points[p] * (1.0 - n as f64 * beta) + sum * beta
+            })
+            .collect();
+        let first_edge_point = points.len();
+        points.extend(edge_points);
+
+        let mut next = self.next_level(points);
+        let mut edge_vertices = HashMap::new();
+        let mut edge_vertex = |next: &mut Self, a: usize, b: usize| {
+            *edge_vertices.entry(edge_key(a, b)).or_insert_with(|| {
+                let key = edge_key(self.vertex_points[a], self.vertex_points[b]);
+                next.add_vertex(first_edge_point + edges.index[&key], &[a, b])
+            })
+        };
+
+        for face in &self.faces {
+            let [a, b, c] = [face[0], face[1], face[2]];
+            let ab = edge_vertex(&mut next, a, b);
+            let bc = edge_vertex(&mut next, b, c);
+            let ca = edge_vertex(&mut next, c, a);
+            next.faces.extend([
+                vec![a, ab, ca],
+                vec![b, bc, ab],
+                vec![c, ca, bc],
+                vec![ab, bc, ca],
+            ]);
+        }
+        next
+    }
+
+    fn to_mesh_data(&self) -> MeshData {
+        let indices: Vec<[u32; 3]> = self
+            .faces
+            .iter()
+            .flat_map(|face| {
+                (1..face.len() - 1).map(|i| [face[0], face[i], face[i + 1]].map(|v| v as u32))
+            })
+            .collect();
+
+        // Summing the unnormalized normals of the triangles weights them by their area.
+        let mut point_normals = vec![Vec3(0.0, 0.0, 0.0); self.points.len()];
+        for triangle in &indices {
+            let [a, b, c] = triangle.map(|v| self.point(v as usize));
+            let normal = (b - a).cross(c - a);
+            for v in triangle {
+                point_normals[self.vertex_points[*v as usize]] += normal;
+            }
+        }
+
+        let mut data = MeshData::new(
+            (0..self.vertex_points.len())
+                .map(|v| self.point(v))
+                .collect(),
+            indices,
+        );
+        data.normals = self
+            .vertex_points
+            .iter()
+            .map(|&p| {
+                let normal = point_normals[p];
+                if normal.near_zero() {
+                    Vec3(0.0, 1.0, 0.0)
+                } else {
+                    normal.normalize()
+                }
+            })
+            .collect();
+        data.uvs = self.uvs.clone();
+        data.colors = self.colors.clone();
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vec3::Vec3;
+
+    use super::{PolygonMesh, Subdivision, SubdivisionScheme};
+
+    fn cube() -> PolygonMesh {
+        let positions = (0..8)
+            .map(|i| {
+                let coordinate = |bit: u32| if i & (1 << bit) == 0 { -1.0 } else { 1.0 };
+                Vec3(coordinate(0), coordinate(1), coordinate(2))
+            })
+            .collect();
+        PolygonMesh {
+            positions,
+            uvs: vec![],
+            colors: vec![],
+            faces: vec![
+                vec![0, 2, 3, 1],
+                vec![4, 5, 7, 6],
+                vec![0, 1, 5, 4],
+                vec![2, 6, 7, 3],
+                vec![0, 4, 6, 2],
+                vec![1, 3, 7, 5],
+            ],
+        }
+    }
+
+    #[test]
+    fn catmull_clark_rounds_cube() {
+        let data = cube().subdivide(Subdivision {
+            scheme: SubdivisionScheme::CatmullClark,
+            levels: 1,
+        });
+        assert!(data.validate().is_ok());
+        // 24 quads, each split into two triangles.
+        assert_eq!(data.indices.len(), 48);
+        assert_eq!(data.positions.len(), 8 + 6 + 12);
+
+        // A corner moves to the average of its face points, edge midpoints and itself,
+        // weighted 1, 2 and 0 for three faces.
+        let corner = data.positions[7];
+        assert!((corner - Vec3(5.0, 5.0, 5.0) / 9.0).length() < 1e-12);
+        assert!((data.normals[7] - Vec3(1.0, 1.0, 1.0).normalize()).length() < 1e-12);
+    }
+
+    #[test]
+    fn loop_keeps_normals_outwards() {
+        let data = cube().subdivide(Subdivision {
+            scheme: SubdivisionScheme::Loop,
+            levels: 2,
+        });
+        assert!(data.validate().is_ok());
+        // 12 triangles, each split into four twice.
+        assert_eq!(data.indices.len(), 12 * 16);
+        for (position, normal) in data.positions.iter().zip(&data.normals) {
+            assert!(position.dot(*normal) > 0.0);
+        }
+    }
+}
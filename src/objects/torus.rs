@@ -0,0 +1,175 @@
+use crate::{
+    materials::{material::MaterialRef, texture::TexCoord},
+    objects::{
+        aabb::Aabb,
+        frame::{turn_around_axis, Frame},
+        hittable::{HitRecord, Hittable},
+    },
+    polynomial::solve_quartic,
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+/// A ring with a circular cross section, swept around an axis through its center.
+pub struct Torus {
+    pub center: Vec3,
+    pub axis: Vec3,
+    /// Distance from the center to the middle of the tube.
+    pub major_radius: f64,
+    /// Radius of the tube.
+    pub minor_radius: f64,
+    pub material: MaterialRef,
+
+    frame: Frame,
+    bounding_box: Aabb,
+}
+
+impl Torus {
+    pub fn new(
+        center: Vec3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: MaterialRef,
+    ) -> Self {
+        let frame = Frame::new(center, axis);
+        let tube = Vec3(minor_radius, minor_radius, minor_radius);
+        let extent = frame.circle_extent(major_radius) + tube;
+        let bounding_box = Aabb::span_points(center - extent, center + extent);
+
+        Self {
+            center,
+            axis: frame.w,
+            major_radius,
+            minor_radius,
+            material,
+
+            frame,
+            bounding_box,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let local = self.frame.local_ray(ray);
+        let length = local.direction.length();
+        let d = local.direction / length;
+
+        // Solving from the point of the ray closest to the center keeps the coefficients
+        // small, which the quartic solver needs for precise roots.
+        let shift = -local.origin.dot(d);
+        let o = local.origin + shift * d;
+        let outer = self.major_radius + self.minor_radius;
+        if o.length_squared() > outer * outer {
+            return None;
+        }
+
+        // Points on the torus satisfy (|p|² + R² - r²)² = 4 R² (x² + y²).
+        let r2 = self.major_radius * self.major_radius;
+        let m = o.length_squared();
+        let n = o.dot(d);
+        let k = m + r2 - self.minor_radius * self.minor_radius;
+        let roots = solve_quartic(
+            1.0,
+            4.0 * n,
+            2.0 * k + 4.0 * n * n - 4.0 * r2 * (d.x() * d.x() + d.y() * d.y()),
+            4.0 * n * k - 8.0 * r2 * (o.x() * d.x() + o.y() * d.y()),
+            k * k - 4.0 * r2 * (o.x() * o.x() + o.y() * o.y()),
+        );
+
+        let t = roots
+            .into_iter()
+            .map(|s| (s + shift) / length)
+            .find(|&t| allowed_t.surrounds(t))?;
+
+        let point = local.at(t);
+        let radial = Vec3(point.x(), point.y(), 0.0);
+        let ring = if radial.near_zero() {
+            Vec3(0.0, 0.0, 0.0)
+        } else {
+            radial.normalize() * self.major_radius
+        };
+        let normal = (point - ring).normalize();
+
+        let pi = std::f64::consts::PI;
+        let around_tube = point.z().atan2(radial.length() - self.major_radius);
+        let uv = TexCoord::new(turn_around_axis(point), (around_tube + pi) / (2.0 * pi));
+
+        let along_u = Vec3(-point.y(), point.x(), 0.0);
+        let along_v = normal.cross(along_u);
+        Some(
+            HitRecord::with_face_normal(
+                *ray,
+                t,
+                ray.at(t),
+                uv,
+                self.frame.world_vector(normal),
+                self.material,
+            )
+            .with_tangents(
+                self.frame.world_vector(along_u),
+                self.frame.world_vector(along_v),
+            ),
+        )
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for Torus {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Torus {
+            center: self.center,
+            axis: self.axis,
+            major_radius: self.major_radius,
+            minor_radius: self.minor_radius,
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::material::MaterialRef, objects::hittable::Hittable, ray::Ray, util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::Torus;
+
+    #[test]
+    fn hits_tube_and_misses_hole() {
+        let torus = Torus::new(
+            Vec3(0.0, 1.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            2.0,
+            0.5,
+            MaterialRef(0),
+        );
+        let allowed_t = Interval(0.001, f64::INFINITY);
+
+        let ray = Ray::new(Vec3(10.0, 1.0, 0.0), Vec3(-2.0, 0.0, 0.0), 0.0);
+        let rec = torus.hit(&ray, allowed_t).unwrap();
+        assert!((rec.t - 3.75).abs() < 1e-9);
+        assert!((rec.normal - Vec3(1.0, 0.0, 0.0)).length() < 1e-9);
+
+        let from_above = Ray::new(Vec3(2.3, 5.0, 0.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        let rec = torus.hit(&from_above, allowed_t).unwrap();
+        assert!((rec.position.y() - (1.0 + 0.4)).abs() < 1e-9);
+
+        let through_hole = Ray::new(Vec3(0.0, 5.0, 0.0), Vec3(0.0, -1.0, 0.0), 0.0);
+        assert!(torus.hit(&through_hole, allowed_t).is_none());
+
+        let bounds = torus.bounding_box();
+        assert!((bounds.x.1 - 2.5).abs() < 1e-9 && (bounds.y.1 - 1.5).abs() < 1e-9);
+    }
+}
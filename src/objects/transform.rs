@@ -1,10 +1,16 @@
 use std::sync::Arc;
 
 use crate::{
+    matrix::Matrix4,
     objects::aabb::Aabb,
     objects::hittable::{HitRecord, Hittable},
     ray::Ray,
-    util::Interval,
+    scene_file::{
+        description::{ObjectDescription, TransformStep},
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::{rad2deg, Interval},
     vec3::Vec3,
 };
 
@@ -43,6 +49,15 @@ impl Hittable for Translate {
     }
 }
 
+impl DescribeObject for Translate {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Translate {
+            offset: self.offset,
+            object: Box::new(self.object.describe(exporter)?),
+        })
+    }
+}
+
 pub struct RotateY {
     object: Arc<dyn Hittable>,
     bounding_box: Aabb,
@@ -53,71 +68,531 @@ pub struct RotateY {
 
 impl RotateY {
     pub fn new(angle: f64, object: Arc<dyn Hittable>) -> Self {
-        let bbox = object.bounding_box();
+        let rotation = Matrix4::rotation(Vec3(0.0, 1.0, 0.0), angle);
+        let bounding_box = object.bounding_box().transform(&rotation);
 
-        let mut min = Vec3(f64::INFINITY, f64::INFINITY, f64::INFINITY);
-        let mut max = Vec3(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        Self {
+            object,
+            bounding_box,
 
-        for i in 0..2 {
-            for j in 0..2 {
-                for k in 0..2 {
-                    let x = i as f64 * bbox.x.1 + (1.0 - i as f64) * bbox.x.0;
-                    let y = j as f64 * bbox.y.1 + (1.0 - j as f64) * bbox.y.0;
-                    let z = k as f64 * bbox.z.1 + (1.0 - k as f64) * bbox.z.0;
+            cos_angle: angle.cos(),
+            sin_angle: angle.sin(),
+        }
+    }
+}
 
-                    let new_x = angle.cos() * x + angle.sin() * z;
-                    let new_z = -angle.sin() * x + angle.sin() * z;
+impl RotateY {
+    /// Rotates `v` by the angle of the object.
+    fn object_to_world(&self, v: Vec3) -> Vec3 {
+        Vec3(
+            self.cos_angle * v.x() + self.sin_angle * v.z(),
+            v.y(),
+            -self.sin_angle * v.x() + self.cos_angle * v.z(),
+        )
+    }
 
-                    let tester = Vec3(new_x, y, new_z);
+    /// Rotates `v` back by the angle of the object.
+    fn world_to_object(&self, v: Vec3) -> Vec3 {
+        Vec3(
+            self.cos_angle * v.x() - self.sin_angle * v.z(),
+            v.y(),
+            self.sin_angle * v.x() + self.cos_angle * v.z(),
+        )
+    }
+}
 
-                    for c in 0..3 {
-                        min[c] = min[c].min(tester[c]);
-                        max[c] = max[c].max(tester[c]);
-                    }
+impl Hittable for RotateY {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let rotated_ray = Ray::new(
+            self.world_to_object(ray.origin),
+            self.world_to_object(ray.direction),
+            ray.time,
+        );
+
+        if let Some(mut hit) = self.object.hit(&rotated_ray, allowed_t) {
+            hit.position = self.object_to_world(hit.position);
+            hit.normal = self.object_to_world(hit.normal);
+            hit.geometric_normal = self.object_to_world(hit.geometric_normal);
+            hit.tangent = self.object_to_world(hit.tangent);
+            hit.bitangent = self.object_to_world(hit.bitangent);
+
+            Some(hit)
+        } else {
+            None
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for RotateY {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        let angle = self.sin_angle.atan2(self.cos_angle);
+        Ok(ObjectDescription::RotateY {
+            angle: rad2deg(angle),
+            object: Box::new(self.object.describe(exporter)?),
+        })
+    }
+}
+
+/// One step of an affine transformation, kept separate from the others so that
+/// the parameters of the steps can be interpolated by animations.
+#[derive(Debug, Clone, Copy)]
+pub enum AffineStep {
+    Translate(Vec3),
+    /// Rotation about an axis by an angle in radians.
+    Rotate {
+        axis: Vec3,
+        angle: f64,
+    },
+    Scale(Vec3),
+    Matrix(Matrix4),
+}
+
+impl AffineStep {
+    pub fn matrix(&self) -> Matrix4 {
+        match *self {
+            AffineStep::Translate(offset) => Matrix4::translation(offset),
+            AffineStep::Rotate { axis, angle } => Matrix4::rotation(axis, angle),
+            AffineStep::Scale(factors) => Matrix4::scaling(factors),
+            AffineStep::Matrix(matrix) => matrix,
+        }
+    }
+
+    pub fn describe(&self) -> TransformStep {
+        match *self {
+            AffineStep::Translate(offset) => TransformStep::Translate(offset),
+            AffineStep::Rotate { axis, angle } => TransformStep::Rotate {
+                axis,
+                angle: rad2deg(angle),
+            },
+            AffineStep::Scale(factors) => TransformStep::Scale(factors),
+            AffineStep::Matrix(matrix) => TransformStep::Matrix(matrix.affine_rows()),
+        }
+    }
+
+    /// Combines steps that are applied one after another into a single matrix.
+    pub fn combine(steps: &[AffineStep]) -> Matrix4 {
+        steps
+            .iter()
+            .fold(Matrix4::IDENTITY, |matrix, step| step.matrix() * matrix)
+    }
+
+    /// Returns whether both steps are of the same kind and can be interpolated.
+    pub fn same_kind(a: &AffineStep, b: &AffineStep) -> bool {
+        std::mem::discriminant(a) == std::mem::discriminant(b)
+    }
+
+    /// Returns the inverse of the matrix of the step, or `None` if it is not invertible.
+    /// Apart from general matrices this needs no matrix inversion.
+    pub fn inverse_matrix(&self) -> Option<Matrix4> {
+        match *self {
+            AffineStep::Translate(offset) => Some(Matrix4::translation(-offset)),
+            AffineStep::Rotate { axis, angle } => Some(Matrix4::rotation(axis, -angle)),
+            AffineStep::Scale(factors) => {
+                // The same threshold on the determinant as `Matrix4::inverse` uses.
+                if (factors.0 * factors.1 * factors.2).abs() < 1e-12 {
+                    return None;
+                }
+                Some(Matrix4::scaling(Vec3(
+                    1.0 / factors.0,
+                    1.0 / factors.1,
+                    1.0 / factors.2,
+                )))
+            }
+            AffineStep::Matrix(matrix) => matrix.inverse(),
+        }
+    }
+
+    /// Sums the parameters of steps of the same kind, each multiplied by its weight,
+    /// which interpolates between the steps when the weights add up to one.
+    /// Returns `None` if there are no steps or they are of different kinds.
+    pub fn weighted_sum<'a>(
+        steps: impl IntoIterator<Item = (&'a AffineStep, f64)>,
+    ) -> Option<AffineStep> {
+        let mut steps = steps.into_iter();
+        let (first, weight) = steps.next()?;
+        let mut sum = first.parameters().map(|parameter| weight * parameter);
+        for (step, weight) in steps {
+            if !Self::same_kind(first, step) {
+                return None;
+            }
+            for (sum, parameter) in sum.iter_mut().zip(step.parameters()) {
+                *sum += weight * parameter;
+            }
+        }
+        Some(first.with_parameters(&sum))
+    }
+
+    /// Returns the parameters of the step as a flat list of numbers, padded with zeros
+    /// to the 16 parameters of a matrix.
+    fn parameters(&self) -> [f64; 16] {
+        let mut p = [0.0; 16];
+        match *self {
+            AffineStep::Translate(v) | AffineStep::Scale(v) => {
+                p[..3].copy_from_slice(&[v.0, v.1, v.2])
+            }
+            AffineStep::Rotate { axis, angle } => {
+                let axis = axis.normalize();
+                p[..4].copy_from_slice(&[axis.0, axis.1, axis.2, angle]);
+            }
+            AffineStep::Matrix(matrix) => {
+                for (row, values) in matrix.0.iter().enumerate() {
+                    p[row * 4..row * 4 + 4].copy_from_slice(values);
                 }
             }
         }
+        p
+    }
+
+    /// Returns a step of the same kind with the parameters given as by `parameters`.
+    fn with_parameters(&self, p: &[f64]) -> AffineStep {
+        match self {
+            AffineStep::Translate(_) => AffineStep::Translate(Vec3(p[0], p[1], p[2])),
+            AffineStep::Scale(_) => AffineStep::Scale(Vec3(p[0], p[1], p[2])),
+            AffineStep::Rotate { .. } => AffineStep::Rotate {
+                axis: Vec3(p[0], p[1], p[2]),
+                angle: p[3],
+            },
+            AffineStep::Matrix(_) => AffineStep::Matrix(Matrix4(std::array::from_fn(|row| {
+                std::array::from_fn(|col| p[row * 4 + col])
+            }))),
+        }
+    }
+}
+
+/// Places an object in the scene by an arbitrary affine transformation,
+/// e.g. a combination of translations, rotations about any axis, scaling and shearing.
+pub struct Transform {
+    object: Arc<dyn Hittable>,
+    /// Transformation from the space of the object into world space.
+    matrix: Matrix4,
+    inverse: Matrix4,
+    bounding_box: Aabb,
+}
+
+impl Transform {
+    /// Creates a transformed copy of `object`. Panics if `matrix` is not invertible.
+    pub fn new(object: Arc<dyn Hittable>, matrix: Matrix4) -> Self {
+        let inverse = matrix.inverse().expect("transform must be invertible");
+        let bounding_box = object.bounding_box().transform(&matrix);
 
         Self {
             object,
-            bounding_box: Aabb::span_points(min, max),
-
-            cos_angle: angle.cos(),
-            sin_angle: angle.sin(),
+            matrix,
+            inverse,
+            bounding_box,
         }
     }
+
+    pub fn object(&self) -> &Arc<dyn Hittable> {
+        &self.object
+    }
+
+    pub fn matrix(&self) -> Matrix4 {
+        self.matrix
+    }
 }
 
-impl Hittable for RotateY {
+/// Intersects `ray` with `object` placed in the world by `matrix`, whose inverse is `inverse`.
+pub fn hit_transformed(
+    object: &dyn Hittable,
+    matrix: &Matrix4,
+    inverse: &Matrix4,
+    ray: &Ray,
+    allowed_t: Interval,
+) -> Option<HitRecord> {
+    // The direction is not normalized, so distances along the ray are the same in both spaces.
+    let object_ray = Ray::new(
+        inverse.transform_point(ray.origin),
+        inverse.transform_vector(ray.direction),
+        ray.time,
+    );
+
+    let mut hit = object.hit(&object_ray, allowed_t)?;
+    hit.position = matrix.transform_point(hit.position);
+    // Normals are transformed by the inverse transpose to stay perpendicular to the surface,
+    // while tangents lie in the surface and are transformed like any other direction.
+    // Scaling and shearing skew the tangent frame, which is squared up again around the new normal.
+    let normal_matrix = inverse.transpose();
+    hit.normal = normal_matrix.transform_vector(hit.normal).normalize();
+    hit.geometric_normal = normal_matrix
+        .transform_vector(hit.geometric_normal)
+        .normalize();
+    let along_u = matrix.transform_vector(hit.tangent);
+    let along_v = matrix.transform_vector(hit.bitangent);
+    hit.tangent = along_u.normalize();
+    hit.bitangent = along_v.normalize();
+
+    Some(hit.with_tangents(along_u, along_v))
+}
+
+impl Hittable for Transform {
     fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
-        let Ray {
-            mut origin,
-            mut direction,
-            ..
-        } = *ray;
+        hit_transformed(
+            self.object.as_ref(),
+            &self.matrix,
+            &self.inverse,
+            ray,
+            allowed_t,
+        )
+    }
 
-        origin[0] = self.cos_angle * origin[0] - self.sin_angle * origin[2];
-        origin[2] = self.sin_angle * origin[0] + self.cos_angle * origin[2];
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
 
-        direction[0] = self.cos_angle * direction[0] - self.sin_angle * direction[2];
-        direction[2] = self.sin_angle * direction[0] + self.cos_angle * direction[2];
+impl DescribeObject for Transform {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        Ok(ObjectDescription::Transform {
+            transform: vec![TransformStep::Matrix(self.matrix.affine_rows())],
+            object: Box::new(self.object.describe(exporter)?),
+        })
+    }
+}
 
-        let rotated_ray = Ray::new(origin, direction, ray.time);
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
 
-        if let Some(mut hit) = self.object.hit(&rotated_ray, allowed_t) {
-            hit.position[0] = self.cos_angle * hit.position[0] + self.sin_angle * hit.position[2];
-            hit.position[2] = -self.sin_angle * hit.position[0] + self.cos_angle * hit.position[2];
+    use crate::{
+        materials::material::MaterialRef,
+        matrix::Matrix4,
+        objects::{cube::cube, hittable::Hittable, quad::Quad, sphere::Sphere},
+        ray::Ray,
+        test_util::TestRng,
+        util::{deg2rad, Interval},
+        vec3::Vec3,
+    };
 
-            hit.normal[0] = self.cos_angle * hit.normal[0] + self.sin_angle * hit.normal[2];
-            hit.normal[2] = -self.sin_angle * hit.normal[0] + self.cos_angle * hit.normal[2];
+    use super::{RotateY, Transform, Translate};
 
-            Some(hit)
-        } else {
-            None
+    /// Quads pad their flat bounding boxes, so boxes are only compared up to this precision.
+    const BOUNDS_EPSILON: f64 = 1e-3;
+
+    const ANGLES: [f64; 8] = [0.0, 15.0, -18.0, 45.0, 90.0, 137.0, 180.0, -250.0];
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!(
+            (actual - expected).length() < 1e-9,
+            "expected {expected:?}, got {actual:?}"
+        );
+    }
+
+    /// Rotates `v` about the y axis the way `RotateY` is expected to.
+    fn rotate_y(v: Vec3, angle: f64) -> Vec3 {
+        Vec3(
+            angle.cos() * v.x() + angle.sin() * v.z(),
+            v.y(),
+            -angle.sin() * v.x() + angle.cos() * v.z(),
+        )
+    }
+
+    #[test]
+    fn rotate_y_hits_match_rotated_sphere() {
+        let mut rng = TestRng::new(1);
+        for angle in ANGLES.map(deg2rad) {
+            let center = Vec3(3.0, 1.0, -2.0);
+            let sphere = Arc::new(Sphere::stationary(center, 1.5, MaterialRef(0)));
+            let rotated = RotateY::new(angle, sphere);
+            let expected = Sphere::stationary(rotate_y(center, angle), 1.5, MaterialRef(0));
+
+            for _ in 0..200 {
+                let ray = rng.ray_towards(rotate_y(center, angle), 1.0, (5.0, 10.0));
+                let allowed_t = Interval(0.001, f64::INFINITY);
+
+                let actual = rotated.hit(&ray, allowed_t);
+                let expected = expected.hit(&ray, allowed_t);
+                assert_eq!(actual.is_some(), expected.is_some());
+
+                if let (Some(actual), Some(expected)) = (actual, expected) {
+                    assert!((actual.t - expected.t).abs() < 1e-9);
+                    assert_close(actual.position, expected.position);
+                    assert_close(actual.normal, expected.normal);
+                    assert_close(actual.position, ray.at(actual.t));
+                }
+            }
         }
     }
 
-    fn bounding_box(&self) -> Aabb {
-        self.bounding_box
+    #[test]
+    fn rotate_y_rotates_normals() {
+        for angle in ANGLES.map(deg2rad) {
+            // A unit square in the xy plane facing +z.
+            let quad = Arc::new(Quad::new(
+                Vec3(-0.5, -0.5, 0.0),
+                Vec3(1.0, 0.0, 0.0),
+                Vec3(0.0, 1.0, 0.0),
+                MaterialRef(0),
+            ));
+            let rotated = RotateY::new(angle, quad);
+
+            let facing = Vec3(angle.sin(), 0.0, angle.cos());
+            let ray = Ray::new(facing * 5.0 + Vec3(0.0, 0.2, 0.0), -facing, 0.0);
+            let rec = rotated
+                .hit(&ray, Interval(0.001, f64::INFINITY))
+                .expect("ray towards the front of the quad must hit");
+
+            assert!((rec.t - 5.0).abs() < 1e-9);
+            assert!(rec.front_face);
+            assert_close(rec.normal, facing);
+            assert_close(rec.position, Vec3(0.0, 0.2, 0.0));
+        }
+    }
+
+    #[test]
+    fn rotate_y_bounding_box_contains_rotated_corners() {
+        for angle in ANGLES.map(deg2rad) {
+            let (a, b) = (Vec3(-1.0, 0.0, -2.0), Vec3(3.0, 1.0, 2.0));
+            let rotated = RotateY::new(angle, Arc::new(cube(a, b, MaterialRef(0))));
+            let bounds = rotated.bounding_box();
+
+            let mut min = Vec3(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+            let mut max = -min;
+            for x in [a.x(), b.x()] {
+                for z in [a.z(), b.z()] {
+                    let corner = rotate_y(Vec3(x, 0.0, z), angle);
+                    for c in [0, 2] {
+                        min[c] = min[c].min(corner[c]);
+                        max[c] = max[c].max(corner[c]);
+                    }
+                }
+            }
+
+            for (c, axis) in [(0, bounds.x), (2, bounds.z)] {
+                assert!((axis.0 - min[c]).abs() < BOUNDS_EPSILON, "angle {angle}");
+                assert!((axis.1 - max[c]).abs() < BOUNDS_EPSILON, "angle {angle}");
+            }
+            assert!(bounds.y.0.abs() < BOUNDS_EPSILON && (bounds.y.1 - 1.0).abs() < BOUNDS_EPSILON);
+        }
+    }
+
+    #[test]
+    fn translate_offsets_hits_and_bounds() {
+        let offset = Vec3(1.0, -2.0, 3.0);
+        let sphere = Arc::new(Sphere::stationary(Vec3(0.0, 0.0, 0.0), 1.0, MaterialRef(0)));
+        let translated = Translate::new(offset, sphere);
+
+        let ray = Ray::new(offset + Vec3(0.0, 0.0, 5.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = translated
+            .hit(&ray, Interval(0.001, f64::INFINITY))
+            .unwrap();
+        assert!((rec.t - 4.0).abs() < 1e-9);
+        assert_close(rec.position, offset + Vec3(0.0, 0.0, 1.0));
+        assert_close(rec.normal, Vec3(0.0, 0.0, 1.0));
+
+        let bounds = translated.bounding_box();
+        assert!((bounds.x.0 - 0.0).abs() < 1e-9 && (bounds.x.1 - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn transform_matches_rotate_y() {
+        let mut rng = TestRng::new(1);
+        for angle in ANGLES.map(deg2rad) {
+            let object = Arc::new(cube(
+                Vec3(-1.0, 0.0, -2.0),
+                Vec3(3.0, 1.0, 2.0),
+                MaterialRef(0),
+            ));
+            let rotated = RotateY::new(angle, object.clone());
+            let transformed = Transform::new(object, Matrix4::rotation(Vec3(0.0, 1.0, 0.0), angle));
+
+            let (a, b) = (rotated.bounding_box(), transformed.bounding_box());
+            for axis in 0..3 {
+                assert!((a.axis(axis).0 - b.axis(axis).0).abs() < BOUNDS_EPSILON);
+                assert!((a.axis(axis).1 - b.axis(axis).1).abs() < BOUNDS_EPSILON);
+            }
+
+            for _ in 0..200 {
+                let ray = rng.ray_towards(Vec3(0.0, 0.0, 0.0), 1.0, (10.0, 10.0));
+                let allowed_t = Interval(0.001, f64::INFINITY);
+
+                let actual = transformed.hit(&ray, allowed_t);
+                let expected = rotated.hit(&ray, allowed_t);
+                assert_eq!(actual.is_some(), expected.is_some());
+
+                if let (Some(actual), Some(expected)) = (actual, expected) {
+                    assert!((actual.t - expected.t).abs() < 1e-9);
+                    assert_close(actual.position, expected.position);
+                    assert_close(actual.normal, expected.normal);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn transform_bounding_box_is_tight() {
+        let cube = Arc::new(cube(
+            Vec3(-1.0, -1.0, -1.0),
+            Vec3(1.0, 1.0, 1.0),
+            MaterialRef(0),
+        ));
+        let matrix = Matrix4::rotation(Vec3(0.0, 0.0, 1.0), deg2rad(45.0))
+            * Matrix4::scaling(Vec3(1.0, 1.0, 3.0));
+        let bounds = Transform::new(cube, matrix).bounding_box();
+
+        let half_diagonal = 2.0_f64.sqrt();
+        assert!((bounds.x.0 + half_diagonal).abs() < BOUNDS_EPSILON);
+        assert!((bounds.x.1 - half_diagonal).abs() < BOUNDS_EPSILON);
+        assert!((bounds.y.1 - half_diagonal).abs() < BOUNDS_EPSILON);
+        assert!(
+            (bounds.z.0 + 3.0).abs() < BOUNDS_EPSILON && (bounds.z.1 - 3.0).abs() < BOUNDS_EPSILON
+        );
+    }
+
+    #[test]
+    fn normals_of_scaled_sphere_match_ellipsoid() {
+        let radii = Vec3(3.0, 1.0, 0.5);
+        let unit_sphere = Arc::new(Sphere::stationary(Vec3(0.0, 0.0, 0.0), 1.0, MaterialRef(0)));
+        let center = Vec3(2.0, 0.0, -1.0);
+        let matrix = Matrix4::translation(center)
+            * Matrix4::rotation(Vec3(0.0, 0.0, 1.0), 0.3)
+            * Matrix4::scaling(radii);
+        let ellipsoid = Transform::new(unit_sphere, matrix);
+        let inverse = matrix.inverse().unwrap();
+
+        let mut rng = TestRng::new(1);
+        for _ in 0..1000 {
+            let ray = rng.ray_towards(center, 0.0, (10.0, 10.0));
+            let rec = ellipsoid
+                .hit(&ray, Interval(0.001, f64::INFINITY))
+                .expect("ray through the center must hit");
+
+            // On the unit sphere `p` is also the normal, which scaling by `radii` turns into `p / radii`.
+            let p = inverse.transform_point(rec.position);
+            assert!((p.length() - 1.0).abs() < 1e-9);
+            let gradient = Vec3(p.x() / radii.x(), p.y() / radii.y(), p.z() / radii.z());
+            let expected = Matrix4::rotation(Vec3(0.0, 0.0, 1.0), 0.3)
+                .transform_vector(gradient)
+                .normalize();
+            assert!((rec.normal - expected).length() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn tangent_frame_of_scaled_quad_stays_orthonormal() {
+        // The edges are perpendicular, but stretching along x skews them.
+        let quad = Arc::new(Quad::new(
+            Vec3(0.0, -1.0, 0.0),
+            Vec3(1.0, 1.0, 0.0),
+            Vec3(-1.0, 1.0, 0.0),
+            MaterialRef(0),
+        ));
+        let scaled = Transform::new(quad, Matrix4::scaling(Vec3(3.0, 1.0, 1.0)));
+
+        let ray = Ray::new(Vec3(0.0, 0.0, 5.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = scaled.hit(&ray, Interval(0.001, f64::INFINITY)).unwrap();
+        let outward = rec.outward_normal();
+        assert_close(outward, Vec3(0.0, 0.0, 1.0));
+        assert_close(rec.tangent, Vec3(3.0, 1.0, 0.0).normalize());
+        assert!(rec.tangent.dot(rec.bitangent).abs() < 1e-9);
+        assert!(rec.bitangent.dot(outward).abs() < 1e-9);
+        assert!((rec.bitangent.length() - 1.0).abs() < 1e-9);
+        // The frame keeps its handedness and `v` still increases along the bitangent.
+        assert!(rec.tangent.cross(rec.bitangent).dot(outward) > 0.0);
+        assert!(rec.bitangent.dot(Vec3(-3.0, 1.0, 0.0)) > 0.0);
     }
 }
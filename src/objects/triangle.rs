@@ -0,0 +1,212 @@
+use crate::{
+    materials::{material::MaterialRef, texture::TexCoord},
+    objects::{
+        aabb::Aabb,
+        hittable::{HitRecord, Hittable},
+    },
+    ray::Ray,
+    scene_file::{
+        description::ObjectDescription,
+        error::ExportError,
+        exporter::{DescribeObject, SceneExporter},
+    },
+    util::Interval,
+    vec3::Vec3,
+};
+
+pub struct Triangle {
+    pub vertices: [Vec3; 3],
+    /// Per-vertex normals, interpolated across the face for smooth shading.
+    pub normals: Option<[Vec3; 3]>,
+    pub material: MaterialRef,
+
+    normal: Vec3,
+    bounding_box: Aabb,
+}
+
+impl Triangle {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3, material: MaterialRef) -> Self {
+        Self {
+            vertices: [a, b, c],
+            normals: None,
+            material,
+            normal: (b - a).cross(c - a).normalize(),
+            bounding_box: triangle_bounding_box(a, b, c),
+        }
+    }
+
+    pub fn with_normals(
+        a: Vec3,
+        b: Vec3,
+        c: Vec3,
+        normals: [Vec3; 3],
+        material: MaterialRef,
+    ) -> Self {
+        Self {
+            normals: Some(normals.map(Vec3::normalize)),
+            ..Self::new(a, b, c, material)
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, allowed_t: Interval) -> Option<HitRecord> {
+        let [a, b, c] = self.vertices;
+        let (t, b1, b2) = intersect_triangle(a, b, c, ray, allowed_t)?;
+
+        let shading_normal = self
+            .normals
+            .map(|[n0, n1, n2]| interpolate(n0, n1, n2, b1, b2).normalize());
+
+        Some(triangle_hit_record(
+            ray,
+            t,
+            TexCoord::new(b1, b2),
+            self.normal,
+            shading_normal,
+            (b - a, c - a),
+            self.material,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl DescribeObject for Triangle {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        let [a, b, c] = self.vertices;
+        Ok(ObjectDescription::Triangle {
+            a,
+            b,
+            c,
+            normals: self.normals,
+            material: exporter.material(self.material),
+        })
+    }
+}
+
+pub fn triangle_bounding_box(a: Vec3, b: Vec3, c: Vec3) -> Aabb {
+    Aabb::combine(Aabb::span_points(a, b), Aabb::span_points(a, c))
+}
+
+/// Intersects a ray with the triangle `a`, `b`, `c` using the Möller-Trumbore algorithm.
+///
+/// Returns the ray parameter `t` and the barycentric coordinates of the hit
+/// with respect to `b` and `c`. The weight of `a` is `1 - b1 - b2`.
+pub fn intersect_triangle(
+    a: Vec3,
+    b: Vec3,
+    c: Vec3,
+    ray: &Ray,
+    allowed_t: Interval,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+
+    let p = ray.direction.cross(edge2);
+    let det = edge1.dot(p);
+    if det.abs() < f64::EPSILON {
+        // The ray is parallel to the triangle.
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let s = ray.origin - a;
+    let b1 = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let b2 = ray.direction.dot(q) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inv_det;
+    if !allowed_t.surrounds(t) {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+/// Interpolates per-vertex values using the barycentric coordinates of a hit.
+pub fn interpolate(v0: Vec3, v1: Vec3, v2: Vec3, b1: f64, b2: f64) -> Vec3 {
+    (1.0 - b1 - b2) * v0 + b1 * v1 + b2 * v2
+}
+
+/// Returns the derivatives of the triangle `a`, `b`, `c` along the texture coordinates
+/// given at its vertices, which are the edges from `a` for barycentric coordinates.
+pub fn uv_tangents([a, b, c]: [Vec3; 3], [uv0, uv1, uv2]: [TexCoord; 3]) -> (Vec3, Vec3) {
+    let (edge1, edge2) = (b - a, c - a);
+    let (du1, dv1) = (uv1.u - uv0.u, uv1.v - uv0.v);
+    let (du2, dv2) = (uv2.u - uv0.u, uv2.v - uv0.v);
+    let det = du1 * dv2 - du2 * dv1;
+    if det.abs() < 1e-20 {
+        return (edge1, edge2);
+    }
+    (
+        (edge1 * dv2 - edge2 * dv1) / det,
+        (edge2 * du1 - edge1 * du2) / det,
+    )
+}
+
+/// Creates the hit record of a triangle hit. The side of the surface that was hit is
+/// determined by the geometric normal, the optional shading normal replaces it afterwards.
+/// The tangent frame follows the derivatives of the surface along `u` and `v`.
+pub fn triangle_hit_record(
+    ray: &Ray,
+    t: f64,
+    uv: TexCoord,
+    geometric_normal: Vec3,
+    shading_normal: Option<Vec3>,
+    (along_u, along_v): (Vec3, Vec3),
+    material: MaterialRef,
+) -> HitRecord {
+    let mut rec = HitRecord::with_face_normal(*ray, t, ray.at(t), uv, geometric_normal, material);
+
+    if let Some(normal) = shading_normal {
+        rec.normal = if rec.front_face { normal } else { -normal };
+    }
+
+    rec.with_tangents(along_u, along_v)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        materials::{material::MaterialRef, texture::TexCoord},
+        objects::hittable::Hittable,
+        ray::Ray,
+        util::Interval,
+        vec3::Vec3,
+    };
+
+    use super::Triangle;
+
+    #[test]
+    fn hit_reports_barycentric_uv() {
+        let triangle = Triangle::new(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            MaterialRef(0),
+        );
+
+        let ray = Ray::new(Vec3(0.25, 0.5, 2.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        let rec = triangle.hit(&ray, Interval(0.001, f64::INFINITY)).unwrap();
+
+        assert!((rec.t - 2.0).abs() < 1e-12);
+        assert_eq!(rec.uv, TexCoord::new(0.25, 0.5));
+        assert!(rec.front_face);
+        assert!((rec.normal.z() - 1.0).abs() < 1e-12);
+
+        let miss = Ray::new(Vec3(0.75, 0.5, 2.0), Vec3(0.0, 0.0, -1.0), 0.0);
+        assert!(triangle
+            .hit(&miss, Interval(0.001, f64::INFINITY))
+            .is_none());
+    }
+}
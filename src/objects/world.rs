@@ -1,32 +1,85 @@
+use std::sync::Arc;
+
 use crate::materials::material::{Material, MaterialRef};
+use crate::scene_file::{
+    description::ObjectDescription,
+    error::ExportError,
+    exporter::{DescribeObject, SceneExporter},
+};
 
-use super::{bvh::Bvh, hittable::Hittable};
+use super::{
+    bvh::{Bvh, BvhBuilder, BvhStats},
+    hittable::Hittable,
+};
 
 pub struct World {
     pub materials: Vec<Box<dyn Material>>,
-    pub bvh: Option<Bvh>,
+    objects: Vec<Arc<dyn Hittable>>,
+    bvh: Option<Bvh>,
+    /// Strategy used for the BVH of the world and for those inside its objects.
+    bvh_builder: BvhBuilder,
+    /// Statistics of the BVHs inside objects, such as those of meshes and groups.
+    object_bvh_stats: BvhStats,
 }
 
 impl World {
-    pub fn new() -> Self {
+    pub fn new(bvh_builder: BvhBuilder) -> Self {
         Self {
             materials: vec![],
+            objects: vec![],
             bvh: None,
+            bvh_builder,
+            object_bvh_stats: BvhStats::default(),
         }
     }
 
+    pub fn bvh_builder(&self) -> BvhBuilder {
+        self.bvh_builder
+    }
+
+    /// Records the statistics of a BVH built for one of the objects, so that they are
+    /// included in those returned by `build_bvh`.
+    pub fn add_bvh_stats(&mut self, stats: BvhStats) {
+        self.object_bvh_stats.merge(&stats);
+    }
+
+    /// Builds a BVH over `objects` that is used as a single object, such as a group.
+    pub fn object_bvh(&mut self, objects: &[Arc<dyn Hittable>]) -> Bvh {
+        let (bvh, stats) = Bvh::build(objects, self.bvh_builder);
+        self.add_bvh_stats(stats);
+        bvh
+    }
+
     pub fn register_material(&mut self, material: Box<dyn Material>) -> MaterialRef {
         let new_ref = MaterialRef(self.materials.len());
         self.materials.push(material);
         new_ref
     }
 
-    pub fn set_bvh(&mut self, bvh: Bvh) {
+    /// Sets the objects of the world. The BVH over them is built by `build_bvh`
+    /// once the world is about to be rendered.
+    pub fn set_objects(&mut self, objects: Vec<Arc<dyn Hittable>>) {
+        self.objects = objects;
+        self.bvh = None;
+    }
+
+    pub fn objects(&self) -> &[Arc<dyn Hittable>] {
+        &self.objects
+    }
+
+    /// Builds the BVH over the objects and returns its statistics together with those
+    /// of the BVHs inside the objects.
+    pub fn build_bvh(&mut self) -> BvhStats {
+        let (bvh, mut stats) = Bvh::build(&self.objects, self.bvh_builder);
         self.bvh = Some(bvh);
+        stats.merge(&self.object_bvh_stats);
+        stats
     }
 
     pub fn bvh(&self) -> &Bvh {
-        self.bvh.as_ref().unwrap()
+        self.bvh
+            .as_ref()
+            .expect("BVH of the world has not been built")
     }
 }
 
@@ -43,3 +96,14 @@ impl Hittable for World {
         self.bvh().hit(ray, allowed_t)
     }
 }
+
+impl DescribeObject for World {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError> {
+        let objects = self
+            .objects
+            .iter()
+            .map(|object| object.describe(exporter))
+            .collect::<Result<_, _>>()?;
+        Ok(ObjectDescription::Group { objects })
+    }
+}
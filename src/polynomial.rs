@@ -0,0 +1,252 @@
+//! Real roots of polynomials up to degree four, as needed to intersect rays with quadrics and
+//! quartic surfaces, and of higher degrees within an interval.
+//!
+//! The cubic and quartic solvers follow the closed-form solutions by Cardano and Ferrari.
+
+/// Coefficients closer to zero than this are treated as zero.
+const EPSILON: f64 = 1e-9;
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < EPSILON
+}
+
+/// Returns the real roots of `a x² + b x + c` in increasing order.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return if b == 0.0 { vec![] } else { vec![-c / b] };
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return vec![];
+    }
+
+    // Avoids the cancellation of `-b + sqrt(discriminant)` when both are of similar size.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let mut roots = if q == 0.0 {
+        vec![0.0, 0.0]
+    } else {
+        vec![q / a, c / q]
+    };
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+/// Returns the real roots of `x³ + a x² + b x + c` in no particular order.
+fn solve_normalized_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    // Substituting x = y - a/3 gives the depressed cubic y³ + 3p y + 2q = 0.
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + c) / 2.0;
+
+    let cb_p = p * p * p;
+    let discriminant = q * q + cb_p;
+
+    let roots = if is_zero(discriminant) {
+        if is_zero(q) {
+            vec![0.0]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u, -u]
+        }
+    } else if discriminant < 0.0 {
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        let third = std::f64::consts::PI / 3.0;
+        vec![
+            t * phi.cos(),
+            -t * (phi + third).cos(),
+            -t * (phi - third).cos(),
+        ]
+    } else {
+        let sqrt_d = discriminant.sqrt();
+        vec![(sqrt_d - q).cbrt() - (sqrt_d + q).cbrt()]
+    };
+
+    roots.into_iter().map(|y| y - a / 3.0).collect()
+}
+
+/// Returns the real roots of `a x⁴ + b x³ + c x² + d x + e` in increasing order.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a == 0.0 {
+        // Only needed for completeness, tori always have a quartic term.
+        let mut roots: Vec<f64> = if b == 0.0 {
+            solve_quadratic(c, d, e)
+        } else {
+            solve_normalized_cubic(c / b, d / b, e / b)
+        };
+        roots.sort_by(f64::total_cmp);
+        return roots;
+    }
+
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+
+    // Substituting x = y - b/4 gives the depressed quartic y⁴ + p y² + q y + r = 0.
+    let sq_b = b * b;
+    let p = -3.0 / 8.0 * sq_b + c;
+    let q = sq_b * b / 8.0 - b * c / 2.0 + d;
+    let r = -3.0 / 256.0 * sq_b * sq_b + sq_b * c / 16.0 - b * d / 4.0 + e;
+
+    let mut roots = if is_zero(r) {
+        // y (y³ + p y + q) = 0
+        let mut roots = solve_normalized_cubic(0.0, p, q);
+        roots.push(0.0);
+        roots
+    } else {
+        // Any real root of the resolvent cubic splits the quartic into two quadratics.
+        let z = solve_normalized_cubic(-p / 2.0, -r, r * p / 2.0 - q * q / 8.0)[0];
+
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if is_zero(u) {
+            0.0
+        } else if u > 0.0 {
+            u.sqrt()
+        } else {
+            return vec![];
+        };
+        let v = if is_zero(v) {
+            0.0
+        } else if v > 0.0 {
+            v.sqrt()
+        } else {
+            return vec![];
+        };
+
+        let v = if q < 0.0 { -v } else { v };
+        let mut roots = solve_quadratic(1.0, v, z - u);
+        roots.extend(solve_quadratic(1.0, -v, z + u));
+        roots
+    };
+
+    // The closed-form solution loses precision, which a few Newton steps on the
+    // original polynomial recover.
+    let polynomial = |x: f64| (((x + b) * x + c) * x + d) * x + e;
+    let derivative = |x: f64| ((4.0 * x + 3.0 * b) * x + 2.0 * c) * x + d;
+    for root in &mut roots {
+        *root -= b / 4.0;
+        for _ in 0..2 {
+            let slope = derivative(*root);
+            if slope != 0.0 {
+                *root -= polynomial(*root) / slope;
+            }
+        }
+    }
+
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+/// Returns the real roots between `min` and `max` of the polynomial with the given coefficients,
+/// starting with the highest power, in increasing order.
+///
+/// The roots of the derivative split the interval into parts in which the polynomial only rises
+/// or falls, so each part contains at most one root, which is found by bisection.
+pub fn solve_polynomial_between(coefficients: &[f64], min: f64, max: f64) -> Vec<f64> {
+    let start = coefficients
+        .iter()
+        .position(|&c| c != 0.0)
+        .unwrap_or(coefficients.len());
+    let coefficients = &coefficients[start..];
+    let evaluate = |x: f64| coefficients.iter().fold(0.0, |sum, &c| sum * x + c);
+
+    let degree = match coefficients.len() {
+        0 | 1 => return vec![],
+        2 => {
+            let root = -coefficients[1] / coefficients[0];
+            return if (min..=max).contains(&root) {
+                vec![root]
+            } else {
+                vec![]
+            };
+        }
+        len => len - 1,
+    };
+
+    let derivative: Vec<f64> = coefficients[..degree]
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c * (degree - i) as f64)
+        .collect();
+    let mut bounds = vec![min];
+    bounds.extend(solve_polynomial_between(&derivative, min, max));
+    bounds.push(max);
+
+    let mut roots: Vec<f64> = vec![];
+    for pair in bounds.windows(2) {
+        let (mut low, mut high) = (pair[0], pair[1]);
+        let (value_low, value_high) = (evaluate(low), evaluate(high));
+        let root = if value_low == 0.0 {
+            low
+        } else if value_high == 0.0 {
+            high
+        } else if value_low.signum() != value_high.signum() {
+            let rising = value_high > 0.0;
+            for _ in 0..100 {
+                let middle = 0.5 * (low + high);
+                if middle <= low || middle >= high {
+                    break;
+                }
+                if (evaluate(middle) > 0.0) == rising {
+                    high = middle;
+                } else {
+                    low = middle;
+                }
+            }
+            0.5 * (low + high)
+        } else {
+            continue;
+        };
+
+        // Roots on the boundary between two parts are found by both.
+        if roots.last().is_none_or(|&last| root > last) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{solve_polynomial_between, solve_quadratic, solve_quartic};
+
+    #[test]
+    fn solves_quadratics() {
+        assert_eq!(solve_quadratic(1.0, -3.0, 2.0), vec![1.0, 2.0]);
+        assert!(solve_quadratic(1.0, 0.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn solves_quartics() {
+        // (x - 1)(x - 2)(x + 3)(x - 0.5)
+        let roots = solve_quartic(1.0, -0.5, -7.0, 9.5, -3.0);
+        let expected = [-3.0, 0.5, 1.0, 2.0];
+        assert_eq!(roots.len(), 4);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-9, "{roots:?}");
+        }
+
+        // (x² + 1)(x - 4)(x + 2) has only two real roots.
+        let roots = solve_quartic(1.0, -2.0, -7.0, -2.0, -8.0);
+        assert_eq!(roots.len(), 2);
+        assert!((roots[0] + 2.0).abs() < 1e-9 && (roots[1] - 4.0).abs() < 1e-9);
+
+        assert!(solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn solves_polynomials_within_interval() {
+        // (x² - 1)(x² - 4)(x² - 9) = x⁶ - 14x⁴ + 49x² - 36
+        let coefficients = [1.0, 0.0, -14.0, 0.0, 49.0, 0.0, -36.0];
+        let roots = solve_polynomial_between(&coefficients, -2.5, 10.0);
+        let expected = [-2.0, -1.0, 1.0, 2.0, 3.0];
+        assert_eq!(roots.len(), expected.len(), "{roots:?}");
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-9, "{roots:?}");
+        }
+
+        // Touching zero at a double root.
+        let roots = solve_polynomial_between(&[1.0, -2.0, 1.0], 0.0, 2.0);
+        assert_eq!(roots, vec![1.0]);
+    }
+}
@@ -0,0 +1,445 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use toml::Spanned;
+
+use crate::{
+    animation::Interpolation,
+    objects::{
+        csg::CsgOperation,
+        curves::{CurveBasis, CurveShape, Strand},
+        metaballs::Metaball,
+        point_cloud::PointShape,
+        sdf::DistanceField,
+        subdivision::Subdivision,
+    },
+    scenes::scene::Settings,
+    vec3::{Color, Vec3},
+};
+
+/// Root of a scene file.
+///
+/// Textures and materials are named so that they can be shared between
+/// multiple materials and objects. Objects refer to materials by name and
+/// materials refer to textures by name. Named geometry is built once and
+/// placed in the scene any number of times by instances.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    #[serde(default)]
+    pub settings: Settings,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub textures: BTreeMap<String, Spanned<TextureDescription>>,
+
+    #[serde(default)]
+    pub materials: BTreeMap<String, Spanned<MaterialDescription>>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub geometry: BTreeMap<String, Spanned<ObjectDescription>>,
+
+    #[serde(default)]
+    pub objects: Vec<Spanned<ObjectDescription>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TextureDescription {
+    Solid {
+        color: Color,
+    },
+    Checker {
+        scale: f64,
+        /// Name of the texture used for even cells.
+        even: String,
+        /// Name of the texture used for odd cells.
+        odd: String,
+    },
+    Image {
+        /// Path of the image, relative to the scene file.
+        path: PathBuf,
+    },
+    /// Per-vertex colors of triangle meshes, interpolated across each triangle.
+    VertexColor,
+}
+
+/// Materials that sample a texture accept either the name of a texture
+/// or a plain color, but not both. Diffuse, metal and glass materials can add
+/// normal and bump maps, which need texture coordinates on the surface.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MaterialDescription {
+    Lambertian {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        texture: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<Color>,
+        /// Path of a tangent-space normal map, relative to the scene file.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normal_map: Option<PathBuf>,
+        /// Path of a grayscale height map whose slopes tilt the normal, relative to the scene file.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bump_map: Option<PathBuf>,
+        /// How far the bump map tilts the normal, 1 if not given.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bump_strength: Option<f64>,
+    },
+    Metal {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        texture: Option<String>,
+        /// Called `albedo` by earlier versions.
+        #[serde(default, alias = "albedo", skip_serializing_if = "Option::is_none")]
+        color: Option<Color>,
+        fuzziness: f64,
+        /// Path of a tangent-space normal map, relative to the scene file.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normal_map: Option<PathBuf>,
+        /// Path of a grayscale height map whose slopes tilt the normal, relative to the scene file.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bump_map: Option<PathBuf>,
+        /// How far the bump map tilts the normal, 1 if not given.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bump_strength: Option<f64>,
+    },
+    Dialectric {
+        refraction_index: f64,
+        /// Path of a tangent-space normal map, relative to the scene file.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normal_map: Option<PathBuf>,
+        /// Path of a grayscale height map whose slopes tilt the normal, relative to the scene file.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bump_map: Option<PathBuf>,
+        /// How far the bump map tilts the normal, 1 if not given.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bump_strength: Option<f64>,
+    },
+    DiffuseLight {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        texture: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<Color>,
+    },
+    Isotropic {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        texture: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<Color>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ObjectDescription {
+    Sphere {
+        center: Vec3,
+        /// If present the sphere moves from `center` to `end_center` between the times 0 and 1,
+        /// i.e. during the exposure of a still image, and rests there afterwards.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end_center: Option<Vec3>,
+        radius: f64,
+        material: String,
+    },
+    Quad {
+        corner: Vec3,
+        u: Vec3,
+        v: Vec3,
+        #[serde(default, skip_serializing_if = "QuadShape::is_parallelogram")]
+        shape: QuadShape,
+        material: String,
+    },
+    /// A flat ellipse around `center` with the semi-axes `u` and `v`.
+    Ellipse {
+        center: Vec3,
+        u: Vec3,
+        v: Vec3,
+        material: String,
+    },
+    /// A flat ellipse with a hole, e.g. a ring shaped light.
+    Annulus {
+        center: Vec3,
+        u: Vec3,
+        v: Vec3,
+        /// Size of the hole relative to the outer ellipse, between 0 and 1.
+        inner: f64,
+        material: String,
+    },
+    /// An infinite plane through `point`.
+    Plane {
+        point: Vec3,
+        normal: Vec3,
+        /// Size of the square over which the texture coordinates run from 0 to 1 before repeating.
+        #[serde(default = "unit_tile_size")]
+        tile_size: f64,
+        material: String,
+    },
+    /// A flat circular disk facing in the direction of `normal`.
+    Disk {
+        center: Vec3,
+        normal: Vec3,
+        radius: f64,
+        material: String,
+    },
+    /// A cylinder between the centers of its two ends.
+    Cylinder {
+        base: Vec3,
+        top: Vec3,
+        radius: f64,
+        /// Whether the ends are closed by disks.
+        #[serde(default = "capped_by_default")]
+        capped: bool,
+        material: String,
+    },
+    /// A cone narrowing from a circular base to its apex.
+    Cone {
+        base: Vec3,
+        apex: Vec3,
+        /// Radius of the base.
+        radius: f64,
+        /// Whether the base is closed by a disk.
+        #[serde(default = "capped_by_default")]
+        capped: bool,
+        material: String,
+    },
+    /// A ring around `axis` through its center.
+    Torus {
+        center: Vec3,
+        axis: Vec3,
+        /// Distance from the center to the middle of the tube.
+        major_radius: f64,
+        /// Radius of the tube.
+        minor_radius: f64,
+        material: String,
+    },
+    Triangle {
+        a: Vec3,
+        b: Vec3,
+        c: Vec3,
+        /// Optional per-vertex normals for smooth shading.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        normals: Option<[Vec3; 3]>,
+        material: String,
+    },
+    /// A triangle mesh given by a vertex list and one triple of vertex indices per triangle.
+    Mesh {
+        positions: Vec<Vec3>,
+        indices: Vec<[u32; 3]>,
+        /// Optional per-vertex normals, one for every position.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        normals: Vec<Vec3>,
+        /// Optional per-vertex texture coordinates, one for every position.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        uvs: Vec<[f64; 2]>,
+        /// Optional per-vertex colors, one for every position.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        colors: Vec<Color>,
+        /// Smooths the mesh by subdividing it when loading the scene.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subdivision: Option<Subdivision>,
+        material: String,
+    },
+    /// Triangle meshes loaded from a Wavefront OBJ file.
+    Obj {
+        /// Path of the OBJ file, relative to the scene file.
+        path: PathBuf,
+        /// Material used for all faces instead of the materials from the file's MTL libraries.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<String>,
+        /// Smooths the mesh by subdividing it when loading the scene.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subdivision: Option<Subdivision>,
+    },
+    /// Triangle mesh loaded from an ASCII or binary PLY file.
+    Ply {
+        /// Path of the PLY file, relative to the scene file.
+        path: PathBuf,
+        /// Material used for the mesh. Defaults to a diffuse material
+        /// colored by the vertex colors of the file.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<String>,
+        /// Smooths the mesh by subdividing it when loading the scene.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subdivision: Option<Subdivision>,
+    },
+    /// Triangle meshes of the default scene of a glTF or GLB file.
+    Gltf {
+        /// Path of the glTF file, relative to the scene file.
+        path: PathBuf,
+        /// Material used for all primitives instead of the materials from the file.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<String>,
+        /// Index of a camera in the file that replaces the camera settings of the scene.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        camera: Option<usize>,
+        /// Smooths the mesh by subdividing it when loading the scene.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subdivision: Option<Subdivision>,
+    },
+    /// Terrain with the heights of a grayscale image spanned along x and z from `corner`,
+    /// `scale` giving its extent and the height of white.
+    Heightfield {
+        /// Path of the image, relative to the scene file.
+        path: PathBuf,
+        corner: Vec3,
+        scale: Vec3,
+        material: String,
+    },
+    /// Blobs around `balls` that melt into each other, where the sum of their fields
+    /// reaches `threshold`.
+    Metaballs {
+        balls: Vec<Metaball>,
+        threshold: f64,
+        material: String,
+    },
+    /// A bicubic Bézier patch with 16 control points in rows of four.
+    BezierPatch {
+        points: Vec<Vec3>,
+        material: String,
+    },
+    /// Bicubic Bézier patches loaded from a BPT file, such as the Utah teapot.
+    Bpt {
+        /// Path of the BPT file, relative to the scene file.
+        path: PathBuf,
+        material: String,
+    },
+    /// Many small spheres or discs of the same radius, e.g. of a LiDAR scan, given inline
+    /// or loaded from the vertices of a PLY file.
+    PointCloud {
+        /// Path of a PLY file whose vertices, normals and colors are used, relative to the
+        /// scene file.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<PathBuf>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        points: Vec<Vec3>,
+        /// Per-point normals that discs face. Without them, discs face the ray.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        normals: Vec<Vec3>,
+        /// Per-point colors, shown by the `vertex_color` texture.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        colors: Vec<Color>,
+        radius: f64,
+        #[serde(default, skip_serializing_if = "PointShape::is_sphere")]
+        shape: PointShape,
+        /// Defaults to a diffuse material colored by the colors of the points.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        material: Option<String>,
+    },
+    /// Hair, fur or grass made of strands whose radius varies along them.
+    Curves {
+        #[serde(default)]
+        basis: CurveBasis,
+        #[serde(default)]
+        shape: CurveShape,
+        strands: Vec<Strand>,
+        material: String,
+    },
+    Cube {
+        a: Vec3,
+        b: Vec3,
+        material: String,
+    },
+    ConstantVolume {
+        boundary: Box<ObjectDescription>,
+        density: f64,
+        /// Phase function of the volume, usually an isotropic material.
+        material: String,
+    },
+    Translate {
+        offset: Vec3,
+        object: Box<ObjectDescription>,
+    },
+    RotateY {
+        /// Rotation angle in degrees.
+        angle: f64,
+        object: Box<ObjectDescription>,
+    },
+    /// Named geometry placed by an affine transformation, sharing its memory
+    /// with all other instances of the same geometry.
+    Instance {
+        /// Name of the entry in the `geometry` table.
+        geometry: String,
+        /// Steps transforming the geometry into the scene.
+        #[serde(default)]
+        transform: Vec<TransformStep>,
+    },
+    /// An object transformed by an arbitrary affine transformation.
+    Transform {
+        /// Steps transforming the object into the scene.
+        transform: Vec<TransformStep>,
+        object: Box<ObjectDescription>,
+    },
+    /// An object whose transformation changes during the exposure, blurring it along its motion.
+    AnimatedTransform {
+        /// How the parameters of the steps are interpolated between keyframes.
+        #[serde(default)]
+        interpolation: Interpolation,
+        /// Transformations at increasing points in time, which all consist of the same
+        /// kinds of steps.
+        keyframes: Vec<TransformKeyframeDescription>,
+        object: Box<ObjectDescription>,
+    },
+    /// Constructive solid geometry combining the volumes of two closed objects.
+    Csg {
+        operation: CsgOperation,
+        left: Box<ObjectDescription>,
+        right: Box<ObjectDescription>,
+    },
+    /// A surface given by a signed distance function, traced only within `bounds`,
+    /// the two opposite corners of its bounding box.
+    Sdf {
+        distance: DistanceField,
+        bounds: [Vec3; 2],
+        material: String,
+    },
+    /// A set of objects with its own bounding volume hierarchy.
+    Group {
+        objects: Vec<ObjectDescription>,
+    },
+}
+
+/// One step of an affine transformation. The steps of a transformation are
+/// applied to the object in the order they are listed.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum TransformStep {
+    Translate(Vec3),
+    /// Rotation about `axis` by `angle` degrees, counterclockwise when looking
+    /// against the direction of the axis.
+    Rotate {
+        axis: Vec3,
+        angle: f64,
+    },
+    Scale(Vec3),
+    /// First three rows of an arbitrary affine matrix.
+    Matrix([[f64; 4]; 3]),
+}
+
+/// Part of the parallelogram spanned by the edges of a quad that it covers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuadShape {
+    #[default]
+    Parallelogram,
+    /// The half on the side of the corner.
+    Triangle,
+}
+
+impl QuadShape {
+    fn is_parallelogram(&self) -> bool {
+        *self == QuadShape::Parallelogram
+    }
+}
+
+fn capped_by_default() -> bool {
+    true
+}
+
+fn unit_tile_size() -> f64 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransformKeyframeDescription {
+    pub time: f64,
+    pub transform: Vec<TransformStep>,
+}
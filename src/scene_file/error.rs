@@ -0,0 +1,114 @@
+use std::{fmt, path::PathBuf};
+
+use crate::import::ImportError;
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    /// The scene file could not be read.
+    Io(std::io::Error),
+
+    /// The scene file is not valid TOML or does not match the expected structure.
+    Parse(toml::de::Error),
+
+    /// A glTF file loaded as a scene could not be imported.
+    Import(ImportError),
+
+    /// The scene file is well-formed but describes an invalid scene,
+    /// for example by referring to a material that does not exist.
+    Invalid { line: usize, message: String },
+}
+
+impl SceneFileError {
+    /// Creates an error for the entry starting at byte offset `offset` in `source`.
+    pub fn invalid(source: &str, offset: usize, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            line: line_number(source, offset),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneFileError::Io(err) => write!(f, "failed to read scene file: {err}"),
+            SceneFileError::Parse(err) => write!(f, "{err}"),
+            SceneFileError::Import(err) => write!(f, "{err}"),
+            SceneFileError::Invalid { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {}
+
+impl From<std::io::Error> for SceneFileError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ImportError> for SceneFileError {
+    fn from(err: ImportError) -> Self {
+        Self::Import(err)
+    }
+}
+
+impl From<toml::de::Error> for SceneFileError {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    /// Part of the scene cannot be represented in a scene file.
+    Unsupported(String),
+
+    /// The scene description could not be converted to TOML.
+    Serialize(toml::ser::Error),
+
+    /// The scene file could not be written.
+    Io(std::io::Error),
+
+    /// An image that was not loaded from a file could not be written next to the scene file.
+    Image {
+        path: PathBuf,
+        err: image::ImageError,
+    },
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Unsupported(message) => write!(f, "cannot export scene: {message}"),
+            ExportError::Serialize(err) => write!(f, "failed to serialize scene: {err}"),
+            ExportError::Io(err) => write!(f, "failed to write scene file: {err}"),
+            ExportError::Image { path, err } => {
+                write!(f, "failed to write image {}: {err}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<toml::ser::Error> for ExportError {
+    fn from(err: toml::ser::Error) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+fn line_number(source: &str, offset: usize) -> usize {
+    let offset = offset.min(source.len());
+    source.as_bytes()[..offset]
+        .iter()
+        .filter(|&&c| c == b'\n')
+        .count()
+        + 1
+}
@@ -0,0 +1,346 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use image::ImageResult;
+use toml::Spanned;
+
+use crate::{
+    materials::{material::MaterialRef, texture::Texture},
+    objects::{hittable::Hittable, world::World},
+    scenes::scene::Settings,
+    vec3::Color,
+};
+
+use super::{
+    description::{MaterialDescription, ObjectDescription, SceneDescription, TextureDescription},
+    error::ExportError,
+};
+
+/// Objects that can be written to a scene file.
+pub trait DescribeObject {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<ObjectDescription, ExportError>;
+}
+
+/// Materials that can be written to a scene file.
+pub trait DescribeMaterial {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<MaterialDescription, ExportError>;
+}
+
+/// Textures that can be written to a scene file.
+pub trait DescribeTexture {
+    fn describe(&self, exporter: &mut SceneExporter) -> Result<TextureDescription, ExportError>;
+}
+
+/// Collects the descriptions of the textures and materials used by a world
+/// while its objects are being described.
+pub struct SceneExporter {
+    textures: BTreeMap<String, Spanned<TextureDescription>>,
+    /// Names of the textures exported so far, keyed by the address of the texture
+    /// so that shared textures are only written once.
+    texture_names: Vec<(*const (), String)>,
+    geometry: BTreeMap<String, Spanned<ObjectDescription>>,
+    /// Names of the shared geometry exported so far, keyed by address like `texture_names`.
+    geometry_names: Vec<(*const (), String)>,
+    /// Path of the scene file being written, next to which images without a file are saved.
+    scene_path: Option<PathBuf>,
+    images_saved: usize,
+}
+
+impl SceneExporter {
+    fn new(scene_path: Option<&Path>) -> Self {
+        Self {
+            textures: BTreeMap::new(),
+            texture_names: vec![],
+            geometry: BTreeMap::new(),
+            geometry_names: vec![],
+            scene_path: scene_path.map(Path::to_owned),
+            images_saved: 0,
+        }
+    }
+
+    /// Returns the path under which an image loaded from `path` is written to the scene file.
+    ///
+    /// Images that were not loaded from a file, such as the textures embedded in glTF files,
+    /// are saved by `save` as PNG files next to the scene file. `what` names the image in the
+    /// error if there is no scene file to save them next to.
+    pub fn image_path(
+        &mut self,
+        path: Option<&Path>,
+        what: &str,
+        save: impl FnOnce(&Path) -> ImageResult<()>,
+    ) -> Result<PathBuf, ExportError> {
+        if let Some(path) = path {
+            // Scene files resolve image paths relative to themselves, so the scene file
+            // and its images can be moved together.
+            let path = absolute_path(path);
+            return Ok(match self.scene_path.as_deref().and_then(Path::parent) {
+                Some(dir) => relative_path(&path, &absolute_path(dir)),
+                None => path,
+            });
+        }
+
+        let Some(scene_path) = &self.scene_path else {
+            return Err(ExportError::Unsupported(format!(
+                "{what} was not loaded from a file"
+            )));
+        };
+        let stem = scene_path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = format!("{stem}_image_{}.png", self.images_saved);
+        let image_path = scene_path.with_file_name(&name);
+        save(&image_path).map_err(|err| ExportError::Image {
+            path: image_path,
+            err,
+        })?;
+        self.images_saved += 1;
+
+        Ok(PathBuf::from(name))
+    }
+
+    /// Returns the name under which `texture` is exported, describing it first if necessary.
+    pub fn texture(&mut self, texture: &Arc<dyn Texture>) -> Result<String, ExportError> {
+        if let Some(name) = self.texture_name(texture) {
+            return Ok(name);
+        }
+
+        let description = texture.describe(self)?;
+        Ok(self.add_texture(texture, description))
+    }
+
+    /// Returns the name of `texture` if it has already been exported.
+    fn texture_name(&self, texture: &Arc<dyn Texture>) -> Option<String> {
+        let key = Arc::as_ptr(texture) as *const ();
+        self.texture_names
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, name)| name.clone())
+    }
+
+    /// Adds the `description` of `texture` under a new name and returns that name.
+    fn add_texture(
+        &mut self,
+        texture: &Arc<dyn Texture>,
+        description: TextureDescription,
+    ) -> String {
+        let name = format!("texture_{}", self.texture_names.len());
+        self.texture_names
+            .push((Arc::as_ptr(texture) as *const (), name.clone()));
+        self.textures
+            .insert(name.clone(), Spanned::new(0..0, description));
+        name
+    }
+
+    /// Returns the name under which the geometry shared by instances is exported,
+    /// describing it first if necessary.
+    pub fn geometry(&mut self, object: &Arc<dyn Hittable>) -> Result<String, ExportError> {
+        let key = Arc::as_ptr(object) as *const ();
+        if let Some((_, name)) = self.geometry_names.iter().find(|(k, _)| *k == key) {
+            return Ok(name.clone());
+        }
+
+        let description = object.describe(self)?;
+        let name = format!("geometry_{}", self.geometry_names.len());
+        self.geometry_names.push((key, name.clone()));
+        self.geometry
+            .insert(name.clone(), Spanned::new(0..0, description));
+
+        Ok(name)
+    }
+
+    /// Describes the texture of a material that accepts either a texture name or a color.
+    /// Solid colors are written inline instead of as a separate texture.
+    pub fn texture_or_color(
+        &mut self,
+        texture: &Arc<dyn Texture>,
+    ) -> Result<(Option<String>, Option<Color>), ExportError> {
+        if let Some(name) = self.texture_name(texture) {
+            return Ok((Some(name), None));
+        }
+
+        match texture.describe(self)? {
+            TextureDescription::Solid { color } => Ok((None, Some(color))),
+            description => Ok((Some(self.add_texture(texture, description)), None)),
+        }
+    }
+
+    /// Returns the name under which the material `material` is exported.
+    pub fn material(&self, material: MaterialRef) -> String {
+        material_name(material.0)
+    }
+}
+
+/// `path` made absolute, with symbolic links resolved if it exists.
+fn absolute_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_owned())
+}
+
+/// The path that leads from the directory `base` to `path`, both absolute.
+/// Paths on different drives have no relative form and are returned as they are.
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path_components: Vec<Component> = path.components().collect();
+    let base_components: Vec<Component> = base.components().collect();
+    let common = path_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return path.to_owned();
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push(Component::ParentDir);
+    }
+    relative.extend(&path_components[common..]);
+    relative
+}
+
+fn material_name(index: usize) -> String {
+    format!("material_{index}")
+}
+
+/// Describes `world` together with the camera `settings` so that it can be
+/// written to a scene file at `path`. Without a path, images that were not loaded
+/// from a file cannot be exported.
+pub fn export(
+    settings: Settings,
+    world: &World,
+    path: Option<&Path>,
+) -> Result<SceneDescription, ExportError> {
+    let mut exporter = SceneExporter::new(path);
+
+    let mut materials = BTreeMap::new();
+    for (i, material) in world.materials.iter().enumerate() {
+        let description = material.describe(&mut exporter)?;
+        materials.insert(material_name(i), Spanned::new(0..0, description));
+    }
+
+    let objects = world
+        .objects()
+        .iter()
+        .map(|object| object.describe(&mut exporter))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(SceneDescription {
+        settings,
+        textures: exporter.textures,
+        materials,
+        geometry: exporter.geometry,
+        objects: objects
+            .into_iter()
+            .map(|object| Spanned::new(0..0, object))
+            .collect(),
+    })
+}
+
+/// Writes `world` and the camera `settings` to a scene file at `path`.
+pub fn write(path: &Path, settings: Settings, world: &World) -> Result<(), ExportError> {
+    let description = export(settings, world, Some(path))?;
+    let source = toml::to_string(&description)?;
+    std::fs::write(path, source)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use image::{Rgb, RgbImage};
+
+    use clap::ValueEnum;
+
+    use crate::{
+        builtin_scene,
+        materials::{material::Lambertian, texture::ImageTexture},
+        objects::{aabb::Aabb, bvh::BvhBuilder, hittable::Hittable, sphere::Sphere, world::World},
+        scene_file::loader::load,
+        scenes::scene::Settings,
+        vec3::Vec3,
+    };
+
+    use super::{export, relative_path, write};
+
+    fn assert_same_bounds(a: Aabb, b: Aabb) {
+        for axis in 0..3 {
+            let (a, b) = (a.axis(axis), b.axis(axis));
+            for (a, b) in [(a.0, b.0), (a.1, b.1)] {
+                assert!(
+                    a == b || (a - b).abs() <= 1e-9 * a.abs().max(1.0),
+                    "{a} != {b}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn exported_scenes_load_again() {
+        let dir = std::env::temp_dir().join("raytracer_rs_export_scenes");
+        std::fs::create_dir_all(&dir).unwrap();
+        let working_dir = std::fs::canonicalize(".").unwrap();
+
+        for &variant in crate::Scene::value_variants() {
+            let scene = builtin_scene(variant);
+            let mut world = scene.world(BvhBuilder::default());
+            world.build_bvh();
+            let path = dir.join(format!("{variant}.toml"));
+            write(&path, scene.default_settings(), &world).unwrap();
+
+            // Image paths are relative to the scene file, not absolute.
+            let source = std::fs::read_to_string(&path).unwrap();
+            let absolute = format!("\"{}", working_dir.display());
+            assert!(!source.contains(&absolute), "{variant}");
+
+            let (settings, mut loaded) = load(&path, BvhBuilder::default()).unwrap();
+            loaded.build_bvh();
+            assert_eq!(
+                settings.samples,
+                scene.default_settings().samples,
+                "{variant}"
+            );
+            assert_eq!(loaded.objects().len(), world.objects().len(), "{variant}");
+            assert_eq!(loaded.materials.len(), world.materials.len(), "{variant}");
+            assert_same_bounds(loaded.bvh().bounding_box(), world.bvh().bounding_box());
+        }
+    }
+
+    #[test]
+    fn image_paths_relative_to_scene() {
+        assert_eq!(
+            relative_path(Path::new("/a/b/image.png"), Path::new("/a/c/d")),
+            Path::new("../../b/image.png")
+        );
+        assert_eq!(
+            relative_path(Path::new("/a/image.png"), Path::new("/a")),
+            Path::new("image.png")
+        );
+    }
+
+    #[test]
+    fn saves_images_without_file_next_to_scene() {
+        // Such as a texture embedded in a glTF file.
+        let texture = ImageTexture::new(RgbImage::from_pixel(2, 2, Rgb([255, 0, 0])));
+        let mut world = World::new(BvhBuilder::default());
+        let material = world.register_material(Box::new(Lambertian::new(Arc::new(texture))));
+        world.set_objects(vec![Arc::new(Sphere::stationary(
+            Vec3(0.0, 0.0, 0.0),
+            1.0,
+            material,
+        ))]);
+
+        assert!(export(Settings::default(), &world, None).is_err());
+
+        let dir = std::env::temp_dir().join("raytracer_rs_export_images");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scene.toml");
+        write(&path, Settings::default(), &world).unwrap();
+        assert!(dir.join("scene_image_0.png").is_file());
+
+        let (_, loaded) = load(&path, BvhBuilder::default()).unwrap();
+        assert_eq!(loaded.materials.len(), 1);
+    }
+}
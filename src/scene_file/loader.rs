@@ -0,0 +1,1078 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    import::{
+        bpt::load_bpt,
+        gltf::load_gltf,
+        obj::load_obj,
+        ply::{default_material, load_ply, load_ply_points},
+    },
+    materials::{
+        material::{Dialectric, DiffuseLight, Isotropic, Lambertian, Material, MaterialRef, Metal},
+        normal_map::{BumpMap, NormalMap, SurfaceDetail},
+        texture::{
+            CheckerTexture, ImageTexture, SolidColor, TexCoord, Texture, VertexColorTexture,
+        },
+    },
+    matrix::Matrix4,
+    objects::{
+        aabb::Aabb,
+        animated_transform::{AnimatedTransform, TransformAnimation, TransformKeyframe},
+        bezier_patch::BezierPatch,
+        bvh::BvhBuilder,
+        cone::Cone,
+        constant_volume::ConstantVolume,
+        csg::Csg,
+        cube::cube,
+        curves::Curves,
+        cylinder::Cylinder,
+        disk::Disk,
+        heightfield::Heightfield,
+        hittable::Hittable,
+        instance::Instance,
+        mesh::{MeshData, TriangleMesh},
+        metaballs::Metaballs,
+        plane::Plane,
+        point_cloud::{PointCloud, PointCloudData},
+        quad::{PlanarShape, Quad},
+        sdf::Sdf,
+        sphere::Sphere,
+        subdivision::{PolygonMesh, Subdivision},
+        torus::Torus,
+        transform::{AffineStep, RotateY, Transform, Translate},
+        triangle::Triangle,
+        world::World,
+    },
+    scenes::scene::Settings,
+    util::deg2rad,
+    vec3::{Color, Vec3},
+};
+
+use super::{
+    description::{
+        MaterialDescription, ObjectDescription, QuadShape, SceneDescription, TextureDescription,
+        TransformStep,
+    },
+    error::SceneFileError,
+};
+
+/// Reads the scene file at `path` and builds the camera settings and world it describes.
+///
+/// Relative image paths inside the file are resolved relative to the directory
+/// containing the scene file.
+///
+/// glTF and GLB files can be loaded directly as well, using the first camera in the file if any.
+/// The BVHs of the world and of its objects are built with `bvh_builder`.
+pub fn load(path: &Path, bvh_builder: BvhBuilder) -> Result<(Settings, World), SceneFileError> {
+    let extension = path.extension().and_then(|e| e.to_str());
+    if matches!(extension, Some("gltf" | "glb")) {
+        let mut settings = Settings::default();
+        let mut world = World::new(bvh_builder);
+        let scene = load_gltf(path, &mut world, None, None)?;
+        if let Some(camera) = scene.cameras.first() {
+            camera.apply(&mut settings);
+        }
+        world.set_objects(scene.objects);
+        return Ok((settings, world));
+    }
+
+    let source = std::fs::read_to_string(path)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    load_from_str(&source, base_dir, bvh_builder)
+}
+
+pub fn load_from_str(
+    source: &str,
+    base_dir: &Path,
+    bvh_builder: BvhBuilder,
+) -> Result<(Settings, World), SceneFileError> {
+    let description: SceneDescription = toml::from_str(source)?;
+
+    let mut loader = Loader {
+        source,
+        settings: description.settings.clone(),
+        base_dir,
+        description: &description,
+        textures: HashMap::new(),
+        resolving_textures: vec![],
+        materials: HashMap::new(),
+        geometry: HashMap::new(),
+        resolving_geometry: vec![],
+        world: World::new(bvh_builder),
+    };
+
+    for (name, material) in &description.materials {
+        let offset = material.span().start;
+        let material = loader.material(material.get_ref(), offset)?;
+        let material_ref = loader.world.register_material(material);
+        loader.materials.insert(name.clone(), material_ref);
+    }
+
+    let mut objects = vec![];
+    for object in &description.objects {
+        objects.push(loader.object(object.get_ref(), object.span().start)?);
+    }
+
+    if objects.is_empty() {
+        return Err(SceneFileError::invalid(
+            source,
+            source.len(),
+            "scene does not contain any objects",
+        ));
+    }
+
+    let mut world = loader.world;
+    world.set_objects(objects);
+
+    Ok((loader.settings, world))
+}
+
+struct Loader<'a> {
+    source: &'a str,
+    settings: Settings,
+    base_dir: &'a Path,
+    description: &'a SceneDescription,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    /// Names of the textures currently being built, used to detect cycles
+    /// between checker textures.
+    resolving_textures: Vec<String>,
+    materials: HashMap<String, MaterialRef>,
+    geometry: HashMap<String, Arc<dyn Hittable>>,
+    /// Names of the geometry currently being built, used to detect geometry instancing itself.
+    resolving_geometry: Vec<String>,
+    world: World,
+}
+
+impl<'a> Loader<'a> {
+    fn error(&self, offset: usize, message: impl Into<String>) -> SceneFileError {
+        SceneFileError::invalid(self.source, offset, message)
+    }
+
+    fn subdivision(
+        &self,
+        subdivision: Option<Subdivision>,
+        offset: usize,
+    ) -> Result<Option<Subdivision>, SceneFileError> {
+        if let Some(Subdivision { levels, .. }) = subdivision {
+            if !(1..=Subdivision::MAX_LEVELS).contains(&levels) {
+                return Err(self.error(
+                    offset,
+                    format!(
+                        "subdivision levels must be between 1 and {}",
+                        Subdivision::MAX_LEVELS
+                    ),
+                ));
+            }
+        }
+        Ok(subdivision)
+    }
+
+    /// Looks up (and builds if necessary) the texture called `name`.
+    /// `offset` is the position of the entry referring to the texture.
+    fn texture(&mut self, name: &str, offset: usize) -> Result<Arc<dyn Texture>, SceneFileError> {
+        if let Some(texture) = self.textures.get(name) {
+            return Ok(Arc::clone(texture));
+        }
+
+        let description = self.description;
+        let Some(spanned) = description.textures.get(name) else {
+            return Err(self.error(offset, format!("unknown texture `{name}`")));
+        };
+        let offset = spanned.span().start;
+
+        if self.resolving_textures.iter().any(|n| n == name) {
+            return Err(self.error(offset, format!("texture `{name}` refers to itself")));
+        }
+        self.resolving_textures.push(name.to_owned());
+
+        let texture: Arc<dyn Texture> = match spanned.get_ref() {
+            TextureDescription::Solid { color } => Arc::new(SolidColor { color: *color }),
+            TextureDescription::Checker { scale, even, odd } => {
+                if *scale <= 0.0 {
+                    return Err(self.error(offset, "checker scale must be positive"));
+                }
+                let even = self.texture(even, offset)?;
+                let odd = self.texture(odd, offset)?;
+                Arc::new(CheckerTexture::new(*scale, even, odd))
+            }
+            TextureDescription::Image { path } => {
+                let path = self.resolve_path(path);
+                let texture = ImageTexture::open(&path).map_err(|err| {
+                    self.error(
+                        offset,
+                        format!("failed to load image `{}`: {err}", path.display()),
+                    )
+                })?;
+                Arc::new(texture)
+            }
+            TextureDescription::VertexColor => Arc::new(VertexColorTexture),
+        };
+
+        self.resolving_textures.pop();
+        self.textures.insert(name.to_owned(), Arc::clone(&texture));
+
+        Ok(texture)
+    }
+
+    /// Resolves the texture of a material that accepts either a texture name or a color.
+    fn texture_or_color(
+        &mut self,
+        texture: &Option<String>,
+        color: &Option<Color>,
+        offset: usize,
+    ) -> Result<Arc<dyn Texture>, SceneFileError> {
+        match (texture, color) {
+            (Some(name), None) => self.texture(name, offset),
+            (None, Some(color)) => Ok(Arc::new(SolidColor { color: *color })),
+            (Some(_), Some(_)) => Err(self.error(
+                offset,
+                "material must specify either `texture` or `color`, not both",
+            )),
+            (None, None) => Err(self.error(offset, "material must specify `texture` or `color`")),
+        }
+    }
+
+    fn material(
+        &mut self,
+        description: &MaterialDescription,
+        offset: usize,
+    ) -> Result<Box<dyn Material>, SceneFileError> {
+        Ok(match description {
+            MaterialDescription::Lambertian {
+                texture,
+                color,
+                normal_map,
+                bump_map,
+                bump_strength,
+            } => Box::new(
+                Lambertian::new(self.texture_or_color(texture, color, offset)?)
+                    .with_surface_detail(self.surface_detail(
+                        normal_map,
+                        bump_map,
+                        *bump_strength,
+                        offset,
+                    )?),
+            ),
+            MaterialDescription::Metal {
+                texture,
+                color,
+                fuzziness,
+                normal_map,
+                bump_map,
+                bump_strength,
+            } => Box::new(
+                Metal::new(self.texture_or_color(texture, color, offset)?, *fuzziness)
+                    .with_surface_detail(self.surface_detail(
+                        normal_map,
+                        bump_map,
+                        *bump_strength,
+                        offset,
+                    )?),
+            ),
+            MaterialDescription::Dialectric {
+                refraction_index,
+                normal_map,
+                bump_map,
+                bump_strength,
+            } => Box::new(Dialectric::new(*refraction_index).with_surface_detail(
+                self.surface_detail(normal_map, bump_map, *bump_strength, offset)?,
+            )),
+            MaterialDescription::DiffuseLight { texture, color } => Box::new(DiffuseLight::new(
+                self.texture_or_color(texture, color, offset)?,
+            )),
+            MaterialDescription::Isotropic { texture, color } => Box::new(Isotropic::new(
+                self.texture_or_color(texture, color, offset)?,
+            )),
+        })
+    }
+
+    fn surface_detail(
+        &self,
+        normal_map: &Option<PathBuf>,
+        bump_map: &Option<PathBuf>,
+        bump_strength: Option<f64>,
+        offset: usize,
+    ) -> Result<SurfaceDetail, SceneFileError> {
+        let load_error = |path: &Path, err| {
+            self.error(
+                offset,
+                format!("failed to load image `{}`: {err}", path.display()),
+            )
+        };
+
+        let normal_map = match normal_map {
+            Some(path) => {
+                let path = self.resolve_path(path);
+                Some(NormalMap::open(&path).map_err(|err| load_error(&path, err))?)
+            }
+            None => None,
+        };
+
+        let bump_map = match (bump_map, bump_strength) {
+            (Some(path), strength) => {
+                let strength = strength.unwrap_or(1.0);
+                if !strength.is_finite() {
+                    return Err(self.error(offset, "bump strength must be finite"));
+                }
+                let path = self.resolve_path(path);
+                Some(BumpMap::open(&path, strength).map_err(|err| load_error(&path, err))?)
+            }
+            (None, Some(_)) => {
+                return Err(self.error(offset, "`bump_strength` requires a `bump_map`"));
+            }
+            (None, None) => None,
+        };
+
+        Ok(SurfaceDetail {
+            normal_map,
+            bump_map,
+        })
+    }
+
+    fn material_ref(&self, name: &str, offset: usize) -> Result<MaterialRef, SceneFileError> {
+        self.materials
+            .get(name)
+            .copied()
+            .ok_or_else(|| self.error(offset, format!("unknown material `{name}`")))
+    }
+
+    /// Looks up (and builds if necessary) the shared geometry called `name`.
+    /// `offset` is the position of the entry referring to the geometry.
+    fn geometry(&mut self, name: &str, offset: usize) -> Result<Arc<dyn Hittable>, SceneFileError> {
+        if let Some(geometry) = self.geometry.get(name) {
+            return Ok(Arc::clone(geometry));
+        }
+
+        let description = self.description;
+        let Some(spanned) = description.geometry.get(name) else {
+            return Err(self.error(offset, format!("unknown geometry `{name}`")));
+        };
+        let offset = spanned.span().start;
+
+        if self.resolving_geometry.iter().any(|n| n == name) {
+            return Err(self.error(offset, format!("geometry `{name}` refers to itself")));
+        }
+        self.resolving_geometry.push(name.to_owned());
+        let geometry = self.object(spanned.get_ref(), offset)?;
+        self.resolving_geometry.pop();
+
+        self.geometry.insert(name.to_owned(), Arc::clone(&geometry));
+
+        Ok(geometry)
+    }
+
+    /// Builds an object. Nested objects are reported at the offset of the
+    /// top-level entry they belong to.
+    fn object(
+        &mut self,
+        description: &ObjectDescription,
+        offset: usize,
+    ) -> Result<Arc<dyn Hittable>, SceneFileError> {
+        Ok(match description {
+            ObjectDescription::Sphere {
+                center,
+                end_center,
+                radius,
+                material,
+            } => {
+                if *radius <= 0.0 {
+                    return Err(self.error(offset, "sphere radius must be positive"));
+                }
+                let material = self.material_ref(material, offset)?;
+                match end_center {
+                    Some(end_center) => {
+                        Arc::new(Sphere::moving(*center, *end_center, *radius, material))
+                    }
+                    None => Arc::new(Sphere::stationary(*center, *radius, material)),
+                }
+            }
+            ObjectDescription::Quad {
+                corner,
+                u,
+                v,
+                shape,
+                material,
+            } => {
+                if u.cross(*v).near_zero() {
+                    return Err(self.error(offset, "quad edges `u` and `v` must not be parallel"));
+                }
+                let shape = match shape {
+                    QuadShape::Parallelogram => PlanarShape::Parallelogram,
+                    QuadShape::Triangle => PlanarShape::Triangle,
+                };
+                let material = self.material_ref(material, offset)?;
+                Arc::new(Quad::with_shape(*corner, *u, *v, shape, material))
+            }
+            ObjectDescription::Ellipse {
+                center,
+                u,
+                v,
+                material,
+            } => {
+                if u.cross(*v).near_zero() {
+                    return Err(self.error(offset, "ellipse axes `u` and `v` must not be parallel"));
+                }
+                let material = self.material_ref(material, offset)?;
+                Arc::new(Quad::with_shape(
+                    *center,
+                    *u,
+                    *v,
+                    PlanarShape::Ellipse,
+                    material,
+                ))
+            }
+            ObjectDescription::Annulus {
+                center,
+                u,
+                v,
+                inner,
+                material,
+            } => {
+                if u.cross(*v).near_zero() {
+                    return Err(self.error(offset, "annulus axes `u` and `v` must not be parallel"));
+                }
+                if !(0.0..1.0).contains(inner) {
+                    return Err(self.error(offset, "annulus `inner` must be between 0 and 1"));
+                }
+                let material = self.material_ref(material, offset)?;
+                Arc::new(Quad::with_shape(
+                    *center,
+                    *u,
+                    *v,
+                    PlanarShape::Annulus { inner: *inner },
+                    material,
+                ))
+            }
+            ObjectDescription::Plane {
+                point,
+                normal,
+                tile_size,
+                material,
+            } => {
+                if normal.near_zero() {
+                    return Err(self.error(offset, "plane normal must not be zero"));
+                }
+                if *tile_size <= 0.0 {
+                    return Err(self.error(offset, "plane tile size must be positive"));
+                }
+                let material = self.material_ref(material, offset)?;
+                Arc::new(Plane::new(*point, *normal, *tile_size, material))
+            }
+            ObjectDescription::Disk {
+                center,
+                normal,
+                radius,
+                material,
+            } => {
+                if normal.near_zero() {
+                    return Err(self.error(offset, "disk normal must not be zero"));
+                }
+                if *radius <= 0.0 {
+                    return Err(self.error(offset, "disk radius must be positive"));
+                }
+                let material = self.material_ref(material, offset)?;
+                Arc::new(Disk::new(*center, *normal, *radius, material))
+            }
+            ObjectDescription::Cylinder {
+                base,
+                top,
+                radius,
+                capped,
+                material,
+            } => {
+                if (*top - *base).near_zero() {
+                    return Err(self.error(offset, "cylinder ends must not coincide"));
+                }
+                if *radius <= 0.0 {
+                    return Err(self.error(offset, "cylinder radius must be positive"));
+                }
+                let material = self.material_ref(material, offset)?;
+                Arc::new(Cylinder::new(*base, *top, *radius, *capped, material))
+            }
+            ObjectDescription::Cone {
+                base,
+                apex,
+                radius,
+                capped,
+                material,
+            } => {
+                if (*apex - *base).near_zero() {
+                    return Err(self.error(offset, "cone apex must not coincide with its base"));
+                }
+                if *radius <= 0.0 {
+                    return Err(self.error(offset, "cone radius must be positive"));
+                }
+                let material = self.material_ref(material, offset)?;
+                Arc::new(Cone::new(*base, *apex, *radius, *capped, material))
+            }
+            ObjectDescription::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+                material,
+            } => {
+                if axis.near_zero() {
+                    return Err(self.error(offset, "torus axis must not be zero"));
+                }
+                if *major_radius <= 0.0 || *minor_radius <= 0.0 {
+                    return Err(self.error(offset, "torus radii must be positive"));
+                }
+                let material = self.material_ref(material, offset)?;
+                Arc::new(Torus::new(
+                    *center,
+                    *axis,
+                    *major_radius,
+                    *minor_radius,
+                    material,
+                ))
+            }
+            ObjectDescription::Triangle {
+                a,
+                b,
+                c,
+                normals,
+                material,
+            } => {
+                if (*b - *a).cross(*c - *a).near_zero() {
+                    return Err(self.error(offset, "triangle must not be degenerate"));
+                }
+                let material = self.material_ref(material, offset)?;
+                match normals {
+                    Some(normals) => {
+                        Arc::new(Triangle::with_normals(*a, *b, *c, *normals, material))
+                    }
+                    None => Arc::new(Triangle::new(*a, *b, *c, material)),
+                }
+            }
+            ObjectDescription::Mesh {
+                positions,
+                indices,
+                normals,
+                uvs,
+                colors,
+                subdivision,
+                material,
+            } => {
+                let subdivision = self.subdivision(*subdivision, offset)?;
+                let material = self.material_ref(material, offset)?;
+                let data = MeshData {
+                    positions: positions.clone(),
+                    normals: normals.clone(),
+                    uvs: uvs.iter().map(|&[u, v]| TexCoord::new(u, v)).collect(),
+                    colors: colors.clone(),
+                    indices: indices.clone(),
+                };
+                data.validate().map_err(|err| self.error(offset, err))?;
+                let data = match subdivision {
+                    Some(subdivision) => PolygonMesh::from_triangles(&data).subdivide(subdivision),
+                    None => data,
+                };
+                let (mesh, stats) =
+                    TriangleMesh::build(Arc::new(data), material, self.world.bvh_builder());
+                self.world.add_bvh_stats(stats);
+                Arc::new(mesh)
+            }
+            ObjectDescription::Obj {
+                path,
+                material,
+                subdivision,
+            } => {
+                let subdivision = self.subdivision(*subdivision, offset)?;
+                let material = match material {
+                    Some(material) => Some(self.material_ref(material, offset)?),
+                    None => None,
+                };
+                let path = self.resolve_path(path);
+                let meshes = load_obj(&path, &mut self.world, material, subdivision)
+                    .map_err(|err| self.error(offset, err.to_string()))?;
+                if meshes.len() == 1 {
+                    Arc::clone(&meshes[0])
+                } else {
+                    Arc::new(self.world.object_bvh(&meshes))
+                }
+            }
+            ObjectDescription::Ply {
+                path,
+                material,
+                subdivision,
+            } => {
+                let subdivision = self.subdivision(*subdivision, offset)?;
+                let material = match material {
+                    Some(material) => Some(self.material_ref(material, offset)?),
+                    None => None,
+                };
+                let path = self.resolve_path(path);
+                load_ply(&path, &mut self.world, material, subdivision)
+                    .map_err(|err| self.error(offset, err.to_string()))?
+            }
+            ObjectDescription::Gltf {
+                path,
+                material,
+                camera,
+                subdivision,
+            } => {
+                let subdivision = self.subdivision(*subdivision, offset)?;
+                let material = match material {
+                    Some(material) => Some(self.material_ref(material, offset)?),
+                    None => None,
+                };
+                let path = self.resolve_path(path);
+                let scene = load_gltf(&path, &mut self.world, material, subdivision)
+                    .map_err(|err| self.error(offset, err.to_string()))?;
+                if let Some(index) = camera {
+                    let Some(camera) = scene.cameras.get(*index) else {
+                        return Err(self.error(
+                            offset,
+                            format!("`{}` has no camera {index}", path.display()),
+                        ));
+                    };
+                    camera.apply(&mut self.settings);
+                }
+                if scene.objects.len() == 1 {
+                    Arc::clone(&scene.objects[0])
+                } else {
+                    Arc::new(self.world.object_bvh(&scene.objects))
+                }
+            }
+            ObjectDescription::Heightfield {
+                path,
+                corner,
+                scale,
+                material,
+            } => {
+                if scale.x() <= 0.0 || scale.y() <= 0.0 || scale.z() <= 0.0 {
+                    return Err(self.error(offset, "heightfield scale must be positive"));
+                }
+                let material = self.material_ref(material, offset)?;
+                let path = self.resolve_path(path);
+                let heightfield =
+                    Heightfield::open(&path, *corner, *scale, material).map_err(|err| {
+                        self.error(
+                            offset,
+                            format!("failed to load heightfield `{}`: {err}", path.display()),
+                        )
+                    })?;
+                Arc::new(heightfield)
+            }
+            ObjectDescription::Metaballs {
+                balls,
+                threshold,
+                material,
+            } => {
+                if balls.iter().any(|ball| ball.radius <= 0.0) {
+                    return Err(self.error(offset, "metaball radius must be positive"));
+                }
+                if !balls.iter().any(|ball| ball.strength > 0.0) {
+                    return Err(self.error(offset, "metaballs need a ball of positive strength"));
+                }
+                if *threshold <= 0.0 {
+                    return Err(self.error(offset, "metaball threshold must be positive"));
+                }
+                let material = self.material_ref(material, offset)?;
+                Arc::new(Metaballs::new(balls.clone(), *threshold, material))
+            }
+            ObjectDescription::BezierPatch { points, material } => {
+                let Ok(points) = <[Vec3; 16]>::try_from(points.as_slice()) else {
+                    return Err(self.error(offset, "bezier patch needs 16 control points"));
+                };
+                let material = self.material_ref(material, offset)?;
+                Arc::new(BezierPatch::new(points, material))
+            }
+            ObjectDescription::Bpt { path, material } => {
+                let material = self.material_ref(material, offset)?;
+                let path = self.resolve_path(path);
+                let patches =
+                    load_bpt(&path, material).map_err(|err| self.error(offset, err.to_string()))?;
+                if patches.is_empty() {
+                    return Err(self.error(offset, "BPT file does not contain any patches"));
+                }
+                Arc::new(self.world.object_bvh(&patches))
+            }
+            ObjectDescription::PointCloud {
+                path,
+                points,
+                normals,
+                colors,
+                radius,
+                shape,
+                material,
+            } => {
+                if !(radius.is_finite() && *radius > 0.0) {
+                    return Err(self.error(offset, "point radius must be positive"));
+                }
+                let material = match material {
+                    Some(material) => Some(self.material_ref(material, offset)?),
+                    None => None,
+                };
+                match path {
+                    Some(path) => {
+                        if !points.is_empty() || !normals.is_empty() || !colors.is_empty() {
+                            return Err(self.error(
+                                offset,
+                                "point cloud takes either a `path` or inline `points`",
+                            ));
+                        }
+                        let path = self.resolve_path(path);
+                        load_ply_points(&path, &mut self.world, material, *radius, *shape)
+                            .map_err(|err| self.error(offset, err.to_string()))?
+                    }
+                    None => {
+                        let data = PointCloudData {
+                            positions: points.clone(),
+                            normals: normals.clone(),
+                            colors: colors.clone(),
+                        };
+                        data.validate().map_err(|err| self.error(offset, err))?;
+                        let material = match material {
+                            Some(material) => material,
+                            None => default_material(&mut self.world, !data.colors.is_empty()),
+                        };
+                        let builder = self.world.bvh_builder();
+                        let (cloud, stats) =
+                            PointCloud::build(data, *radius, *shape, material, builder)
+                                .map_err(|err| self.error(offset, err))?;
+                        self.world.add_bvh_stats(stats);
+                        Arc::new(cloud)
+                    }
+                }
+            }
+            ObjectDescription::Curves {
+                basis,
+                shape,
+                strands,
+                material,
+            } => {
+                let material = self.material_ref(material, offset)?;
+                let builder = self.world.bvh_builder();
+                let (curves, stats) =
+                    Curves::build(strands.clone(), *basis, *shape, material, builder)
+                        .map_err(|err| self.error(offset, err))?;
+                self.world.add_bvh_stats(stats);
+                Arc::new(curves)
+            }
+            ObjectDescription::Cube { a, b, material } => {
+                let material = self.material_ref(material, offset)?;
+                Arc::new(cube(*a, *b, material))
+            }
+            ObjectDescription::ConstantVolume {
+                boundary,
+                density,
+                material,
+            } => {
+                if *density <= 0.0 {
+                    return Err(self.error(offset, "volume density must be positive"));
+                }
+                let material = self.material_ref(material, offset)?;
+                let boundary = self.object(boundary, offset)?;
+                Arc::new(ConstantVolume::new(boundary, *density, material))
+            }
+            ObjectDescription::Translate { offset: by, object } => {
+                Arc::new(Translate::new(*by, self.object(object, offset)?))
+            }
+            ObjectDescription::RotateY { angle, object } => {
+                Arc::new(RotateY::new(deg2rad(*angle), self.object(object, offset)?))
+            }
+            ObjectDescription::Instance {
+                geometry,
+                transform,
+            } => {
+                let transform = self.transform(transform, offset)?;
+                Arc::new(Instance::new(self.geometry(geometry, offset)?, transform))
+            }
+            ObjectDescription::Transform { transform, object } => {
+                let transform = self.transform(transform, offset)?;
+                Arc::new(Transform::new(self.object(object, offset)?, transform))
+            }
+            ObjectDescription::AnimatedTransform {
+                keyframes,
+                interpolation,
+                object,
+            } => {
+                let keyframes = keyframes
+                    .iter()
+                    .map(|keyframe| {
+                        Ok(TransformKeyframe {
+                            time: keyframe.time,
+                            steps: self.transform_steps(&keyframe.transform, offset)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, SceneFileError>>()?;
+                let animation = TransformAnimation::new(keyframes, *interpolation)
+                    .map_err(|err| self.error(offset, err))?;
+                Arc::new(AnimatedTransform::new(
+                    self.object(object, offset)?,
+                    animation,
+                ))
+            }
+            ObjectDescription::Csg {
+                operation,
+                left,
+                right,
+            } => Arc::new(Csg::new(
+                *operation,
+                self.object(left, offset)?,
+                self.object(right, offset)?,
+            )),
+            ObjectDescription::Sdf {
+                distance,
+                bounds,
+                material,
+            } => {
+                distance.validate().map_err(|err| self.error(offset, err))?;
+                let bounds = Aabb::span_points(bounds[0], bounds[1]);
+                if !bounds.is_bounded() {
+                    return Err(self.error(offset, "distance field bounds must be finite"));
+                }
+                let material = self.material_ref(material, offset)?;
+                Arc::new(Sdf::new(distance.clone(), bounds, material))
+            }
+            ObjectDescription::Group { objects } => {
+                if objects.is_empty() {
+                    return Err(self.error(offset, "group does not contain any objects"));
+                }
+                let objects = objects
+                    .iter()
+                    .map(|object| self.object(object, offset))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Arc::new(self.world.object_bvh(&objects))
+            }
+        })
+    }
+
+    /// Converts the steps of a transformation from their description.
+    fn transform_steps(
+        &self,
+        steps: &[TransformStep],
+        offset: usize,
+    ) -> Result<Vec<AffineStep>, SceneFileError> {
+        steps
+            .iter()
+            .map(|step| {
+                Ok(match step {
+                    TransformStep::Translate(by) => AffineStep::Translate(*by),
+                    TransformStep::Rotate { axis, angle } => {
+                        if axis.near_zero() {
+                            return Err(self.error(offset, "rotation axis must not be zero"));
+                        }
+                        AffineStep::Rotate {
+                            axis: *axis,
+                            angle: deg2rad(*angle),
+                        }
+                    }
+                    TransformStep::Scale(factors) => AffineStep::Scale(*factors),
+                    TransformStep::Matrix(rows) => {
+                        AffineStep::Matrix(Matrix4::from_affine_rows(*rows))
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Combines the steps of a transformation into a single matrix.
+    fn transform(&self, steps: &[TransformStep], offset: usize) -> Result<Matrix4, SceneFileError> {
+        let matrix = AffineStep::combine(&self.transform_steps(steps, offset)?);
+        if matrix.inverse().is_none() {
+            return Err(self.error(offset, "transform must be invertible"));
+        }
+        Ok(matrix)
+    }
+
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        if path.is_relative() {
+            self.base_dir.join(path)
+        } else {
+            path.to_owned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{
+        objects::{aabb::Aabb, bvh::BvhBuilder},
+        scene_file::{error::SceneFileError, exporter::export},
+        scenes::scene::Settings,
+        vec3::Vec3,
+    };
+
+    use super::{load, load_from_str};
+
+    fn load_error(source: &str) -> SceneFileError {
+        match load_from_str(source, Path::new("."), BvhBuilder::default()) {
+            Ok(_) => panic!("expected scene file to be rejected"),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn loads_minimal_scene() {
+        let source = r#"
+            [settings]
+            width = 64
+            height = 32
+
+            [materials.red]
+            type = "lambertian"
+            color = [0.65, 0.05, 0.05]
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, 0.0]
+            radius = 1.0
+            material = "red"
+        "#;
+
+        let (settings, world) =
+            load_from_str(source, Path::new("."), BvhBuilder::default()).unwrap();
+        assert_eq!(settings.width, 64);
+        assert_eq!(settings.height, 32);
+        assert_eq!(settings.samples, 100);
+        assert_eq!(world.materials.len(), 1);
+    }
+
+    #[test]
+    fn bvh_stats_include_meshes() {
+        let source = r#"
+            [materials.red]
+            type = "lambertian"
+            color = [0.65, 0.05, 0.05]
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, 0.0]
+            radius = 1.0
+            material = "red"
+
+            [[objects]]
+            type = "mesh"
+            positions = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0]]
+            indices = [[0, 1, 2], [2, 1, 3]]
+            material = "red"
+        "#;
+
+        let (_, mut world) = load_from_str(source, Path::new("."), BvhBuilder::Median).unwrap();
+        let stats = world.build_bvh();
+        // One leaf with the sphere and the mesh, and one with the two triangles of the mesh.
+        assert_eq!(stats.leaves, 2);
+        assert_eq!(stats.objects, 4);
+    }
+
+    #[test]
+    fn animates_camera_between_keyframes() {
+        let source = r#"
+            [settings]
+            camera_eye = [0.0, 0.0, 10.0]
+            fov = 40.0
+
+            [settings.animation]
+            frames = 10
+            camera = [
+                { time = 1, eye = [0.0, 0.0, 10.0] },
+                { time = 10, eye = [9.0, 0.0, 10.0], fov = 20.0 },
+            ]
+
+            [materials.red]
+            type = "lambertian"
+            color = [0.65, 0.05, 0.05]
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, 0.0]
+            radius = 1.0
+            material = "red"
+        "#;
+
+        let (settings, _) = load_from_str(source, Path::new("."), BvhBuilder::default()).unwrap();
+        let frames = settings.animation.frame_range().unwrap();
+        assert_eq!((frames.first, frames.last), (1, 10));
+
+        let halfway = settings.at_time(5.5);
+        assert!((halfway.camera_eye - Vec3(4.5, 0.0, 10.0)).length() < 1e-12);
+        // The only keyframe setting the field of view holds it over the whole animation.
+        assert_eq!(halfway.fov, 20.0);
+        assert!((settings.at_time(0.0).camera_eye - Vec3(0.0, 0.0, 10.0)).length() < 1e-12);
+    }
+
+    #[test]
+    fn loads_bundled_cornell_box() {
+        let (settings, world) = load(
+            Path::new("assets/scenes/cornell_box.toml"),
+            BvhBuilder::default(),
+        )
+        .unwrap();
+        assert_eq!(settings.width, 720);
+        assert_eq!(world.materials.len(), 4);
+    }
+
+    #[test]
+    fn reports_line_of_unknown_material() {
+        let source = "[materials.red]\n\
+                      type = \"lambertian\"\n\
+                      color = [1.0, 0.0, 0.0]\n\
+                      \n\
+                      [[objects]]\n\
+                      type = \"sphere\"\n\
+                      center = [0.0, 0.0, 0.0]\n\
+                      radius = 1.0\n\
+                      material = \"blue\"\n";
+
+        match load_error(source) {
+            SceneFileError::Invalid { line, message } => {
+                assert_eq!(line, 5);
+                assert_eq!(message, "unknown material `blue`");
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn instances_share_geometry() {
+        let source = r#"
+            [materials.white]
+            type = "lambertian"
+            color = [0.73, 0.73, 0.73]
+
+            [geometry.box]
+            type = "cube"
+            a = [0.0, 0.0, 0.0]
+            b = [1.0, 1.0, 1.0]
+            material = "white"
+
+            [[objects]]
+            type = "instance"
+            geometry = "box"
+            transform = [{ scale = [2.0, 1.0, 1.0] }, { translate = [5.0, 0.0, 0.0] }]
+
+            [[objects]]
+            type = "instance"
+            geometry = "box"
+        "#;
+
+        let (_, world) = load_from_str(source, Path::new("."), BvhBuilder::default()).unwrap();
+        let [first, second] = world.objects() else {
+            panic!("expected two instances");
+        };
+        let bounds = Aabb::combine(first.bounding_box(), second.bounding_box());
+        assert!(bounds.x.0.abs() < 1e-3 && (bounds.x.1 - 7.0).abs() < 1e-3);
+
+        let description = export(Settings::default(), &world, None).unwrap();
+        assert_eq!(description.geometry.len(), 1);
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let source = "[settings]\nwidht = 100\n";
+
+        match load_error(source) {
+            SceneFileError::Parse(err) => assert!(err.to_string().contains("line 2")),
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod description;
+pub mod error;
+pub mod exporter;
+pub mod loader;
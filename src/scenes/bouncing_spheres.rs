@@ -5,12 +5,12 @@ use crate::{
         material::{Dialectric, Lambertian, Metal},
         texture::CheckerTexture,
     },
-    objects::{bvh::Bvh, hittable::Hittable, sphere::Sphere, world::World},
+    objects::{bvh::BvhBuilder, hittable::Hittable, plane::Plane, sphere::Sphere, world::World},
     util::{rand_f64, rand_vec3},
     vec3::{Color, Vec3},
 };
 
-use super::scene::{Scene, Settings};
+use super::scene::{AnimationSettings, Scene, Settings};
 
 pub struct BouncingSpheresScene;
 
@@ -19,6 +19,7 @@ impl Scene for BouncingSpheresScene {
         Settings {
             camera_eye: Vec3(13.0, 2.0, 3.0),
             camera_target: Vec3(0.0, 0.0, -1.0),
+            camera_up: Vec3(0.0, 1.0, 0.0),
             width: 1280,
             height: 720,
             fov: 20.0,
@@ -27,11 +28,14 @@ impl Scene for BouncingSpheresScene {
             samples: 100,
             max_bounces: 50,
             background_color: None,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            animation: AnimationSettings::default(),
         }
     }
 
-    fn world(&self) -> World {
-        let mut world = World::new();
+    fn world(&self, bvh_builder: BvhBuilder) -> World {
+        let mut world = World::new(bvh_builder);
 
         let mut objects: Vec<Arc<dyn Hittable>> = vec![];
 
@@ -41,9 +45,10 @@ impl Scene for BouncingSpheresScene {
             Color::new(0.9, 0.9, 0.9),
         ));
         let material_ground = world.register_material(Box::new(Lambertian::new(checker_texture)));
-        objects.push(Arc::new(Sphere::stationary(
-            Vec3(0.0, -1000.0, 0.0),
-            1000.0,
+        objects.push(Arc::new(Plane::new(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            1.0,
             material_ground,
         )));
 
@@ -63,7 +68,7 @@ impl Scene for BouncingSpheresScene {
                     } else if choose_mat < 0.95 {
                         let albedo = rand_vec3(0.5, 1.0);
                         let fuzz = rand_f64(0.0, 0.5);
-                        world.register_material(Box::new(Metal::new(albedo, fuzz)))
+                        world.register_material(Box::new(Metal::from_color(albedo, fuzz)))
                     } else {
                         world.register_material(Box::new(Dialectric::new(1.5)))
                     };
@@ -90,14 +95,14 @@ impl Scene for BouncingSpheresScene {
         )));
 
         let material3 =
-            world.register_material(Box::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)));
+            world.register_material(Box::new(Metal::from_color(Color::new(0.7, 0.6, 0.5), 0.0)));
         objects.push(Arc::new(Sphere::stationary(
             Vec3(4.0, 1.0, 0.0),
             1.0,
             material3,
         )));
 
-        world.set_bvh(Bvh::new(objects.as_slice()));
+        world.set_objects(objects);
 
         world
     }
@@ -5,12 +5,14 @@ use crate::{
         material::{Dialectric, DiffuseLight, Lambertian, Metal},
         texture::CheckerTexture,
     },
-    objects::{bvh::Bvh, hittable::Hittable, quad::Quad, sphere::Sphere, world::World},
+    objects::{
+        bvh::BvhBuilder, hittable::Hittable, plane::Plane, quad::Quad, sphere::Sphere, world::World,
+    },
     util::{rand_f64, rand_vec3},
     vec3::{Color, Vec3},
 };
 
-use super::scene::{Scene, Settings};
+use super::scene::{AnimationSettings, Scene, Settings};
 
 pub struct BouncingSpheresWithLightScene;
 
@@ -19,6 +21,7 @@ impl Scene for BouncingSpheresWithLightScene {
         Settings {
             camera_eye: Vec3(13.0, 2.0, 3.0),
             camera_target: Vec3(0.0, 0.0, -1.0),
+            camera_up: Vec3(0.0, 1.0, 0.0),
             width: 1280,
             height: 720,
             fov: 20.0,
@@ -27,11 +30,14 @@ impl Scene for BouncingSpheresWithLightScene {
             samples: 100,
             max_bounces: 50,
             background_color: Some(Color::new(0.0, 0.0, 0.0)),
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            animation: AnimationSettings::default(),
         }
     }
 
-    fn world(&self) -> World {
-        let mut world = World::new();
+    fn world(&self, bvh_builder: BvhBuilder) -> World {
+        let mut world = World::new(bvh_builder);
         let checker_texture = Arc::new(CheckerTexture::from_colors(
             0.32,
             Color::new(0.2, 0.3, 0.1),
@@ -41,9 +47,10 @@ impl Scene for BouncingSpheresWithLightScene {
 
         let mut objects: Vec<Arc<dyn Hittable>> = vec![];
 
-        objects.push(Arc::new(Sphere::stationary(
-            Vec3(0.0, -1000.0, 0.0),
-            1000.0,
+        objects.push(Arc::new(Plane::new(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            1.0,
             ground_material,
         )));
 
@@ -63,7 +70,7 @@ impl Scene for BouncingSpheresWithLightScene {
                     } else if choose_mat < 0.95 {
                         let albedo = rand_vec3(0.5, 1.0);
                         let fuzz = rand_f64(0.0, 0.5);
-                        world.register_material(Box::new(Metal::new(albedo, fuzz)))
+                        world.register_material(Box::new(Metal::from_color(albedo, fuzz)))
                     } else {
                         world.register_material(Box::new(Dialectric::new(1.5)))
                     };
@@ -90,7 +97,7 @@ impl Scene for BouncingSpheresWithLightScene {
         )));
 
         let material3 =
-            world.register_material(Box::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0)));
+            world.register_material(Box::new(Metal::from_color(Color::new(0.7, 0.6, 0.5), 0.0)));
         objects.push(Arc::new(Sphere::stationary(
             Vec3(4.0, 1.0, 0.0),
             1.0,
@@ -107,7 +114,7 @@ impl Scene for BouncingSpheresWithLightScene {
             diffuse_light,
         )));
 
-        world.set_bvh(Bvh::new(objects.as_slice()));
+        world.set_objects(objects);
 
         world
     }
@@ -2,11 +2,11 @@ use std::sync::Arc;
 
 use crate::{
     materials::{material::Lambertian, texture::CheckerTexture},
-    objects::{bvh::Bvh, hittable::Hittable, sphere::Sphere, world::World},
+    objects::{bvh::BvhBuilder, hittable::Hittable, sphere::Sphere, world::World},
     vec3::{Color, Vec3},
 };
 
-use super::scene::{Scene, Settings};
+use super::scene::{AnimationSettings, Scene, Settings};
 
 pub struct CheckeredSpheresScene;
 
@@ -15,6 +15,7 @@ impl Scene for CheckeredSpheresScene {
         Settings {
             camera_eye: Vec3(13.0, 2.0, 3.0),
             camera_target: Vec3(0.0, 0.0, 0.0),
+            camera_up: Vec3(0.0, 1.0, 0.0),
             width: 1280,
             height: 720,
             fov: 20.0,
@@ -23,11 +24,14 @@ impl Scene for CheckeredSpheresScene {
             samples: 100,
             max_bounces: 50,
             background_color: None,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            animation: AnimationSettings::default(),
         }
     }
 
-    fn world(&self) -> World {
-        let mut world = World::new();
+    fn world(&self, bvh_builder: BvhBuilder) -> World {
+        let mut world = World::new(bvh_builder);
 
         let mut objects: Vec<Arc<dyn Hittable>> = vec![];
 
@@ -49,7 +53,7 @@ impl Scene for CheckeredSpheresScene {
             material,
         )));
 
-        world.set_bvh(Bvh::new(objects.as_slice()));
+        world.set_objects(objects);
 
         world
     }
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use crate::{
     materials::material::{DiffuseLight, Lambertian},
     objects::{
-        bvh::Bvh,
+        bvh::BvhBuilder,
         cube::cube,
         hittable::Hittable,
         quad::Quad,
@@ -13,7 +13,7 @@ use crate::{
     vec3::{Color, Vec3},
 };
 
-use super::scene::{Scene, Settings};
+use super::scene::{AnimationSettings, Scene, Settings};
 
 pub struct CornellBoxScene;
 
@@ -22,6 +22,7 @@ impl Scene for CornellBoxScene {
         Settings {
             camera_eye: Vec3(278.0, 278.0, -800.0),
             camera_target: Vec3(278.0, 278.0, 0.0),
+            camera_up: Vec3(0.0, 1.0, 0.0),
             width: 720,
             height: 720,
             fov: 38.0,
@@ -30,11 +31,14 @@ impl Scene for CornellBoxScene {
             samples: 500,
             max_bounces: 50,
             background_color: Some(Color::new(0.0, 0.0, 0.0)),
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            animation: AnimationSettings::default(),
         }
     }
 
-    fn world(&self) -> World {
-        let mut world = World::new();
+    fn world(&self, bvh_builder: BvhBuilder) -> World {
+        let mut world = World::new(bvh_builder);
 
         let mut objects: Vec<Arc<dyn Hittable>> = vec![];
 
@@ -102,7 +106,7 @@ impl Scene for CornellBoxScene {
             objects.push(cube);
         }
 
-        world.set_bvh(Bvh::new(objects.as_slice()));
+        world.set_objects(objects);
 
         world
     }
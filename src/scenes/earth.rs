@@ -2,11 +2,11 @@ use std::sync::Arc;
 
 use crate::{
     materials::{material::Lambertian, texture::ImageTexture},
-    objects::{bvh::Bvh, hittable::Hittable, sphere::Sphere, world::World},
+    objects::{bvh::BvhBuilder, hittable::Hittable, sphere::Sphere, world::World},
     vec3::Vec3,
 };
 
-use super::scene::{Scene, Settings};
+use super::scene::{AnimationSettings, Scene, Settings};
 
 pub struct EarthScene;
 
@@ -15,6 +15,7 @@ impl Scene for EarthScene {
         Settings {
             camera_eye: Vec3(0.0, 0.0, 12.0),
             camera_target: Vec3(0.0, 0.0, 0.0),
+            camera_up: Vec3(0.0, 1.0, 0.0),
             width: 1280,
             height: 720,
             fov: 20.0,
@@ -23,16 +24,18 @@ impl Scene for EarthScene {
             samples: 100,
             max_bounces: 50,
             background_color: None,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            animation: AnimationSettings::default(),
         }
     }
 
-    fn world(&self) -> World {
-        let mut world = World::new();
+    fn world(&self, bvh_builder: BvhBuilder) -> World {
+        let mut world = World::new(bvh_builder);
 
         let mut objects: Vec<Arc<dyn Hittable>> = vec![];
 
-        let earthmap = image::open("./assets/earthmap.jpg").unwrap().into_rgb8();
-        let img_texture = Arc::new(ImageTexture::new(earthmap));
+        let img_texture = Arc::new(ImageTexture::open("./assets/earthmap.jpg").unwrap());
         let material = world.register_material(Box::new(Lambertian::new(img_texture)));
 
         objects.push(Arc::new(Sphere::stationary(
@@ -41,7 +44,7 @@ impl Scene for EarthScene {
             material,
         )));
 
-        world.set_bvh(Bvh::new(objects.as_slice()));
+        world.set_objects(objects);
 
         world
     }
@@ -2,11 +2,11 @@ use std::sync::Arc;
 
 use crate::{
     materials::material::{DiffuseLight, Lambertian},
-    objects::{bvh::Bvh, hittable::Hittable, quad::Quad, world::World},
+    objects::{bvh::BvhBuilder, hittable::Hittable, quad::Quad, world::World},
     vec3::{Color, Vec3},
 };
 
-use super::scene::{Scene, Settings};
+use super::scene::{AnimationSettings, Scene, Settings};
 
 pub struct EmptyCornellBoxScene;
 
@@ -15,6 +15,7 @@ impl Scene for EmptyCornellBoxScene {
         Settings {
             camera_eye: Vec3(278.0, 278.0, -800.0),
             camera_target: Vec3(278.0, 278.0, 0.0),
+            camera_up: Vec3(0.0, 1.0, 0.0),
             width: 720,
             height: 720,
             fov: 38.0,
@@ -23,11 +24,14 @@ impl Scene for EmptyCornellBoxScene {
             samples: 500,
             max_bounces: 50,
             background_color: Some(Color::new(0.0, 0.0, 0.0)),
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            animation: AnimationSettings::default(),
         }
     }
 
-    fn world(&self) -> World {
-        let mut world = World::new();
+    fn world(&self, bvh_builder: BvhBuilder) -> World {
+        let mut world = World::new(bvh_builder);
 
         let mut objects: Vec<Arc<dyn Hittable>> = vec![];
 
@@ -81,7 +85,7 @@ impl Scene for EmptyCornellBoxScene {
             white,
         )));
 
-        world.set_bvh(Bvh::new(objects.as_slice()));
+        world.set_objects(objects);
 
         world
     }
@@ -5,21 +5,23 @@ use crate::{
         material::{Dialectric, DiffuseLight, Isotropic, Lambertian, Metal},
         texture::ImageTexture,
     },
+    matrix::Matrix4,
     objects::{
-        bvh::Bvh,
+        bvh::BvhBuilder,
         constant_volume::ConstantVolume,
         cube::cube,
         hittable::Hittable,
+        instance::Instance,
         quad::Quad,
         sphere::Sphere,
         transform::{RotateY, Translate},
         world::World,
     },
-    util::{rand_f64, rand_vec3},
+    util::{deg2rad, rand_f64, rand_vec3},
     vec3::{Color, Vec3},
 };
 
-use super::scene::{Scene, Settings};
+use super::scene::{AnimationSettings, Scene, Settings};
 
 pub struct EverythingScene;
 
@@ -28,6 +30,7 @@ impl Scene for EverythingScene {
         Settings {
             camera_eye: Vec3(478.0, 278.0, -600.0),
             camera_target: Vec3(278.0, 278.0, 0.0),
+            camera_up: Vec3(0.0, 1.0, 0.0),
             width: 720,
             height: 720,
             fov: 40.0,
@@ -36,17 +39,23 @@ impl Scene for EverythingScene {
             samples: 500,
             max_bounces: 50,
             background_color: Some(Color::new(0.0, 0.0, 0.0)),
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            animation: AnimationSettings::default(),
         }
     }
 
-    fn world(&self) -> World {
-        let mut world = World::new();
+    fn world(&self, bvh_builder: BvhBuilder) -> World {
+        let mut world = World::new(bvh_builder);
         let mut objects: Vec<Arc<dyn Hittable>> = vec![];
 
         let ground = world.register_material(Box::new(Lambertian::from_color(Color::new(
             0.48, 0.83, 0.53,
         ))));
 
+        // The ground boxes are instances of a single unit cube, scaled to their size.
+        let unit_cube: Arc<dyn Hittable> =
+            Arc::new(cube(Vec3(0.0, 0.0, 0.0), Vec3(1.0, 1.0, 1.0), ground));
         let mut boxes: Vec<Arc<dyn Hittable>> = vec![];
         let boxes_per_side = 20;
         for i in 0..boxes_per_side {
@@ -57,14 +66,14 @@ impl Scene for EverythingScene {
                 let z0 = -1000.0 + (j as f64) * w;
                 let y0 = 0.0;
 
-                let x1 = x0 + w;
                 let y1 = rand_f64(1.0, 101.0);
-                let z1 = z0 + w;
 
-                boxes.push(Arc::new(cube(Vec3(x0, y0, z0), Vec3(x1, y1, z1), ground)));
+                let transform =
+                    Matrix4::translation(Vec3(x0, y0, z0)) * Matrix4::scaling(Vec3(w, y1 - y0, w));
+                boxes.push(Arc::new(Instance::new(Arc::clone(&unit_cube), transform)));
             }
         }
-        objects.push(Arc::new(Bvh::new(&boxes)));
+        objects.push(Arc::new(world.object_bvh(&boxes)));
 
         let light = world.register_material(Box::new(DiffuseLight::from_color(Color::new(
             7.0, 7.0, 7.0,
@@ -95,7 +104,7 @@ impl Scene for EverythingScene {
         objects.push(Arc::new(Sphere::stationary(
             Vec3(0.0, 150.0, 145.0),
             50.0,
-            world.register_material(Box::new(Metal::new(Color::new(0.8, 0.8, 0.9), 1.0))),
+            world.register_material(Box::new(Metal::from_color(Color::new(0.8, 0.8, 0.9), 1.0))),
         )));
 
         let boundary = Arc::new(Sphere::stationary(
@@ -120,9 +129,8 @@ impl Scene for EverythingScene {
             world.register_material(Box::new(Isotropic::from_color(Color::new(1.0, 1.0, 1.0)))),
         )));
 
-        let earth_image = image::open("./assets/earthmap.jpg").unwrap().into_rgb8();
         let earth_material = world.register_material(Box::new(Lambertian::new(Arc::new(
-            ImageTexture::new(earth_image),
+            ImageTexture::open("./assets/earthmap.jpg").unwrap(),
         ))));
         objects.push(Arc::new(Sphere::stationary(
             Vec3(400.0, 200.0, 400.0),
@@ -144,10 +152,13 @@ impl Scene for EverythingScene {
 
         objects.push(Arc::new(Translate::new(
             Vec3(-100.0, 270.0, 395.0),
-            Arc::new(RotateY::new(15.0, Arc::new(Bvh::new(&boxes2)))),
+            Arc::new(RotateY::new(
+                deg2rad(15.0),
+                Arc::new(world.object_bvh(&boxes2)),
+            )),
         )));
 
-        world.set_bvh(Bvh::new(&objects));
+        world.set_objects(objects);
         world
     }
 }
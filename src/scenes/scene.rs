@@ -1,8 +1,13 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    objects::world::World,
+    animation::{interpolate, FrameRange, Interpolation},
+    objects::{bvh::BvhBuilder, world::World},
     vec3::{Color, Vec3},
 };
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Settings {
     /// Position of the camera.
     pub camera_eye: Vec3,
@@ -10,6 +15,9 @@ pub struct Settings {
     /// Target position for the camera.
     pub camera_target: Vec3,
 
+    /// Direction that is up for the camera.
+    pub camera_up: Vec3,
+
     /// Width of the output image.
     pub width: u32,
 
@@ -32,10 +40,137 @@ pub struct Settings {
     pub max_bounces: u32,
 
     /// Background color to use for the render, None to use a basic sky-like gradient
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub background_color: Option<Color>,
+
+    /// Time at which the shutter of the camera opens.
+    pub shutter_open: f64,
+
+    /// Time at which the shutter of the camera closes. Objects moving while the
+    /// shutter is open are blurred along their motion.
+    pub shutter_close: f64,
+
+    /// Keyframes moving the camera over the frames of an animation.
+    #[serde(skip_serializing_if = "AnimationSettings::is_still")]
+    pub animation: AnimationSettings,
+}
+
+impl Settings {
+    /// Returns the settings with the camera placed as the animation places it at `time`.
+    pub fn at_time(&self, time: f64) -> Settings {
+        let animation = &self.animation;
+        let mut settings = self.clone();
+
+        let eye = animation.track(|keyframe| keyframe.eye);
+        if !eye.is_empty() {
+            settings.camera_eye = interpolate(&eye, time, animation.interpolation);
+        }
+        let target = animation.track(|keyframe| keyframe.target);
+        if !target.is_empty() {
+            settings.camera_target = interpolate(&target, time, animation.interpolation);
+        }
+        let fov = animation.track(|keyframe| keyframe.fov);
+        if !fov.is_empty() {
+            settings.fov = interpolate(&fov, time, animation.interpolation);
+        }
+        settings
+    }
+}
+
+/// Describes an animation rendered as a sequence of frames.
+///
+/// Time is measured in frames: frame `n` is exposed from `n + shutter_open` to
+/// `n + shutter_close`, so the keyframes of the camera and of animated objects refer to
+/// frame numbers. A still image is frame 0.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnimationSettings {
+    /// Number of frames rendered by default, starting at frame 1. Zero for a still image.
+    pub frames: u32,
+
+    /// How the camera is interpolated between keyframes.
+    pub interpolation: Interpolation,
+
+    /// Keyframes of the camera at increasing times. Each property of the camera is
+    /// interpolated between the keyframes that set it.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub camera: Vec<CameraKeyframe>,
+}
+
+impl AnimationSettings {
+    /// Returns the keyframes that set one property of the camera.
+    fn track<T>(&self, property: impl Fn(&CameraKeyframe) -> Option<T>) -> Vec<(f64, T)> {
+        self.camera
+            .iter()
+            .filter_map(|keyframe| Some((keyframe.time, property(keyframe)?)))
+            .collect()
+    }
+
+    fn is_still(&self) -> bool {
+        self.frames == 0 && self.camera.is_empty()
+    }
+
+    /// Returns the frames to render when none are given on the command line.
+    pub fn frame_range(&self) -> Option<FrameRange> {
+        (self.frames > 0).then_some(FrameRange {
+            first: 1,
+            last: self.frames,
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for pair in self.camera.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            if b.time <= a.time {
+                return Err(format!(
+                    "camera keyframe times must be increasing, but {} follows {}",
+                    b.time, a.time
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Position, target and field of view of the camera at a point in time.
+/// Properties that are left out are not changed by the keyframe.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraKeyframe {
+    pub time: f64,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eye: Option<Vec3>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<Vec3>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fov: Option<f64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            camera_eye: Vec3(13.0, 2.0, 3.0),
+            camera_target: Vec3(0.0, 0.0, 0.0),
+            camera_up: Vec3(0.0, 1.0, 0.0),
+            width: 1280,
+            height: 720,
+            fov: 20.0,
+            focus_distance: 10.0,
+            defocus_angle: 0.6,
+            samples: 100,
+            max_bounces: 50,
+            background_color: None,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            animation: AnimationSettings::default(),
+        }
+    }
 }
 
 pub trait Scene {
     fn default_settings(&self) -> Settings;
-    fn world(&self) -> World;
+    fn world(&self, bvh_builder: BvhBuilder) -> World;
 }
@@ -2,11 +2,13 @@ use std::sync::Arc;
 
 use crate::{
     materials::material::{DiffuseLight, Lambertian},
-    objects::{bvh::Bvh, hittable::Hittable, quad::Quad, sphere::Sphere, world::World},
+    objects::{
+        bvh::BvhBuilder, hittable::Hittable, plane::Plane, quad::Quad, sphere::Sphere, world::World,
+    },
     vec3::{Color, Vec3},
 };
 
-use super::scene::{Scene, Settings};
+use super::scene::{AnimationSettings, Scene, Settings};
 
 pub struct SimpleLightScene;
 
@@ -15,6 +17,7 @@ impl Scene for SimpleLightScene {
         Settings {
             camera_eye: Vec3(26.0, 3.0, 6.0),
             camera_target: Vec3(0.0, 2.0, 0.0),
+            camera_up: Vec3(0.0, 1.0, 0.0),
             width: 1280,
             height: 720,
             fov: 20.0,
@@ -23,19 +26,23 @@ impl Scene for SimpleLightScene {
             samples: 1000,
             max_bounces: 10,
             background_color: Some(Color::new(0.0, 0.0, 0.0)),
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            animation: AnimationSettings::default(),
         }
     }
 
-    fn world(&self) -> World {
-        let mut world = World::new();
+    fn world(&self, bvh_builder: BvhBuilder) -> World {
+        let mut world = World::new(bvh_builder);
 
         let mut objects: Vec<Arc<dyn Hittable>> = vec![];
 
         let lambert =
             world.register_material(Box::new(Lambertian::from_color(Color::new(1.0, 0.5, 0.5))));
-        objects.push(Arc::new(Sphere::stationary(
-            Vec3(0.0, -1000.0, 0.0),
-            1000.0,
+        objects.push(Arc::new(Plane::new(
+            Vec3(0.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            1.0,
             lambert,
         )));
         objects.push(Arc::new(Sphere::stationary(
@@ -54,7 +61,7 @@ impl Scene for SimpleLightScene {
             difflight,
         )));
 
-        world.set_bvh(Bvh::new(objects.as_slice()));
+        world.set_objects(objects);
 
         world
     }
@@ -0,0 +1,50 @@
+//! Reproducible random input for the tests that compare an accelerated or transformed object with
+//! a brute-force reference.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{ray::Ray, vec3::Vec3};
+
+/// A seeded random number generator, so that a failing comparison fails on every run.
+pub struct TestRng(StdRng);
+
+impl TestRng {
+    pub fn new(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+
+    pub fn f64(&mut self, min: f64, max: f64) -> f64 {
+        self.0.gen_range(min..max)
+    }
+
+    pub fn vec3(&mut self, min: f64, max: f64) -> Vec3 {
+        Vec3(self.f64(min, max), self.f64(min, max), self.f64(min, max))
+    }
+
+    pub fn unit_vec3(&mut self) -> Vec3 {
+        // Rejection sampling keeps the directions uniform over the sphere.
+        loop {
+            let v = self.vec3(-1.0, 1.0);
+            let length_squared = v.length_squared();
+            if length_squared > 1e-6 && length_squared <= 1.0 {
+                return v / length_squared.sqrt();
+            }
+        }
+    }
+
+    /// A ray starting between `distance.0` and `distance.1` away from `center`, aimed at a random
+    /// point of the cube with half-size `spread` around it.
+    pub fn ray_towards(&mut self, center: Vec3, spread: f64, distance: (f64, f64)) -> Ray {
+        let origin = if distance.0 < distance.1 {
+            center + self.unit_vec3() * self.f64(distance.0, distance.1)
+        } else {
+            center + self.unit_vec3() * distance.0
+        };
+        let target = if spread > 0.0 {
+            center + self.vec3(-spread, spread)
+        } else {
+            center
+        };
+        Ray::new(origin, target - origin, 0.0)
+    }
+}
@@ -1,175 +1,176 @@
-use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub};
-
-use image::Rgb;
-
-#[derive(Debug, Default, Copy, Clone)]
-pub struct Vec3(pub f64, pub f64, pub f64);
-
-pub type Color = Vec3;
-
-impl Vec3 {
-    pub fn new(x: f64, y: f64, z: f64) -> Self {
-        Self(x, y, z)
-    }
-
-    pub fn x(self) -> f64 {
-        self.0
-    }
-
-    pub fn y(self) -> f64 {
-        self.1
-    }
-
-    pub fn z(self) -> f64 {
-        self.2
-    }
-
-    pub fn r(self) -> f64 {
-        self.0
-    }
-
-    pub fn g(self) -> f64 {
-        self.1
-    }
-
-    pub fn b(self) -> f64 {
-        self.2
-    }
-
-    pub fn length_squared(self) -> f64 {
-        self.dot(self)
-    }
-
-    pub fn length(self) -> f64 {
-        self.length_squared().sqrt()
-    }
-
-    pub fn dot(self, rhs: Vec3) -> f64 {
-        self.0 * rhs.0 + self.1 * rhs.1 + self.2 * rhs.2
-    }
-
-    pub fn cross(self, rhs: Vec3) -> Vec3 {
-        Vec3(
-            self.y() * rhs.z() - self.z() * rhs.y(),
-            self.z() * rhs.x() - self.x() * rhs.z(),
-            self.x() * rhs.y() - self.y() * rhs.x(),
-        )
-    }
-
-    pub fn normalize(self) -> Self {
-        self / self.length()
-    }
-
-    pub fn near_zero(self) -> bool {
-        let e = f64::EPSILON;
-        self.0.abs() < e && self.1.abs() < e && self.2.abs() < e
-    }
-}
-
-impl Index<usize> for Vec3 {
-    type Output = f64;
-
-    fn index(&self, index: usize) -> &Self::Output {
-        match index {
-            0 => &self.0,
-            1 => &self.1,
-            2 => &self.2,
-            _ => panic!("invalid Vec3 index"),
-        }
-    }
-}
-
-impl IndexMut<usize> for Vec3 {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        match index {
-            0 => &mut self.0,
-            1 => &mut self.1,
-            2 => &mut self.2,
-            _ => panic!("invalid Vec3 index"),
-        }
-    }
-}
-
-impl Neg for Vec3 {
-    type Output = Vec3;
-
-    fn neg(self) -> Self::Output {
-        Vec3(-self.0, -self.1, -self.2)
-    }
-}
-
-impl Add<Vec3> for Vec3 {
-    type Output = Vec3;
-
-    fn add(self, rhs: Vec3) -> Self::Output {
-        Vec3(self.0 + rhs.0, self.1 + rhs.1, self.2 + rhs.2)
-    }
-}
-
-impl AddAssign<Vec3> for Vec3 {
-    fn add_assign(&mut self, rhs: Vec3) {
-        *self = *self + rhs;
-    }
-}
-
-impl Sub<Vec3> for Vec3 {
-    type Output = Vec3;
-
-    fn sub(self, rhs: Vec3) -> Self::Output {
-        Vec3(self.0 - rhs.0, self.1 - rhs.1, self.2 - rhs.2)
-    }
-}
-
-impl Mul<Vec3> for Vec3 {
-    type Output = Vec3;
-
-    fn mul(self, rhs: Vec3) -> Self::Output {
-        Vec3(self.0 * rhs.0, self.1 * rhs.1, self.2 * rhs.2)
-    }
-}
-
-impl Mul<f64> for Vec3 {
-    type Output = Vec3;
-
-    fn mul(self, rhs: f64) -> Self::Output {
-        Vec3(self.0 * rhs, self.1 * rhs, self.2 * rhs)
-    }
-}
-
-impl MulAssign<f64> for Vec3 {
-    fn mul_assign(&mut self, rhs: f64) {
-        *self = *self * rhs;
-    }
-}
-
-impl Mul<Vec3> for f64 {
-    type Output = Vec3;
-
-    fn mul(self, rhs: Vec3) -> Self::Output {
-        Vec3(rhs.0 * self, rhs.1 * self, rhs.2 * self)
-    }
-}
-
-impl Div<f64> for Vec3 {
-    type Output = Vec3;
-
-    fn div(self, rhs: f64) -> Self::Output {
-        Vec3(self.0 / rhs, self.1 / rhs, self.2 / rhs)
-    }
-}
-
-impl DivAssign<f64> for Vec3 {
-    fn div_assign(&mut self, rhs: f64) {
-        *self = *self / rhs;
-    }
-}
-
-impl From<Color> for Rgb<u8> {
-    fn from(val: Color) -> Self {
-        Rgb([
-            (val.0 * 255.0) as _,
-            (val.1 * 255.0) as _,
-            (val.2 * 255.0) as _,
-        ])
-    }
-}
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub};
+
+use image::Rgb;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
+pub struct Vec3(pub f64, pub f64, pub f64);
+
+pub type Color = Vec3;
+
+impl Vec3 {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self(x, y, z)
+    }
+
+    pub fn x(self) -> f64 {
+        self.0
+    }
+
+    pub fn y(self) -> f64 {
+        self.1
+    }
+
+    pub fn z(self) -> f64 {
+        self.2
+    }
+
+    pub fn r(self) -> f64 {
+        self.0
+    }
+
+    pub fn g(self) -> f64 {
+        self.1
+    }
+
+    pub fn b(self) -> f64 {
+        self.2
+    }
+
+    pub fn length_squared(self) -> f64 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f64 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn dot(self, rhs: Vec3) -> f64 {
+        self.0 * rhs.0 + self.1 * rhs.1 + self.2 * rhs.2
+    }
+
+    pub fn cross(self, rhs: Vec3) -> Vec3 {
+        Vec3(
+            self.y() * rhs.z() - self.z() * rhs.y(),
+            self.z() * rhs.x() - self.x() * rhs.z(),
+            self.x() * rhs.y() - self.y() * rhs.x(),
+        )
+    }
+
+    pub fn normalize(self) -> Self {
+        self / self.length()
+    }
+
+    pub fn near_zero(self) -> bool {
+        let e = f64::EPSILON;
+        self.0.abs() < e && self.1.abs() < e && self.2.abs() < e
+    }
+}
+
+impl Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.0,
+            1 => &self.1,
+            2 => &self.2,
+            _ => panic!("invalid Vec3 index"),
+        }
+    }
+}
+
+impl IndexMut<usize> for Vec3 {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        match index {
+            0 => &mut self.0,
+            1 => &mut self.1,
+            2 => &mut self.2,
+            _ => panic!("invalid Vec3 index"),
+        }
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Vec3;
+
+    fn neg(self) -> Self::Output {
+        Vec3(-self.0, -self.1, -self.2)
+    }
+}
+
+impl Add<Vec3> for Vec3 {
+    type Output = Vec3;
+
+    fn add(self, rhs: Vec3) -> Self::Output {
+        Vec3(self.0 + rhs.0, self.1 + rhs.1, self.2 + rhs.2)
+    }
+}
+
+impl AddAssign<Vec3> for Vec3 {
+    fn add_assign(&mut self, rhs: Vec3) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Vec3> for Vec3 {
+    type Output = Vec3;
+
+    fn sub(self, rhs: Vec3) -> Self::Output {
+        Vec3(self.0 - rhs.0, self.1 - rhs.1, self.2 - rhs.2)
+    }
+}
+
+impl Mul<Vec3> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Self::Output {
+        Vec3(self.0 * rhs.0, self.1 * rhs.1, self.2 * rhs.2)
+    }
+}
+
+impl Mul<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Vec3(self.0 * rhs, self.1 * rhs, self.2 * rhs)
+    }
+}
+
+impl MulAssign<f64> for Vec3 {
+    fn mul_assign(&mut self, rhs: f64) {
+        *self = *self * rhs;
+    }
+}
+
+impl Mul<Vec3> for f64 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Self::Output {
+        Vec3(rhs.0 * self, rhs.1 * self, rhs.2 * self)
+    }
+}
+
+impl Div<f64> for Vec3 {
+    type Output = Vec3;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Vec3(self.0 / rhs, self.1 / rhs, self.2 / rhs)
+    }
+}
+
+impl DivAssign<f64> for Vec3 {
+    fn div_assign(&mut self, rhs: f64) {
+        *self = *self / rhs;
+    }
+}
+
+impl From<Color> for Rgb<u8> {
+    fn from(val: Color) -> Self {
+        Rgb([
+            (val.0 * 255.0) as _,
+            (val.1 * 255.0) as _,
+            (val.2 * 255.0) as _,
+        ])
+    }
+}